@@ -0,0 +1,251 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::str::FromStr;
+
+/// Number of decimal places an amount can carry
+pub const DECIMALS: u32 = 8;
+/// Base units in one whole L1 (or one whole launched token)
+pub const BASE_UNITS_PER_L1: u128 = 10u128.pow(DECIMALS);
+
+/// Fixed-point ledger amount stored as an integer number of base units
+///
+/// Serialized as a decimal string ("12.5") so no precision is lost in JSON;
+/// deserializes from either a decimal string or a JSON number.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(u128);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+    pub const MAX: Amount = Amount(u128::MAX);
+
+    pub const fn from_base_units(units: u128) -> Self {
+        Amount(units)
+    }
+
+    /// Whole number of L1
+    pub const fn from_l1(whole: u64) -> Self {
+        Amount(whole as u128 * BASE_UNITS_PER_L1)
+    }
+
+    pub const fn base_units(self) -> u128 {
+        self.0
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0.checked_sub(other.0).map(Amount)
+    }
+
+    pub fn checked_mul(self, factor: u128) -> Option<Amount> {
+        self.0.checked_mul(factor).map(Amount)
+    }
+
+    pub fn checked_div(self, divisor: u128) -> Option<Amount> {
+        self.0.checked_div(divisor).map(Amount)
+    }
+
+    /// `self * numerator / denominator`, rounded down
+    pub fn mul_div(self, numerator: u128, denominator: u128) -> Option<Amount> {
+        self.0.checked_mul(numerator)?.checked_div(denominator).map(Amount)
+    }
+
+    pub fn saturating_add(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_sub(other.0))
+    }
+
+    /// Checked addition with the error message used across the ledger
+    pub fn try_add(self, other: Amount) -> Result<Amount, String> {
+        self.checked_add(other)
+            .ok_or_else(|| format!("Amount overflow adding {} to {}", other, self))
+    }
+
+    /// Checked subtraction with the error message used across the ledger
+    pub fn try_sub(self, other: Amount) -> Result<Amount, String> {
+        self.checked_sub(other)
+            .ok_or_else(|| format!("Amount underflow subtracting {} from {}", other, self))
+    }
+
+    /// Convert a float, truncating anything below one base unit
+    pub fn from_f64(value: f64) -> Result<Amount, String> {
+        if !value.is_finite() || value < 0.0 {
+            return Err(format!("Invalid amount: {}", value));
+        }
+        // Go through the shortest decimal representation so 0.1 becomes exactly 0.1
+        let text = format!("{}", value);
+        match text.parse() {
+            Ok(amount) => Ok(amount),
+            Err(_) => {
+                let units = value * BASE_UNITS_PER_L1 as f64;
+                if units >= u128::MAX as f64 {
+                    return Err(format!("Amount too large: {}", value));
+                }
+                Ok(Amount(units as u128))
+            }
+        }
+    }
+
+    /// Lossy conversion for prices, ratios and other display-only maths
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / BASE_UNITS_PER_L1 as f64
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let whole = self.0 / BASE_UNITS_PER_L1;
+        let fraction = self.0 % BASE_UNITS_PER_L1;
+        if fraction == 0 {
+            return write!(f, "{}", whole);
+        }
+        let digits = format!("{:0width$}", fraction, width = DECIMALS as usize);
+        write!(f, "{}.{}", whole, digits.trim_end_matches('0'))
+    }
+}
+
+impl FromStr for Amount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+
+        if whole.is_empty() && fraction.is_empty() {
+            return Err("Amount is empty".to_string());
+        }
+        if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(format!("Invalid amount: {}", s));
+        }
+        if fraction.len() > DECIMALS as usize {
+            return Err(format!("Amount has more than {} decimal places: {}", DECIMALS, s));
+        }
+
+        let whole: u128 = if whole.is_empty() {
+            0
+        } else {
+            whole.parse().map_err(|_| format!("Amount too large: {}", s))?
+        };
+        let fraction: u128 = if fraction.is_empty() {
+            0
+        } else {
+            format!("{:0<width$}", fraction, width = DECIMALS as usize)
+                .parse()
+                .map_err(|_| format!("Invalid amount: {}", s))?
+        };
+
+        whole.checked_mul(BASE_UNITS_PER_L1)
+            .and_then(|units| units.checked_add(fraction))
+            .map(Amount)
+            .ok_or_else(|| format!("Amount too large: {}", s))
+    }
+}
+
+/// Saturates at `Amount::MAX`; used for reporting totals, not for balances
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Amount {
+        iter.fold(Amount::ZERO, Amount::saturating_add)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+struct AmountVisitor;
+
+impl Visitor<'_> for AmountVisitor {
+    type Value = Amount;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a non-negative decimal amount as a string or number")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Amount, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Amount, E> {
+        (v as u128).checked_mul(BASE_UNITS_PER_L1)
+            .map(Amount)
+            .ok_or_else(|| E::custom(format!("Amount too large: {}", v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Amount, E> {
+        u64::try_from(v)
+            .map_err(|_| E::custom(format!("Amount cannot be negative: {}", v)))
+            .and_then(|v| self.visit_u64(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Amount, E> {
+        Amount::from_f64(v).map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!("12.5".parse::<Amount>().unwrap(), Amount::from_base_units(1_250_000_000));
+        assert_eq!("0.00000001".parse::<Amount>().unwrap(), Amount::from_base_units(1));
+        assert_eq!(".5".parse::<Amount>().unwrap().to_string(), "0.5");
+        assert_eq!(Amount::from_l1(10).to_string(), "10");
+        assert_eq!(Amount::from_base_units(1).to_string(), "0.00000001");
+
+        assert!("0.000000001".parse::<Amount>().is_err());
+        assert!("-1".parse::<Amount>().is_err());
+        assert!("1e5".parse::<Amount>().is_err());
+        assert!("".parse::<Amount>().is_err());
+    }
+
+    #[test]
+    fn test_no_float_drift() {
+        let dime: Amount = "0.1".parse().unwrap();
+        let mut total = Amount::ZERO;
+        for _ in 0..10 {
+            total = total.try_add(dime).unwrap();
+        }
+        assert_eq!(total, Amount::from_l1(1));
+        assert_eq!(Amount::from_f64(0.1).unwrap(), dime);
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        assert!(Amount::from_l1(1).try_sub(Amount::from_l1(2)).is_err());
+        assert!(Amount::MAX.try_add(Amount::from_base_units(1)).is_err());
+        assert_eq!(Amount::from_l1(10).mul_div(3, 100).unwrap().to_string(), "0.3");
+    }
+
+    #[test]
+    fn test_json_accepts_strings_and_numbers() {
+        let from_str: Amount = serde_json::from_str("\"1.25\"").unwrap();
+        let from_float: Amount = serde_json::from_str("1.25").unwrap();
+        let from_int: Amount = serde_json::from_str("3").unwrap();
+
+        assert_eq!(from_str, from_float);
+        assert_eq!(from_int, Amount::from_l1(3));
+        assert_eq!(serde_json::to_string(&from_str).unwrap(), "\"1.25\"");
+        assert!(serde_json::from_str::<Amount>("-1").is_err());
+    }
+}
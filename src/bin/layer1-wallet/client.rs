@@ -0,0 +1,225 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+use layer1::amount::Amount;
+use layer1::crypto::MultisigSignature;
+use layer1::enhanced_transaction::{EnhancedTransaction, TransactionReceipt};
+use layer1::vesting::LockSchedule;
+
+/// Body of GET /balance/{address}
+#[derive(Debug, Deserialize)]
+pub struct Balance {
+    pub balance: Amount,
+    /// Part of the balance held back by time locks or vesting
+    #[serde(default)]
+    pub locked: Amount,
+}
+
+impl Balance {
+    pub fn spendable(&self) -> Amount {
+        self.balance.saturating_sub(self.locked)
+    }
+}
+
+/// Body of GET /rpc/account/{address}/nonce
+#[derive(Debug, Deserialize)]
+pub struct AccountNonce {
+    pub nonce: u64,
+    pub confirmed_nonce: u64,
+    pub queued_count: usize,
+}
+
+/// One entry of POST /rpc/accounts/activity
+#[derive(Debug, Deserialize)]
+pub struct AccountActivity {
+    pub address: String,
+    pub balance: Amount,
+    pub transaction_count: u32,
+}
+
+/// A transfer from a multisig account collecting signatures, as GET /rpc/multisig/proposals lists it
+#[derive(Debug, Deserialize)]
+pub struct Proposal {
+    pub id: String,
+    pub transaction: EnhancedTransaction,
+    pub threshold: usize,
+    pub signatures: Vec<MultisigSignature>,
+}
+
+/// One confirmation target of GET /rpc/fees/estimate
+#[derive(Debug, Deserialize)]
+pub struct FeeEstimate {
+    pub target_blocks: u64,
+    pub fee: Amount,
+}
+
+/// Body POST /rpc/transaction/enhanced expects
+#[derive(Serialize)]
+struct SubmitRequest<'a> {
+    from: &'a str,
+    to: &'a str,
+    amount: Amount,
+    fee: Amount,
+    message: Option<&'a str>,
+    timestamp: u64,
+    nonce: u64,
+    public_key: Option<&'a str>,
+    signature: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lock: Option<&'a LockSchedule>,
+}
+
+/// Blocking client for a node's HTTP API
+pub struct NodeClient {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl NodeClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(15)).build(),
+        }
+    }
+
+    pub fn balance(&self, address: &str) -> Result<Balance, String> {
+        self.get(&format!("/balance/{}", address), None)
+    }
+
+    pub fn account_nonce(&self, address: &str) -> Result<AccountNonce, String> {
+        self.get(&format!("/rpc/account/{}/nonce", address), None)
+    }
+
+    /// Balances and transaction counts for a batch of addresses, in the same order
+    pub fn activity(&self, addresses: &[String]) -> Result<Vec<AccountActivity>, String> {
+        let body = self.post("/rpc/accounts/activity", serde_json::json!({ "addresses": addresses }))?;
+        serde_json::from_value(body).map_err(|e| format!("Unexpected activity response: {}", e))
+    }
+
+    /// Fee the node recommends for confirmation within `target_blocks` blocks, or the nearest
+    /// target it quotes above that
+    pub fn recommended_fee(&self, target_blocks: u64) -> Result<Amount, String> {
+        let body: Value = self.get("/rpc/fees/estimate", None)?;
+        let estimates: Vec<FeeEstimate> = field(&body, "estimates")?;
+        estimates.iter()
+            .find(|estimate| estimate.target_blocks >= target_blocks)
+            .or(estimates.last())
+            .map(|estimate| estimate.fee)
+            .ok_or_else(|| "Node returned no fee estimates".to_string())
+    }
+
+    /// Send a signed transaction; returns the id the node assigned to it
+    pub fn submit(&self, tx: &EnhancedTransaction) -> Result<String, String> {
+        let request = SubmitRequest {
+            from: &tx.from,
+            to: &tx.to,
+            amount: tx.amount,
+            fee: tx.fee,
+            message: tx.data.as_deref(),
+            timestamp: tx.timestamp,
+            nonce: tx.nonce,
+            public_key: tx.public_key.as_deref(),
+            signature: &tx.signature,
+            lock: tx.lock.as_ref(),
+        };
+        let response = self.agent.post(&self.url("/rpc/transaction/enhanced"))
+            .send_json(&request)
+            .map_err(|e| self.request_error(e))?;
+        let body = read_body(response)?;
+        field(&body, "transaction_id")
+    }
+
+    /// Send a signed cancellation built by `EnhancedTransaction::cancellation`; returns its id
+    pub fn cancel(&self, tx: &EnhancedTransaction) -> Result<String, String> {
+        let body = self.post("/rpc/transaction/cancel", serde_json::json!({
+            "from": tx.from,
+            "fee": tx.fee,
+            "timestamp": tx.timestamp,
+            "nonce": tx.nonce,
+            "public_key": tx.public_key,
+            "signature": tx.signature,
+        }))?;
+        field(&body, "transaction_id")
+    }
+
+    /// Register an M-of-N account; returns its address
+    pub fn create_multisig(&self, threshold: usize, public_keys: &[String]) -> Result<String, String> {
+        let body = self.post("/rpc/multisig/create", serde_json::json!({
+            "threshold": threshold,
+            "public_keys": public_keys,
+        }))?;
+        field(&body, "address")
+    }
+
+    /// Open a transfer from a multisig account; the node picks its nonce and timestamp
+    pub fn propose(&self, from: &str, to: &str, amount: Amount, fee: Amount, message: Option<&str>) -> Result<Proposal, String> {
+        let body = self.post("/rpc/multisig/propose", serde_json::json!({
+            "from": from,
+            "to": to,
+            "amount": amount,
+            "fee": fee,
+            "message": message,
+        }))?;
+        serde_json::from_value(body).map_err(|e| format!("Unexpected proposal response: {}", e))
+    }
+
+    pub fn proposals(&self) -> Result<Vec<Proposal>, String> {
+        self.get("/rpc/multisig/proposals", None)
+    }
+
+    /// Add a member's signature; returns whether that met the threshold and submitted the transfer
+    pub fn sign_proposal(&self, proposal_id: &str, signature: &MultisigSignature) -> Result<(bool, String), String> {
+        let body = self.post("/rpc/multisig/sign", serde_json::json!({
+            "proposal_id": proposal_id,
+            "public_key": signature.public_key,
+            "signature": signature.signature,
+        }))?;
+        Ok((field(&body, "submitted")?, field(&body, "message")?))
+    }
+
+    pub fn receipt(&self, tx_id: &str) -> Result<TransactionReceipt, String> {
+        self.get("/rpc/transaction/receipt", Some(("x-tx-id", tx_id)))
+    }
+
+    fn get<T: DeserializeOwned>(&self, path: &str, header: Option<(&str, &str)>) -> Result<T, String> {
+        let mut request = self.agent.get(&self.url(path));
+        if let Some((name, value)) = header {
+            request = request.set(name, value);
+        }
+        let response = request.call().map_err(|e| self.request_error(e))?;
+        serde_json::from_value(read_body(response)?).map_err(|e| format!("Unexpected response from {}: {}", path, e))
+    }
+
+    fn post(&self, path: &str, body: Value) -> Result<Value, String> {
+        let response = self.agent.post(&self.url(path))
+            .send_json(body)
+            .map_err(|e| self.request_error(e))?;
+        read_body(response)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn request_error(&self, error: ureq::Error) -> String {
+        format!("Request to {} failed: {}", self.base_url, error)
+    }
+}
+
+// The API reports failures as {"success": false, "error": ...} with a 200 status
+fn read_body(response: ureq::Response) -> Result<Value, String> {
+    let body: Value = response.into_json().map_err(|e| format!("Invalid response body: {}", e))?;
+    if body.get("success") == Some(&Value::Bool(false)) {
+        let error = body.get("error").and_then(Value::as_str).unwrap_or("Request failed");
+        return Err(error.to_string());
+    }
+    Ok(body)
+}
+
+fn field<T: DeserializeOwned>(body: &Value, name: &str) -> Result<T, String> {
+    let value = body.get(name).ok_or_else(|| format!("Response has no {}", name))?;
+    serde_json::from_value(value.clone()).map_err(|e| format!("Invalid {} in response: {}", name, e))
+}
@@ -0,0 +1,478 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use layer1::crypto::{self, KeyPair};
+use layer1::hd::HdWallet;
+
+const KEY_FILE_VERSION: u32 = 1;
+const SEED_FILE_VERSION: u32 = 1;
+
+/// Argon2id settings a secret was sealed with, kept in the file so the defaults can be raised later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    /// Hex-encoded random salt
+    pub salt: String,
+}
+
+impl KdfParams {
+    /// Fresh salt with the OWASP-recommended Argon2id cost
+    pub fn generate() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+            salt: hex::encode(rand::random::<[u8; 16]>()),
+        }
+    }
+
+    fn derive_key(&self, password: &str) -> Result<[u8; 32], String> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| format!("Invalid key derivation settings: {}", e))?;
+        let salt = hex::decode(&self.salt).map_err(|_| "Key file salt is not valid hex".to_string())?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password.as_bytes(), &salt, &mut key)
+            .map_err(|e| format!("Key derivation failed: {}", e))?;
+        Ok(key)
+    }
+}
+
+/// A secret encrypted with ChaCha20-Poly1305 under a password-derived key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sealed {
+    pub kdf: KdfParams,
+    /// Hex-encoded cipher nonce
+    pub nonce: String,
+    /// Hex-encoded ciphertext and tag
+    pub ciphertext: String,
+}
+
+impl Sealed {
+    /// `context` is authenticated but not encrypted, tying the secret to the record that holds it
+    fn seal(secret: &[u8], context: &[u8], password: &str, kdf: KdfParams) -> Result<Self, String> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&kdf.derive_key(password)?));
+        let nonce = rand::random::<[u8; 12]>();
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: secret, aad: context })
+            .map_err(|_| "Failed to encrypt secret".to_string())?;
+        Ok(Self { kdf, nonce: hex::encode(nonce), ciphertext: hex::encode(ciphertext) })
+    }
+
+    /// None on a wrong password or a tampered record
+    fn open(&self, context: &[u8], password: &str) -> Result<Option<Vec<u8>>, String> {
+        let nonce = hex::decode(&self.nonce).ok().filter(|nonce| nonce.len() == 12)
+            .ok_or_else(|| "Sealed nonce is malformed".to_string())?;
+        let ciphertext = hex::decode(&self.ciphertext).map_err(|_| "Sealed ciphertext is not valid hex".to_string())?;
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.kdf.derive_key(password)?));
+        Ok(cipher.decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: context }).ok())
+    }
+}
+
+/// One account's secret key; the address is bound in as associated data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyFile {
+    pub version: u32,
+    pub name: String,
+    pub address: String,
+    pub public_key: String,
+    pub created_at: u64,
+    #[serde(flatten)]
+    pub sealed: Sealed,
+}
+
+impl KeyFile {
+    pub fn seal(name: &str, keys: &KeyPair, password: &str) -> Result<Self, String> {
+        Self::seal_with(name, keys, password, KdfParams::generate())
+    }
+
+    pub fn seal_with(name: &str, keys: &KeyPair, password: &str, kdf: KdfParams) -> Result<Self, String> {
+        validate_name(name)?;
+        let address = keys.address();
+        let secret = hex::decode(keys.secret_key_hex()).map_err(|e| e.to_string())?;
+        Ok(Self {
+            version: KEY_FILE_VERSION,
+            name: name.to_string(),
+            public_key: keys.public_key_hex(),
+            created_at: now(),
+            sealed: Sealed::seal(&secret, address.as_bytes(), password, kdf)?,
+            address,
+        })
+    }
+
+    /// Decrypt the secret key; fails on a wrong password or an edited file
+    pub fn unlock(&self, password: &str) -> Result<KeyPair, String> {
+        if self.version != KEY_FILE_VERSION {
+            return Err(format!("Unsupported key file version {}", self.version));
+        }
+        let secret = self.sealed.open(self.address.as_bytes(), password)?
+            .ok_or_else(|| format!("Wrong password for {}", self.name))?;
+        let keys = KeyPair::from_secret_hex(&hex::encode(secret))?;
+        if keys.address() != self.address {
+            return Err(format!("Key file {} does not match its address", self.name));
+        }
+        Ok(keys)
+    }
+}
+
+/// An account derived from a seed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivedAccount {
+    pub index: u32,
+    pub address: String,
+}
+
+/// A mnemonic-backed wallet. Only the mnemonic is secret; its accounts are derived from it
+/// and listed in the clear so they can be looked up without the password.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeedFile {
+    pub version: u32,
+    pub name: String,
+    pub created_at: u64,
+    #[serde(flatten)]
+    pub sealed: Sealed,
+    /// Accounts in use, by derivation index
+    pub accounts: Vec<DerivedAccount>,
+}
+
+impl SeedFile {
+    pub fn seal(name: &str, mnemonic: &str, password: &str) -> Result<Self, String> {
+        Self::seal_with(name, mnemonic, password, KdfParams::generate())
+    }
+
+    /// Seal a checked mnemonic with its first account derived
+    pub fn seal_with(name: &str, mnemonic: &str, password: &str, kdf: KdfParams) -> Result<Self, String> {
+        validate_name(name)?;
+        let wallet = HdWallet::from_mnemonic(mnemonic, "")?;
+        let mnemonic = mnemonic.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut seed_file = Self {
+            version: SEED_FILE_VERSION,
+            name: name.to_string(),
+            created_at: now(),
+            sealed: Sealed::seal(mnemonic.as_bytes(), seed_context(name).as_bytes(), password, kdf)?,
+            accounts: Vec::new(),
+        };
+        seed_file.derive_accounts(&wallet, 1);
+        Ok(seed_file)
+    }
+
+    /// The mnemonic, for showing to its owner
+    pub fn reveal(&self, password: &str) -> Result<String, String> {
+        if self.version != SEED_FILE_VERSION {
+            return Err(format!("Unsupported seed file version {}", self.version));
+        }
+        let mnemonic = self.sealed.open(seed_context(&self.name).as_bytes(), password)?
+            .ok_or_else(|| format!("Wrong password for {}", self.name))?;
+        String::from_utf8(mnemonic).map_err(|_| format!("Seed file {} is corrupt", self.name))
+    }
+
+    pub fn unlock(&self, password: &str) -> Result<HdWallet, String> {
+        let wallet = HdWallet::from_mnemonic(&self.reveal(password)?, "")?;
+        if let Some(first) = self.accounts.first() {
+            if wallet.account(first.index).address() != first.address {
+                return Err(format!("Seed file {} does not match its accounts", self.name));
+            }
+        }
+        Ok(wallet)
+    }
+
+    /// Make sure accounts 0..count are listed
+    pub fn derive_accounts(&mut self, wallet: &HdWallet, count: u32) {
+        for index in self.next_index()..count {
+            self.accounts.push(DerivedAccount { index, address: wallet.account(index).address() });
+        }
+    }
+
+    pub fn next_index(&self) -> u32 {
+        self.accounts.iter().map(|account| account.index + 1).max().unwrap_or(0)
+    }
+
+    /// How derived accounts are named on the command line: `<wallet>/<index>`
+    pub fn account_name(&self, index: u32) -> String {
+        format!("{}/{}", self.name, index)
+    }
+}
+
+/// Something that can sign: a stored key, or an account derived from a seed
+pub enum Account {
+    Key(KeyFile),
+    Derived { seed: SeedFile, index: u32 },
+}
+
+impl Account {
+    pub fn name(&self) -> String {
+        match self {
+            Account::Key(key) => key.name.clone(),
+            Account::Derived { seed, index } => seed.account_name(*index),
+        }
+    }
+
+    pub fn address(&self) -> String {
+        match self {
+            Account::Key(key) => key.address.clone(),
+            Account::Derived { seed, index } => seed.accounts.iter()
+                .find(|account| account.index == *index)
+                .map(|account| account.address.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn unlock(&self, password: &str) -> Result<KeyPair, String> {
+        match self {
+            Account::Key(key) => key.unlock(password),
+            Account::Derived { seed, index } => {
+                let keys = seed.unlock(password)?.account(*index);
+                if keys.address() != self.address() {
+                    return Err(format!("Seed file {} does not match account {}", seed.name, index));
+                }
+                Ok(keys)
+            }
+        }
+    }
+}
+
+/// Directory of key files, one `<address>.json` per account, with seed files under `seeds/`
+pub struct Keystore {
+    dir: PathBuf,
+}
+
+impl Keystore {
+    /// `$LAYER1_KEYSTORE`, else `~/.layer1/keystore`
+    pub fn default_dir() -> PathBuf {
+        if let Ok(dir) = std::env::var("LAYER1_KEYSTORE") {
+            return PathBuf::from(dir);
+        }
+        let home = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")).unwrap_or_else(|_| ".".to_string());
+        Path::new(&home).join(".layer1").join("keystore")
+    }
+
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, String> {
+        let dir = dir.into();
+        for path in [dir.clone(), dir.join("seeds")] {
+            fs::create_dir_all(&path)
+                .map_err(|e| format!("Failed to create keystore {}: {}", path.display(), e))?;
+            restrict_permissions(&path, 0o700)?;
+        }
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Every stored key, by name
+    pub fn list(&self) -> Result<Vec<KeyFile>, String> {
+        read_records(&self.dir)
+    }
+
+    /// Every seed wallet, by name
+    pub fn seeds(&self) -> Result<Vec<SeedFile>, String> {
+        read_records(&self.dir.join("seeds"))
+    }
+
+    pub fn find_seed(&self, name: &str) -> Result<SeedFile, String> {
+        self.seeds()?
+            .into_iter()
+            .find(|seed| seed.name == name)
+            .ok_or_else(|| format!("No seed wallet named {} in {}", name, self.dir.display()))
+    }
+
+    /// Look an account up by key name, `<wallet>/<index>`, or address
+    pub fn find(&self, name_or_address: &str) -> Result<Account, String> {
+        if let Some(key) = self.list()?.into_iter()
+            .find(|key| key.name == name_or_address || key.address == name_or_address)
+        {
+            return Ok(Account::Key(key));
+        }
+        for seed in self.seeds()? {
+            let found = seed.accounts.iter()
+                .find(|account| seed.account_name(account.index) == name_or_address || account.address == name_or_address)
+                .map(|account| account.index);
+            if let Some(index) = found {
+                return Ok(Account::Derived { seed, index });
+            }
+        }
+        Err(format!("No account named {} in {}", name_or_address, self.dir.display()))
+    }
+
+    /// Write a new key; names and addresses must be unique within the store
+    pub fn insert(&self, key: &KeyFile) -> Result<(), String> {
+        self.check_unused(&key.name)?;
+        if let Ok(existing) = self.find(&key.address) {
+            return Err(format!("Keystore already holds {} as {}", key.address, existing.name()));
+        }
+        write_record(&self.dir.join(format!("{}.json", key.address)), key)
+    }
+
+    /// Write a new seed wallet under a name no key or other wallet uses
+    pub fn insert_seed(&self, seed: &SeedFile) -> Result<(), String> {
+        self.check_unused(&seed.name)?;
+        self.save_seed(seed)
+    }
+
+    /// Overwrite a seed wallet, e.g. after deriving more accounts
+    pub fn save_seed(&self, seed: &SeedFile) -> Result<(), String> {
+        write_record(&self.dir.join("seeds").join(format!("{}.json", seed.name)), seed)
+    }
+
+    fn check_unused(&self, name: &str) -> Result<(), String> {
+        let taken = self.list()?.iter().any(|key| key.name == name)
+            || self.seeds()?.iter().any(|seed| seed.name == name);
+        if taken {
+            return Err(format!("Keystore already has something named {}", name));
+        }
+        Ok(())
+    }
+}
+
+fn read_records<T: DeserializeOwned + Named>(dir: &Path) -> Result<Vec<T>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read keystore {}: {}", dir.display(), e))?;
+    let mut records = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let data = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let record: T = serde_json::from_str(&data).map_err(|e| format!("Invalid keystore file {}: {}", path.display(), e))?;
+        records.push(record);
+    }
+    records.sort_by(|a, b| a.name().cmp(b.name()));
+    Ok(records)
+}
+
+fn write_record(path: &Path, record: &impl Serialize) -> Result<(), String> {
+    let data = serde_json::to_string_pretty(record).map_err(|e| format!("Failed to serialize keystore record: {}", e))?;
+    fs::write(path, data + "\n").map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    restrict_permissions(path, 0o600)
+}
+
+trait Named {
+    fn name(&self) -> &str;
+}
+
+impl Named for KeyFile {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Named for SeedFile {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn seed_context(name: &str) -> String {
+    format!("layer1-seed:{}", name)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+// Names are how accounts are picked on the command line, so they must not be mistaken for addresses
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 32 {
+        return Err("Key names must be 1-32 characters".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Key names may only contain letters, digits, '-' and '_'".to_string());
+    }
+    if crypto::validate_address(name).is_ok() {
+        return Err("Key names cannot be addresses".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path, mode: u32) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|e| format!("Failed to restrict permissions on {}: {}", path.display(), e))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path, _mode: u32) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use layer1::hd;
+
+    // Minimum Argon2 cost so the tests stay fast
+    fn cheap_kdf() -> KdfParams {
+        KdfParams { memory_kib: 8, iterations: 1, parallelism: 1, salt: hex::encode([7u8; 16]) }
+    }
+
+    fn temp_keystore() -> Keystore {
+        Keystore::open(std::env::temp_dir().join(format!("layer1-keystore-{}", uuid::Uuid::new_v4()))).unwrap()
+    }
+
+    #[test]
+    fn test_seal_and_unlock() {
+        let keys = KeyPair::generate();
+        let key_file = KeyFile::seal_with("treasury", &keys, "correct horse", cheap_kdf()).unwrap();
+        assert_eq!(key_file.address, keys.address());
+        assert!(!key_file.sealed.ciphertext.contains(&keys.secret_key_hex()));
+
+        assert_eq!(key_file.unlock("correct horse").unwrap().secret_key_hex(), keys.secret_key_hex());
+        assert!(key_file.unlock("wrong").is_err());
+
+        // The address is authenticated, so pointing the file at another account breaks it
+        let mut tampered = key_file.clone();
+        tampered.address = KeyPair::generate().address();
+        assert!(tampered.unlock("correct horse").is_err());
+    }
+
+    #[test]
+    fn test_keystore_lookup_and_uniqueness() {
+        let keystore = temp_keystore();
+        let keys = KeyPair::generate();
+        let key_file = KeyFile::seal_with("ops", &keys, "pw", cheap_kdf()).unwrap();
+        keystore.insert(&key_file).unwrap();
+
+        assert_eq!(keystore.find("ops").unwrap().address(), keys.address());
+        assert_eq!(keystore.find(&keys.address()).unwrap().name(), "ops");
+        assert!(keystore.find("missing").is_err());
+
+        let same_name = KeyFile::seal_with("ops", &KeyPair::generate(), "pw", cheap_kdf()).unwrap();
+        assert!(keystore.insert(&same_name).is_err());
+        assert!(KeyFile::seal_with(&keys.address(), &keys, "pw", cheap_kdf()).is_err());
+
+        fs::remove_dir_all(keystore.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_seed_accounts_are_found_and_unlock() {
+        let keystore = temp_keystore();
+        let mnemonic = hd::generate_mnemonic(12).unwrap();
+        let mut seed = SeedFile::seal_with("savings", &mnemonic, "pw", cheap_kdf()).unwrap();
+        let wallet = seed.unlock("pw").unwrap();
+        seed.derive_accounts(&wallet, 3);
+        keystore.insert_seed(&seed).unwrap();
+        assert!(SeedFile::seal_with("savings", &mnemonic, "pw", cheap_kdf())
+            .and_then(|duplicate| keystore.insert_seed(&duplicate)).is_err());
+
+        let account = keystore.find("savings/2").unwrap();
+        assert_eq!(account.address(), wallet.account(2).address());
+        assert_eq!(account.unlock("pw").unwrap().address(), account.address());
+        assert_eq!(keystore.find(&wallet.account(1).address()).unwrap().name(), "savings/1");
+        assert!(keystore.find("savings/3").is_err());
+
+        assert_eq!(seed.reveal("pw").unwrap(), mnemonic);
+        assert!(seed.reveal("wrong").is_err());
+
+        fs::remove_dir_all(keystore.dir()).unwrap();
+    }
+}
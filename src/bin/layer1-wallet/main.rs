@@ -0,0 +1,465 @@
+//! Command-line wallet: keeps encrypted keys in a local keystore and signs transfers for a node
+
+mod client;
+mod keystore;
+
+use layer1::amount::Amount;
+use layer1::crypto::{self, KeyPair, MultisigSignature};
+use layer1::enhanced_transaction::EnhancedTransaction;
+use layer1::hd::{self, HdWallet};
+use layer1::vesting::{LockSchedule, Vesting};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use client::{NodeClient, Proposal};
+use keystore::{Account, KeyFile, Keystore, SeedFile};
+
+const USAGE: &str = "\
+Usage: layer1-wallet [OPTIONS] <COMMAND>
+
+Commands:
+  new <NAME>                             Generate a key and add it to the keystore
+  import <NAME>                          Add an existing secret key (prompted for, hex)
+  list                                   Show the keys and seed wallet accounts in the keystore
+  hd new <NAME>                          Create a seed wallet and show its mnemonic once
+  hd recover <NAME>                      Restore a seed wallet from its mnemonic and find its used accounts
+  hd derive <NAME>                       Add the seed wallet's next account
+  hd discover <NAME>                     Rescan the chain for the seed wallet's used accounts
+  hd mnemonic <NAME>                     Show a seed wallet's mnemonic
+  balance <ACCOUNT>                      Balance and next nonce of an account
+  send <FROM> <TO> <AMOUNT>              Sign and submit a transfer
+  cancel <FROM> <NONCE>                  Replace a pending transfer with an empty one (needs a higher --fee)
+  multisig create <M> <KEY>...           Register an M-of-N account from accounts or hex public keys
+  multisig propose <FROM> <TO> <AMOUNT>  Propose a transfer from a multisig account
+  multisig proposals [ADDRESS]           Transfers waiting for members' signatures
+  multisig sign <ACCOUNT> <ID>           Sign a proposal as one of its account's members
+  receipt <TX_ID>                        Status of a submitted transaction
+  help                                   Show this message
+
+Options:
+  --node <URL>        Node HTTP API (default: $LAYER1_NODE, else http://127.0.0.1:3030)
+  --keystore <DIR>    Key directory (default: $LAYER1_KEYSTORE, else ~/.layer1/keystore)
+  --fee <AMOUNT>      send, multisig propose, cancel: fee to pay (default: the node's estimate for
+                      confirmation within 3 blocks, or the next block for cancel)
+  --nonce <N>         send: reuse a pending transfer's nonce to replace it; the fee must be 10% higher
+  --message <TEXT>    send, multisig propose: message attached to the transfer
+  --unlock-height <H> send: recipient cannot spend the amount before block H
+  --unlock-time <T>   send: recipient cannot spend the amount before Unix time T
+  --vest <SECS>       send: release the amount linearly over SECS, from --unlock-time or now
+  --words <N>         hd new: mnemonic length, 12, 15, 18, 21 or 24 words (default 24)
+
+Accounts are key names, seed wallet accounts as <WALLET>/<INDEX>, or addresses.
+Passwords are prompted for, or read from $LAYER1_WALLET_PASSWORD for scripted use;
+hd recover likewise reads the mnemonic from $LAYER1_WALLET_MNEMONIC.";
+
+/// Parsed command line
+#[derive(Default)]
+struct Args {
+    words: Vec<String>,
+    node: Option<String>,
+    keystore: Option<String>,
+    fee: Option<String>,
+    nonce: Option<String>,
+    message: Option<String>,
+    word_count: Option<String>,
+    unlock_height: Option<String>,
+    unlock_time: Option<String>,
+    vest: Option<String>,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = parse_args(&args).unwrap_or_else(|e| {
+        eprintln!("❌ {}\n\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+    if let Err(e) = run(args) {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn parse_args(raw: &[String]) -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut iter = raw.iter();
+    while let Some(arg) = iter.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            args.words.push(arg.clone());
+            continue;
+        };
+        if flag == "help" {
+            args.words = vec!["help".to_string()];
+            return Ok(args);
+        }
+        let (flag, value) = match flag.split_once('=') {
+            Some((flag, value)) => (flag, value.to_string()),
+            None => (flag, iter.next().cloned().ok_or_else(|| format!("--{} needs a value", flag))?),
+        };
+        let slot = match flag {
+            "node" => &mut args.node,
+            "keystore" => &mut args.keystore,
+            "fee" => &mut args.fee,
+            "nonce" => &mut args.nonce,
+            "message" => &mut args.message,
+            "words" => &mut args.word_count,
+            "unlock-height" => &mut args.unlock_height,
+            "unlock-time" => &mut args.unlock_time,
+            "vest" => &mut args.vest,
+            _ => return Err(format!("Unknown option --{}", flag)),
+        };
+        *slot = Some(value);
+    }
+    Ok(args)
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let keystore_dir = args.keystore.clone().map(Into::into).unwrap_or_else(Keystore::default_dir);
+    let node_url = args.node.clone()
+        .or_else(|| std::env::var("LAYER1_NODE").ok())
+        .unwrap_or_else(|| "http://127.0.0.1:3030".to_string());
+    let words: Vec<&str> = args.words.iter().map(String::as_str).collect();
+
+    match words.as_slice() {
+        [] | ["help"] => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        ["new", name] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            add_key(&keystore, name, KeyPair::generate())
+        }
+        ["import", name] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            let secret = rpassword::prompt_password("Secret key (hex): ").map_err(|e| e.to_string())?;
+            add_key(&keystore, name, KeyPair::from_secret_hex(secret.trim())?)
+        }
+        ["list"] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            let keys = keystore.list()?;
+            let seeds = keystore.seeds()?;
+            if keys.is_empty() && seeds.is_empty() {
+                println!("No keys in {}", keystore.dir().display());
+            }
+            for key in keys {
+                println!("{:<20} {}", key.name, key.address);
+            }
+            for seed in seeds {
+                for account in &seed.accounts {
+                    println!("{:<20} {}", seed.account_name(account.index), account.address);
+                }
+            }
+            Ok(())
+        }
+        ["hd", "new", name] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            let word_count = args.word_count.as_deref().unwrap_or("24").parse()
+                .map_err(|_| "--words takes a number".to_string())?;
+            let mnemonic = hd::generate_mnemonic(word_count)?;
+            let seed = SeedFile::seal(name, &mnemonic, &new_password()?)?;
+            keystore.insert_seed(&seed)?;
+            println!("🌱 Seed wallet {} created. Write these words down and keep them offline;", name);
+            println!("   they are the only way to restore its accounts on another device:");
+            println!();
+            println!("   {}", mnemonic);
+            println!();
+            println!("🔑 {} {}", seed.account_name(0), seed.accounts[0].address);
+            Ok(())
+        }
+        ["hd", "recover", name] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            let mnemonic = match std::env::var("LAYER1_WALLET_MNEMONIC") {
+                Ok(mnemonic) => mnemonic,
+                Err(_) => rpassword::prompt_password("Mnemonic: ").map_err(|e| e.to_string())?,
+            };
+            let wallet = HdWallet::from_mnemonic(&mnemonic, "")?;
+            let mut seed = SeedFile::seal(name, &mnemonic, &new_password()?)?;
+            match discover(&NodeClient::new(&node_url), &wallet) {
+                Ok(count) => seed.derive_accounts(&wallet, count),
+                Err(e) => println!("⚠️ Could not scan for used accounts ({}); run hd discover {} later", e, name),
+            }
+            keystore.insert_seed(&seed)?;
+            print_accounts(&seed);
+            Ok(())
+        }
+        ["hd", "derive", name] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            let mut seed = keystore.find_seed(name)?;
+            let wallet = seed.unlock(&password(&format!("Password for {}: ", name))?)?;
+            let index = seed.next_index();
+            seed.derive_accounts(&wallet, index + 1);
+            keystore.save_seed(&seed)?;
+            println!("🔑 {} {}", seed.account_name(index), wallet.account(index).address());
+            println!("   Path {}", hd::account_path(index));
+            Ok(())
+        }
+        ["hd", "discover", name] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            let mut seed = keystore.find_seed(name)?;
+            let wallet = seed.unlock(&password(&format!("Password for {}: ", name))?)?;
+            let count = discover(&NodeClient::new(&node_url), &wallet)?;
+            seed.derive_accounts(&wallet, count);
+            keystore.save_seed(&seed)?;
+            print_accounts(&seed);
+            Ok(())
+        }
+        ["hd", "mnemonic", name] => {
+            let seed = Keystore::open(keystore_dir)?.find_seed(name)?;
+            println!("{}", seed.reveal(&password(&format!("Password for {}: ", name))?)?);
+            Ok(())
+        }
+        ["balance", account] => {
+            let address = resolve_address(&Keystore::open(keystore_dir)?, account)?;
+            let client = NodeClient::new(&node_url);
+            let balance = client.balance(&address)?;
+            let nonce = client.account_nonce(&address)?;
+            println!("Address: {}", address);
+            println!("Balance: {} L1", balance.balance);
+            if !balance.locked.is_zero() {
+                println!("Locked:  {} L1 ({} L1 spendable)", balance.locked, balance.spendable());
+            }
+            println!("Nonce:   {} next, {} confirmed, {} queued", nonce.nonce, nonce.confirmed_nonce, nonce.queued_count);
+            Ok(())
+        }
+        ["send", from, to, amount] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            let amount: Amount = amount.parse()?;
+            send(&keystore, &NodeClient::new(&node_url), from, to, amount, &args)
+        }
+        ["cancel", from, nonce] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            let nonce = nonce.parse().map_err(|_| "The nonce is a number".to_string())?;
+            cancel(&keystore, &NodeClient::new(&node_url), from, nonce, &args)
+        }
+        ["multisig", "create", threshold, keys @ ..] if !keys.is_empty() => {
+            let keystore = Keystore::open(keystore_dir)?;
+            let threshold = threshold.parse().map_err(|_| "The threshold is a number".to_string())?;
+            let public_keys = keys.iter().map(|key| public_key(&keystore, key)).collect::<Result<Vec<_>, _>>()?;
+            let address = NodeClient::new(&node_url).create_multisig(threshold, &public_keys)?;
+            println!("🔐 {} of {} multisig account {}", threshold, public_keys.len(), address);
+            Ok(())
+        }
+        ["multisig", "propose", from, to, amount] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            let client = NodeClient::new(&node_url);
+            let to = resolve_address(&keystore, to)?;
+            let amount: Amount = amount.parse()?;
+            let fee = match args.fee.as_deref() {
+                Some(fee) => fee.parse()?,
+                None => client.recommended_fee(3)?,
+            };
+            let proposal = client.propose(from, &to, amount, fee, args.message.as_deref())?;
+            println!("📝 Proposal {} needs {} signatures", proposal.id, proposal.threshold);
+            print_proposal(&proposal);
+            Ok(())
+        }
+        ["multisig", "proposals", filter @ ..] if filter.len() <= 1 => {
+            let proposals = NodeClient::new(&node_url).proposals()?;
+            let proposals: Vec<&Proposal> = proposals.iter()
+                .filter(|proposal| filter.first().is_none_or(|address| proposal.transaction.from == *address))
+                .collect();
+            if proposals.is_empty() {
+                println!("No open proposals");
+            }
+            for proposal in proposals {
+                println!("{} ({} of {} signatures)", proposal.id, proposal.signatures.len(), proposal.threshold);
+                print_proposal(proposal);
+            }
+            Ok(())
+        }
+        ["multisig", "sign", account, proposal_id] => {
+            let keystore = Keystore::open(keystore_dir)?;
+            sign_proposal(&keystore, &NodeClient::new(&node_url), account, proposal_id)
+        }
+        ["receipt", tx_id] => {
+            let receipt = NodeClient::new(&node_url).receipt(tx_id)?;
+            println!("{}", serde_json::to_string_pretty(&receipt).map_err(|e| e.to_string())?);
+            Ok(())
+        }
+        _ => Err(format!("Unknown command: {}\n\n{}", words.join(" "), USAGE)),
+    }
+}
+
+fn add_key(keystore: &Keystore, name: &str, keys: KeyPair) -> Result<(), String> {
+    let password = new_password()?;
+    let key_file = KeyFile::seal(name, &keys, &password)?;
+    keystore.insert(&key_file)?;
+    println!("🔑 {} {}", key_file.name, key_file.address);
+    println!("   Saved to {}", keystore.dir().display());
+    Ok(())
+}
+
+// Sign with the next nonce and the node's recommended fee unless --fee was given
+fn send(keystore: &Keystore, client: &NodeClient, from: &str, to: &str, amount: Amount, args: &Args) -> Result<(), String> {
+    let account = keystore.find(from)?;
+    let from = account.address();
+    let to = resolve_address(keystore, to)?;
+    let lock = lock_schedule(args)?;
+    let fee = match args.fee.as_deref() {
+        Some(fee) => fee.parse()?,
+        None => client.recommended_fee(3)?,
+    };
+
+    let balance = client.balance(&from)?.spendable();
+    let total = amount.try_add(fee)?;
+    if balance < total {
+        return Err(format!("Insufficient balance: have {} L1 spendable, need {} L1 including the fee", balance, total));
+    }
+
+    let keys = account.unlock(&password(&format!("Password for {}: ", account.name()))?)?;
+    let nonce = match args.nonce.as_deref() {
+        Some(nonce) => nonce.parse().map_err(|_| "--nonce takes a whole number".to_string())?,
+        None => client.account_nonce(&from)?.nonce,
+    };
+    let mut tx = EnhancedTransaction::new(from, to, amount, fee).with_nonce(nonce);
+    if let Some(message) = &args.message {
+        tx = tx.with_message(message.clone());
+    }
+    if let Some(lock) = lock {
+        tx = tx.with_lock(lock);
+    }
+    let tx = tx.sign(&keys);
+
+    let tx_id = client.submit(&tx)?;
+    println!("📤 Sent {} L1 (fee {}) from {} to {} with nonce {}", tx.amount, tx.fee, tx.from, tx.to, tx.nonce);
+    if let Some(lock) = &tx.lock {
+        println!("   Locked for the recipient: {}", describe_lock(lock));
+    }
+    println!("   Transaction id: {}", tx_id);
+    Ok(())
+}
+
+// Sign an empty self-transfer with the stuck transfer's nonce; the node swaps it in if the fee is high enough
+fn cancel(keystore: &Keystore, client: &NodeClient, from: &str, nonce: u64, args: &Args) -> Result<(), String> {
+    let account = keystore.find(from)?;
+    let fee = match args.fee.as_deref() {
+        Some(fee) => fee.parse()?,
+        None => client.recommended_fee(1)?,
+    };
+
+    let keys = account.unlock(&password(&format!("Password for {}: ", account.name()))?)?;
+    let tx = EnhancedTransaction::cancellation(account.address(), nonce, fee).sign(&keys);
+    let tx_id = client.cancel(&tx)?;
+    println!("🚫 Cancelled the pending transfer from {} with nonce {} (fee {})", tx.from, nonce, fee);
+    println!("   Transaction id: {}", tx_id);
+    Ok(())
+}
+
+// Lock from --unlock-height, --unlock-time and --vest; vesting starts at the unlock time if there is one
+fn lock_schedule(args: &Args) -> Result<Option<LockSchedule>, String> {
+    let number = |value: &Option<String>, flag: &str| {
+        value.as_deref()
+            .map(|value| value.parse::<u64>().map_err(|_| format!("--{} takes a whole number", flag)))
+            .transpose()
+    };
+    let unlock_at_height = number(&args.unlock_height, "unlock-height")?;
+    let unlock_at_time = number(&args.unlock_time, "unlock-time")?;
+    let vesting = number(&args.vest, "vest")?.map(|duration_secs| {
+        let start_time = unlock_at_time.unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+        });
+        Vesting { start_time, duration_secs }
+    });
+    if unlock_at_height.is_none() && unlock_at_time.is_none() && vesting.is_none() {
+        return Ok(None);
+    }
+    let lock = LockSchedule { unlock_at_height, unlock_at_time, vesting };
+    lock.validate()?;
+    Ok(Some(lock))
+}
+
+fn describe_lock(lock: &LockSchedule) -> String {
+    let mut conditions = Vec::new();
+    if let Some(height) = lock.unlock_at_height {
+        conditions.push(format!("until block {}", height));
+    }
+    if let Some(time) = lock.unlock_at_time {
+        conditions.push(format!("until time {}", time));
+    }
+    if let Some(vesting) = lock.vesting {
+        conditions.push(format!("vesting over {}s from time {}", vesting.duration_secs, vesting.start_time));
+    }
+    conditions.join(", ")
+}
+
+// Members sign exactly the transfer the node listed; they should read it before signing
+fn sign_proposal(keystore: &Keystore, client: &NodeClient, account: &str, proposal_id: &str) -> Result<(), String> {
+    let account = keystore.find(account)?;
+    let proposal = client.proposals()?.into_iter()
+        .find(|proposal| proposal.id == proposal_id)
+        .ok_or_else(|| format!("No open proposal {}", proposal_id))?;
+    print_proposal(&proposal);
+
+    let keys = account.unlock(&password(&format!("Password for {}: ", account.name()))?)?;
+    let signature = MultisigSignature {
+        public_key: keys.public_key_hex(),
+        signature: keys.sign(proposal.transaction.signing_payload().as_bytes()),
+    };
+    let (submitted, message) = client.sign_proposal(&proposal.id, &signature)?;
+    println!("{} {}", if submitted { "📤" } else { "✍️" }, message);
+    Ok(())
+}
+
+fn print_proposal(proposal: &Proposal) {
+    let tx = &proposal.transaction;
+    println!("   {} L1 (fee {}) from {} to {} with nonce {}", tx.amount, tx.fee, tx.from, tx.to, tx.nonce);
+    if let Some(message) = &tx.data {
+        println!("   Message: {}", message);
+    }
+}
+
+// A key's hex public key, looked up in the keystore or given directly
+fn public_key(keystore: &Keystore, key: &str) -> Result<String, String> {
+    match keystore.find(key) {
+        Ok(Account::Key(key_file)) => Ok(key_file.public_key),
+        Ok(account) => Ok(account.unlock(&password(&format!("Password for {}: ", account.name()))?)?.public_key_hex()),
+        Err(_) => {
+            crypto::address_from_public_key(key)
+                .map_err(|e| format!("{} is neither a key in the keystore nor a public key: {}", key, e))?;
+            Ok(key.to_string())
+        }
+    }
+}
+
+// An account in the keystore, or an address
+fn resolve_address(keystore: &Keystore, account: &str) -> Result<String, String> {
+    if let Ok(account) = keystore.find(account) {
+        return Ok(account.address());
+    }
+    crypto::validate_address(account)
+        .map_err(|e| format!("{} is neither a key in the keystore nor an address: {}", account, e))?;
+    Ok(account.to_string())
+}
+
+// Number of leading accounts the chain has seen, asking the node a gap-limit window at a time
+fn discover(client: &NodeClient, wallet: &HdWallet) -> Result<u32, String> {
+    wallet.discover(|addresses| {
+        let activity = client.activity(addresses)?;
+        if activity.iter().map(|account| &account.address).ne(addresses.iter()) {
+            return Err("Node answered for different addresses".to_string());
+        }
+        Ok(activity.iter().map(|account| account.transaction_count > 0 || !account.balance.is_zero()).collect())
+    })
+}
+
+fn print_accounts(seed: &SeedFile) {
+    for account in &seed.accounts {
+        println!("🔑 {:<20} {}", seed.account_name(account.index), account.address);
+    }
+}
+
+fn password(prompt: &str) -> Result<String, String> {
+    if let Ok(password) = std::env::var("LAYER1_WALLET_PASSWORD") {
+        return Ok(password);
+    }
+    rpassword::prompt_password(prompt).map_err(|e| format!("Failed to read password: {}", e))
+}
+
+fn new_password() -> Result<String, String> {
+    let first = password("New password: ")?;
+    if first.is_empty() {
+        return Err("Password must not be empty".to_string());
+    }
+    if std::env::var("LAYER1_WALLET_PASSWORD").is_err() && password("Repeat password: ")? != first {
+        return Err("Passwords do not match".to_string());
+    }
+    Ok(first)
+}
@@ -0,0 +1,238 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::difficulty;
+use crate::Block;
+
+/// Deepest reorganization the node will perform; blocks further back are treated as final
+pub const MAX_REORG_DEPTH: usize = 100;
+
+/// A block and the total work of the branch it ends
+#[derive(Debug, Clone)]
+struct TreeEntry {
+    block: Block,
+    total_work: u128,
+}
+
+/// Every block the node has accepted, on the active chain or a side branch, keyed by hash
+#[derive(Debug, Clone, Default)]
+pub struct BlockTree {
+    entries: HashMap<String, TreeEntry>,
+}
+
+/// The active chain switched branches
+#[derive(Debug, Clone, Serialize)]
+pub struct ReorgEvent {
+    pub fork_height: u64,
+    pub old_tip: String,
+    pub new_tip: String,
+    /// Hashes of the blocks taken off the active chain, oldest first
+    pub disconnected: Vec<String>,
+    /// Hashes of the blocks that replaced them, oldest first
+    pub connected: Vec<String>,
+    /// Transactions only the abandoned branch had, returned to the pool
+    pub orphaned_transactions: usize,
+    pub timestamp: u64,
+}
+
+impl BlockTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_chain(chain: &[Block]) -> Self {
+        let mut tree = Self::new();
+        for block in chain {
+            if let Err(e) = tree.insert(block.clone()) {
+                println!("⚠️ {}", e);
+            }
+        }
+        tree
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.entries.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Block> {
+        self.entries.get(hash).map(|entry| &entry.block)
+    }
+
+    /// Cumulative work from genesis up to and including this block (zero if unknown)
+    pub fn total_work(&self, hash: &str) -> u128 {
+        self.entries.get(hash).map_or(0, |entry| entry.total_work)
+    }
+
+    /// Add a block whose parent is already in the tree; returns the work of the branch it ends
+    pub fn insert(&mut self, block: Block) -> Result<u128, String> {
+        if let Some(entry) = self.entries.get(&block.hash) {
+            return Ok(entry.total_work);
+        }
+
+        let parent_work = if block.index == 0 {
+            0
+        } else {
+            self.entries.get(&block.previous_hash)
+                .map(|parent| parent.total_work)
+                .ok_or_else(|| format!("Block {} has unknown parent {}", block.index, block.previous_hash))?
+        };
+        let total_work = parent_work + difficulty::block_work(block.difficulty);
+        self.entries.insert(block.hash.clone(), TreeEntry { block, total_work });
+        Ok(total_work)
+    }
+
+    /// Up to `count` blocks ending at `hash`, oldest first
+    pub fn ancestors(&self, hash: &str, count: usize) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut current = self.entries.get(hash);
+        while let Some(entry) = current {
+            if blocks.len() == count {
+                break;
+            }
+            blocks.push(entry.block.clone());
+            current = match entry.block.index {
+                0 => None,
+                _ => self.entries.get(&entry.block.previous_hash),
+            };
+        }
+        blocks.reverse();
+        blocks
+    }
+
+    /// Blocks from `tip` back to, but not including, the first one `is_active` accepts; oldest first
+    pub fn branch(&self, tip: &str, is_active: impl Fn(&Block) -> bool) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut current = self.entries.get(tip);
+        while let Some(entry) = current {
+            if is_active(&entry.block) {
+                break;
+            }
+            blocks.push(entry.block.clone());
+            current = self.entries.get(&entry.block.previous_hash);
+        }
+        blocks.reverse();
+        blocks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::chain_spec::{ChainSpec, GenesisAllocation};
+    use crate::crypto::KeyPair;
+    use crate::enhanced_transaction::EnhancedTransaction;
+    use crate::{Blockchain, Transaction};
+
+    fn mine_rewards(blockchain: &mut Blockchain, recipients: &[&str]) {
+        for recipient in recipients {
+            blockchain.create_system_transaction("genesis", recipient.to_string(), Amount::from_l1(10)).unwrap();
+            blockchain.mine_pending_transactions("miner".to_string());
+        }
+    }
+
+    #[test]
+    fn test_work_accumulates_along_branches() {
+        let mut blockchain = Blockchain::new();
+        mine_rewards(&mut blockchain, &["alice", "bob"]);
+        let tree = BlockTree::from_chain(&blockchain.chain);
+
+        let tip = &blockchain.chain[2];
+        let expected: u128 = blockchain.chain.iter().map(|block| difficulty::block_work(block.difficulty)).sum();
+        assert_eq!(tree.total_work(&tip.hash), expected);
+        assert_eq!(tree.ancestors(&tip.hash, 2).iter().map(|block| block.index).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(tree.branch(&tip.hash, |block| block.index == 0).len(), 2);
+
+        let mut orphan = tip.clone();
+        orphan.previous_hash = "unknown".to_string();
+        orphan.hash = "orphan".to_string();
+        assert!(BlockTree::new().insert(orphan).is_err());
+    }
+
+    // Blocks a peer accepts carry signed transfers only, here from an account funded at genesis
+    fn mine_transfers(blockchain: &mut Blockchain, sender: &KeyPair, recipients: &[&str]) {
+        for (nonce, recipient) in recipients.iter().enumerate() {
+            let tx = EnhancedTransaction::new(sender.address(), recipient.to_string(), Amount::from_l1(10), Amount::from_l1(1))
+                .with_nonce(nonce as u64)
+                .sign(sender);
+            blockchain.add_enhanced_transaction(tx).unwrap();
+            blockchain.mine_pending_transactions("miner".to_string());
+        }
+    }
+
+    #[test]
+    fn test_heavier_branch_wins_and_orphans_return_to_pool() {
+        let (local_keys, remote_keys) = (KeyPair::generate(), KeyPair::generate());
+        let mut spec = ChainSpec::default();
+        for keys in [&local_keys, &remote_keys] {
+            spec.genesis.allocations.push(GenesisAllocation { address: keys.address(), amount: Amount::from_l1(100), lock: None });
+        }
+        let mut local = Blockchain::with_spec(&spec);
+        mine_transfers(&mut local, &local_keys, &["alice"]);
+        let mut remote = Blockchain::with_spec(&spec);
+        mine_transfers(&mut remote, &remote_keys, &["bob", "carol"]);
+
+        // An equally heavy branch is stored but does not replace the tip
+        let local_tip = local.chain[1].hash.clone();
+        local.import_block(remote.chain[1].clone()).unwrap();
+        assert_eq!(local.chain[1].hash, local_tip);
+        assert!(local.reorgs.is_empty());
+
+        local.import_block(remote.chain[2].clone()).unwrap();
+        assert_eq!(local.chain.last().unwrap().hash, remote.chain[2].hash);
+        assert_eq!(local.get_balance("alice"), Amount::ZERO);
+        assert_eq!(local.get_balance("carol"), Amount::from_l1(10));
+
+        // Alice's transfer only made it into the abandoned block, so it is pending again
+        assert!(local.mempool.pending().iter().any(|entry| entry.transaction.to == "alice"));
+        assert!(local.mempool.pending().iter().all(|entry| entry.transaction.from != "mining_reward"));
+
+        let event = local.reorgs.back().unwrap();
+        assert_eq!(event.fork_height, 0);
+        assert_eq!(event.disconnected, vec![local_tip]);
+        assert_eq!(event.connected.len(), 2);
+        assert_eq!(event.orphaned_transactions, 1);
+    }
+
+    #[test]
+    fn test_invalid_branch_leaves_active_chain() {
+        let (local_keys, remote_keys) = (KeyPair::generate(), KeyPair::generate());
+        let mut spec = ChainSpec::default();
+        for keys in [&local_keys, &remote_keys] {
+            spec.genesis.allocations.push(GenesisAllocation { address: keys.address(), amount: Amount::from_l1(100), lock: None });
+        }
+        let mut local = Blockchain::with_spec(&spec);
+        mine_transfers(&mut local, &local_keys, &["alice"]);
+        let mut remote = Blockchain::with_spec(&spec);
+        mine_transfers(&mut remote, &remote_keys, &["bob"]);
+
+        // The heavier branch replays its first transfer, which the ledger would skip
+        let parent = remote.chain[1].clone();
+        let coinbase = Transaction {
+            from: "mining_reward".to_string(),
+            to: "miner".to_string(),
+            amount: remote.emission.block_subsidy(2),
+            ..Default::default()
+        };
+        let mut replay = Block::new(2, vec![parent.transactions[0].clone(), coinbase], parent.hash.clone(), "miner".to_string());
+        replay.timestamp = parent.timestamp + 1;
+        replay.reward = remote.emission.block_subsidy(2);
+        replay.base_fee = remote.next_base_fee();
+        let mut replay = replay.with_difficulty(remote.current_difficulty());
+        replay.mine_block();
+
+        let local_tip = local.chain[1].hash.clone();
+        local.import_block(parent).unwrap();
+        let err = local.import_block(replay).unwrap_err();
+        assert!(err.contains("Rejected branch at block 2"), "{}", err);
+
+        // The old block is back in the chain and the ledger, and can still be reorganized away
+        assert_eq!(local.height(), 1);
+        assert_eq!(local.chain[1].hash, local_tip);
+        assert_eq!(local.get_balance("alice"), Amount::from_l1(10));
+        assert_eq!(local.get_balance("bob"), Amount::ZERO);
+        assert_eq!(local.ledger.nonce(&remote_keys.address()), 0);
+        assert!(local.ledger.can_revert(&local.chain[1..]));
+        assert!(local.reorgs.is_empty());
+    }
+}
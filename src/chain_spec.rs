@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+use crate::amount::Amount;
+use crate::crypto::{self, KeyPair, MultisigPolicy};
+use crate::difficulty::RetargetParams;
+use crate::vesting::LockSchedule;
+
+/// Everything that defines a network: its identity, genesis block, consensus rules and economics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainSpec {
+    pub chain_id: String,
+    pub genesis: GenesisSpec,
+    #[serde(default)]
+    pub consensus: RetargetParams,
+    #[serde(default)]
+    pub economics: EconomicParams,
+}
+
+/// Contents of the genesis block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenesisSpec {
+    /// Unix timestamp recorded in the genesis block
+    pub timestamp: u64,
+    /// Balances minted in the genesis block
+    #[serde(default)]
+    pub allocations: Vec<GenesisAllocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenesisAllocation {
+    pub address: String,
+    pub amount: Amount,
+    /// Vesting or time lock on the allocation, e.g. for team allocations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock: Option<LockSchedule>,
+}
+
+/// Supply cap and the amounts the node pays out or charges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EconomicParams {
+    pub max_supply: Amount,
+    /// Block subsidy of the first halving era
+    pub mining_reward: Amount,
+    /// Blocks between subsidy halvings; by default spaced so mining alone would approach max_supply
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub halving_interval: Option<u64>,
+    /// Paid to an address the first time it registers a username
+    pub signup_bonus: Amount,
+    pub token_launch_fee: Amount,
+    /// Multisig account token launch fees are paid into; without one they go to an account nobody can spend
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub treasury: Option<MultisigPolicy>,
+    /// Public key (hex) that signs protocol payouts, such as signup bonuses, connection and social
+    /// rewards and token sale proceeds, so peers can check them. Without one the well-known dev
+    /// key signs them, which anybody can use, so only dev chains should leave it out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payout_authority: Option<String>,
+}
+
+impl Default for EconomicParams {
+    fn default() -> Self {
+        Self {
+            max_supply: Amount::from_l1(21_000_000),
+            mining_reward: Amount::from_l1(10),
+            halving_interval: None,
+            signup_bonus: Amount::from_l1(1000),
+            token_launch_fee: Amount::from_l1(10),
+            treasury: None,
+            payout_authority: None,
+        }
+    }
+}
+
+impl Default for ChainSpec {
+    fn default() -> Self {
+        Self {
+            chain_id: "layer1-dev".to_string(),
+            genesis: GenesisSpec {
+                timestamp: 1_735_689_600,
+                allocations: Vec::new(),
+            },
+            consensus: RetargetParams::default(),
+            economics: EconomicParams::default(),
+        }
+    }
+}
+
+impl ChainSpec {
+    /// Read a JSON spec file and check it
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read chain spec {}: {}", path.display(), e))?;
+        let spec: Self = serde_json::from_str(&data)
+            .map_err(|e| format!("Invalid chain spec {}: {}", path.display(), e))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.chain_id.trim().is_empty() {
+            return Err("Chain spec needs a chain_id".to_string());
+        }
+
+        let consensus = &self.consensus;
+        if consensus.target_block_time == 0 || consensus.initial_difficulty == 0 || consensus.max_adjustment == 0 {
+            return Err("Consensus target_block_time, initial_difficulty and max_adjustment must be positive".to_string());
+        }
+        if consensus.retarget_interval < 2 {
+            return Err("Consensus retarget_interval must be at least 2".to_string());
+        }
+
+        if self.economics.halving_interval == Some(0) {
+            return Err("Economics halving_interval must be positive".to_string());
+        }
+
+        if let Some(treasury) = &self.economics.treasury {
+            treasury.validate().map_err(|e| format!("Treasury: {}", e))?;
+            if treasury.threshold < 2 {
+                return Err("Treasury must need at least two signatures".to_string());
+            }
+        }
+
+        if let Some(authority) = &self.economics.payout_authority {
+            crypto::address_from_public_key(authority).map_err(|e| format!("Payout authority: {}", e))?;
+        }
+
+        let mut allocated = Amount::ZERO;
+        for allocation in &self.genesis.allocations {
+            crypto::validate_address(&allocation.address)
+                .and_then(|_| allocation.lock.as_ref().map_or(Ok(()), LockSchedule::validate))
+                .map_err(|e| format!("Genesis allocation to {}: {}", allocation.address, e))?;
+            allocated = allocated.try_add(allocation.amount)?;
+        }
+        if allocated > self.economics.max_supply {
+            return Err(format!("Genesis allocates {} but max_supply is {}", allocated, self.economics.max_supply));
+        }
+        Ok(())
+    }
+
+    /// Public key whose signature protocol payouts must carry
+    pub fn payout_authority(&self) -> String {
+        self.economics.payout_authority.clone().unwrap_or_else(|| dev_payout_keys().public_key_hex())
+    }
+
+    /// Hex SHA-256 of the spec; the genesis block commits to it, so any change yields a new network
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(serde_json::to_vec(self).unwrap_or_default());
+        format!("{:x}", hasher.finalize())
+    }
+}
+
+/// Payout key of chains that name no payout authority. Its secret is derived from a public
+/// string, so every node on such a chain can sign payouts with it.
+pub fn dev_payout_keys() -> KeyPair {
+    KeyPair::from_secret_bytes(&Sha256::digest(b"layer1 dev payout authority").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Blockchain;
+
+    #[test]
+    fn test_default_spec_is_valid() {
+        assert!(ChainSpec::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_specs_rejected() {
+        let address = KeyPair::generate().address();
+        let mut spec = ChainSpec::default();
+        spec.genesis.allocations.push(GenesisAllocation { address, amount: Amount::from_l1(21_000_001), lock: None });
+        assert!(spec.validate().is_err());
+
+        let mut spec = ChainSpec::default();
+        spec.consensus.retarget_interval = 1;
+        assert!(spec.validate().is_err());
+
+        let mut spec = ChainSpec::default();
+        spec.economics.halving_interval = Some(0);
+        assert!(spec.validate().is_err());
+
+        let mut spec = ChainSpec::default();
+        spec.genesis.allocations.push(GenesisAllocation { address: "alice".to_string(), amount: Amount::from_l1(1), lock: None });
+        assert!(spec.validate().is_err());
+
+        let keys: Vec<String> = (0..3).map(|_| KeyPair::generate().public_key_hex()).collect();
+        let mut spec = ChainSpec::default();
+        spec.economics.treasury = Some(MultisigPolicy::new(1, keys.clone()).unwrap());
+        assert!(spec.validate().is_err());
+        spec.economics.treasury = Some(MultisigPolicy::new(2, keys).unwrap());
+        assert!(spec.validate().is_ok());
+
+        spec.economics.payout_authority = Some("not a key".to_string());
+        assert!(spec.validate().is_err());
+    }
+
+    #[test]
+    fn test_genesis_follows_spec() {
+        let address = KeyPair::generate().address();
+        let mut spec = ChainSpec { chain_id: "layer1-staging".to_string(), ..ChainSpec::default() };
+        spec.genesis.allocations.push(GenesisAllocation { address: address.clone(), amount: Amount::from_l1(500), lock: None });
+        let team = KeyPair::generate().address();
+        let lock = LockSchedule::at_height(100);
+        spec.genesis.allocations.push(GenesisAllocation { address: team.clone(), amount: Amount::from_l1(300), lock: Some(lock) });
+
+        let staging = Blockchain::with_spec(&spec);
+        let dev = Blockchain::with_spec(&ChainSpec::default());
+        assert_ne!(staging.chain[0].hash, dev.chain[0].hash);
+        assert_eq!(staging.chain[0].hash, Blockchain::with_spec(&spec).chain[0].hash);
+        assert_eq!(staging.get_balance(&address), Amount::from_l1(500));
+        assert_eq!(staging.ledger.circulating_supply(), Amount::from_l1(800));
+        assert_eq!(staging.ledger.spendable_balance(&team, 99, u64::MAX), Amount::ZERO);
+        assert_eq!(staging.ledger.spendable_balance(&team, 100, 0), Amount::from_l1(300));
+    }
+}
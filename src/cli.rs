@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use crate::config::{self, NodeConfig};
+
+pub const USAGE: &str = "\
+Usage: layer1 [COMMAND] [OPTIONS]
+
+Commands:
+  node run       Run the node (the default when no command is given)
+  node init      Create the data directory and genesis block, and write a config file
+  chain export   Write the stored blocks as JSON to --output, or stdout
+  chain spec     Print the chain spec the node would run with
+  help           Show this message
+
+Options:
+  --config <FILE>            Config file (default: $LAYER1_CONFIG, else ./layer1.json if present)
+  --data-dir <DIR>           Block log and state snapshot directory
+  --chain-spec <FILE>        Chain spec to run; the built-in dev chain when unset
+  --listen <ADDR>            HTTP API bind address, e.g. 0.0.0.0:3030
+  --http-port <PORT>         HTTP API port, keeping the bind host
+  --p2p-listen <ADDR>        Peer-to-peer bind address, e.g. 0.0.0.0:3031
+  --p2p-port <PORT>          Peer-to-peer port, keeping the bind host
+  --peers <LIST>             Comma-separated host:port peers to keep connected to
+  --cors-origins <LIST>      Comma-separated origins allowed to call the API; any when empty
+  --reward-interval <SECS>   Seconds between connection reward rounds
+  --cleanup-interval <SECS>  Seconds between pool, security and social mining cleanups
+  --payout-key-file <FILE>   Secret key of the chain spec's payout authority, for signing payouts
+  --force                    node init: overwrite an existing config file
+  --output <FILE>            chain export: write here instead of stdout
+
+Settings are read from the config file, then LAYER1_* environment variables
+(LAYER1_DATA_DIR, LAYER1_LISTEN, LAYER1_PEERS, ...), then flags; later wins.";
+
+/// What the binary was asked to do
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    NodeRun(Options),
+    NodeInit { options: Options, force: bool },
+    ChainExport { options: Options, output: Option<String> },
+    ChainSpec(Options),
+    Help,
+}
+
+/// Flags every command accepts
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub config: Option<String>,
+    /// Settings given on the command line, as (key, value) in the order they appeared
+    pub overrides: Vec<(String, String)>,
+}
+
+impl Options {
+    /// The config file this invocation reads and `node init` writes
+    pub fn config_path(&self) -> Option<String> {
+        self.config.clone().or_else(|| std::env::var("LAYER1_CONFIG").ok())
+    }
+
+    /// Defaults, then the config file, then LAYER1_* variables, then command-line flags
+    pub fn resolve(&self) -> Result<NodeConfig, String> {
+        let mut node_config = match self.config_path() {
+            Some(path) => NodeConfig::load(path)?,
+            None if Path::new(config::DEFAULT_CONFIG_FILE).exists() => NodeConfig::load(config::DEFAULT_CONFIG_FILE)?,
+            None => NodeConfig::default(),
+        };
+        node_config.apply_env(|name| std::env::var(name).ok())?;
+        for (key, value) in &self.overrides {
+            node_config.set(key, value).map_err(|e| format!("--{}: {}", key, e))?;
+        }
+        node_config.validate()?;
+        Ok(node_config)
+    }
+}
+
+/// Parse the arguments after the program name
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let words: Vec<&str> = args.iter()
+        .take_while(|arg| !arg.starts_with("--"))
+        .map(String::as_str)
+        .collect();
+    let mut flags = args[words.len()..].iter();
+
+    let mut options = Options::default();
+    let mut force = false;
+    let mut output = None;
+    let mut help = false;
+    while let Some(flag) = flags.next() {
+        let Some(name) = flag.strip_prefix("--") else {
+            return Err(format!("Unexpected argument {}", flag));
+        };
+        let (name, inline_value) = match name.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (name, None),
+        };
+        match name {
+            "force" => force = true,
+            "help" => help = true,
+            _ => {
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => flags.next().cloned().ok_or_else(|| format!("--{} needs a value", name))?,
+                };
+                match name {
+                    "config" => options.config = Some(value),
+                    "output" => output = Some(value),
+                    _ if config::KEYS.contains(&name) => options.overrides.push((name.to_string(), value)),
+                    _ => return Err(format!("Unknown option --{}", name)),
+                }
+            }
+        }
+    }
+
+    if help || words == ["help"] {
+        return Ok(Command::Help);
+    }
+    let command = match words.as_slice() {
+        [] | ["node"] | ["node", "run"] => Command::NodeRun(options),
+        ["node", "init"] => return Ok(Command::NodeInit { options, force }),
+        ["chain", "export"] => return Ok(Command::ChainExport { options, output }),
+        ["chain", "spec"] => Command::ChainSpec(options),
+        _ => return Err(format!("Unknown command: {}", words.join(" "))),
+    };
+    if force {
+        return Err("--force only applies to node init".to_string());
+    }
+    if output.is_some() {
+        return Err("--output only applies to chain export".to_string());
+    }
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_no_arguments_runs_the_node() {
+        assert_eq!(parse(&[]).unwrap(), Command::NodeRun(Options::default()));
+    }
+
+    #[test]
+    fn test_flags_parse_in_either_form() {
+        let command = parse(&args("node run --config node.json --listen=127.0.0.1:8080 --peers a:1,b:2")).unwrap();
+        let Command::NodeRun(options) = command else { panic!("expected node run") };
+        assert_eq!(options.config.as_deref(), Some("node.json"));
+        assert_eq!(options.overrides, vec![
+            ("listen".to_string(), "127.0.0.1:8080".to_string()),
+            ("peers".to_string(), "a:1,b:2".to_string()),
+        ]);
+
+        let command = parse(&args("chain export --data-dir /tmp/chain --output blocks.json")).unwrap();
+        let Command::ChainExport { options, output } = command else { panic!("expected chain export") };
+        assert_eq!(options.overrides, vec![("data-dir".to_string(), "/tmp/chain".to_string())]);
+        assert_eq!(output.as_deref(), Some("blocks.json"));
+    }
+
+    #[test]
+    fn test_bad_arguments_rejected() {
+        assert!(parse(&args("node start")).is_err());
+        assert!(parse(&args("node run --bogus 1")).is_err());
+        assert!(parse(&args("node run --listen")).is_err());
+        assert!(parse(&args("node run --force")).is_err());
+        assert!(parse(&args("chain export --output out.json stray")).is_err());
+        assert_eq!(parse(&args("node init --help")).unwrap(), Command::Help);
+    }
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Config file a node reads from the working directory when no other is named
+pub const DEFAULT_CONFIG_FILE: &str = "layer1.json";
+
+/// Environment variables and the config keys they set
+const ENV_KEYS: [(&str, &str); 11] = [
+    ("LAYER1_DATA_DIR", "data-dir"),
+    ("LAYER1_CHAIN_SPEC", "chain-spec"),
+    ("LAYER1_LISTEN", "listen"),
+    ("LAYER1_HTTP_PORT", "http-port"),
+    ("LAYER1_P2P_LISTEN", "p2p-listen"),
+    ("LAYER1_P2P_PORT", "p2p-port"),
+    ("LAYER1_PEERS", "peers"),
+    ("LAYER1_CORS_ORIGINS", "cors-origins"),
+    ("LAYER1_REWARD_INTERVAL_SECS", "reward-interval"),
+    ("LAYER1_CLEANUP_INTERVAL_SECS", "cleanup-interval"),
+    ("LAYER1_PAYOUT_KEY_FILE", "payout-key-file"),
+];
+
+/// Keys accepted by `NodeConfig::set`, and as `--key value` flags on the command line
+pub const KEYS: [&str; 11] = [
+    "data-dir", "chain-spec", "listen", "http-port", "p2p-listen",
+    "p2p-port", "peers", "cors-origins", "reward-interval", "cleanup-interval",
+    "payout-key-file",
+];
+
+/// Node settings: built-in defaults, overlaid by a config file, environment variables and flags
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeConfig {
+    /// Where the block log and state snapshot live
+    pub data_dir: String,
+    /// Chain spec file; the built-in dev chain when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_spec: Option<String>,
+    /// HTTP API bind address
+    pub listen: SocketAddr,
+    /// Peer-to-peer bind address
+    pub p2p_listen: SocketAddr,
+    /// Static peers to keep connected to, as host:port
+    pub peers: Vec<String>,
+    /// Origins allowed to call the API from a browser; any origin when empty
+    pub cors_origins: Vec<String>,
+    /// Seconds between connection reward rounds
+    pub reward_interval_secs: u64,
+    /// Seconds between pool, security and social mining cleanups
+    pub cleanup_interval_secs: u64,
+    /// File holding the hex secret key of the chain spec's payout authority; without it the node
+    /// makes no protocol payouts on a chain that names one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payout_key_file: Option<String>,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: "data".to_string(),
+            chain_spec: None,
+            listen: SocketAddr::from(([0, 0, 0, 0], 3030)),
+            p2p_listen: SocketAddr::from(([0, 0, 0, 0], 3031)),
+            peers: Vec::new(),
+            cors_origins: Vec::new(),
+            reward_interval_secs: 30,
+            cleanup_interval_secs: 300,
+            payout_key_file: None,
+        }
+    }
+}
+
+impl NodeConfig {
+    /// Read a JSON config file; keys it leaves out keep their defaults
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+        serde_json::from_str(&data).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let data = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+        fs::write(path, data + "\n").map_err(|e| format!("Failed to write config {}: {}", path.display(), e))
+    }
+
+    /// Overlay the LAYER1_* variables that `var` finds
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        for (name, key) in ENV_KEYS {
+            if let Some(value) = var(name) {
+                self.set(key, &value).map_err(|e| format!("{}: {}", name, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set one setting by key; lists are comma-separated
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "data-dir" => self.data_dir = value.to_string(),
+            "chain-spec" => self.chain_spec = Some(value.to_string()).filter(|path| !path.is_empty()),
+            "listen" => self.listen = parse(key, value)?,
+            "http-port" => self.listen.set_port(parse(key, value)?),
+            "p2p-listen" => self.p2p_listen = parse(key, value)?,
+            "p2p-port" => self.p2p_listen.set_port(parse(key, value)?),
+            "peers" => self.peers = split_list(value),
+            "cors-origins" => self.cors_origins = split_list(value),
+            "reward-interval" => self.reward_interval_secs = parse(key, value)?,
+            "cleanup-interval" => self.cleanup_interval_secs = parse(key, value)?,
+            "payout-key-file" => self.payout_key_file = Some(value.to_string()).filter(|path| !path.is_empty()),
+            _ => return Err(format!("Unknown setting {}", key)),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.data_dir.trim().is_empty() {
+            return Err("data_dir must not be empty".to_string());
+        }
+        if self.reward_interval_secs == 0 || self.cleanup_interval_secs == 0 {
+            return Err("Background task intervals must be at least one second".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    value.trim().parse().map_err(|e| format!("Invalid {} {:?}: {}", key, value, e))
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_file_keeps_defaults_for_missing_keys() {
+        let path = std::env::temp_dir().join(format!("layer1-config-{}.json", uuid::Uuid::new_v4()));
+        fs::write(&path, r#"{ "data_dir": "/var/lib/layer1", "peers": ["10.0.0.2:3031"] }"#).unwrap();
+
+        let config = NodeConfig::load(&path).unwrap();
+        assert_eq!(config.data_dir, "/var/lib/layer1");
+        assert_eq!(config.peers, vec!["10.0.0.2:3031"]);
+        assert_eq!(config.listen, NodeConfig::default().listen);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_env_overlays_config() {
+        let env: HashMap<&str, &str> = HashMap::from([
+            ("LAYER1_LISTEN", "127.0.0.1:8080"),
+            ("LAYER1_P2P_PORT", "4041"),
+            ("LAYER1_CORS_ORIGINS", "https://app.example, https://admin.example"),
+            ("LAYER1_REWARD_INTERVAL_SECS", "60"),
+        ]);
+        let mut config = NodeConfig::default();
+        config.apply_env(|name| env.get(name).map(|value| value.to_string())).unwrap();
+
+        assert_eq!(config.listen, "127.0.0.1:8080".parse().unwrap());
+        assert_eq!(config.p2p_listen.port(), 4041);
+        assert_eq!(config.cors_origins, vec!["https://app.example", "https://admin.example"]);
+        assert_eq!(config.reward_interval_secs, 60);
+        assert_eq!(config.cleanup_interval_secs, 300);
+    }
+
+    #[test]
+    fn test_bad_values_rejected() {
+        let mut config = NodeConfig::default();
+        assert!(config.set("listen", "not an address").is_err());
+        assert!(config.set("bogus", "1").is_err());
+        assert!(config.apply_env(|name| (name == "LAYER1_HTTP_PORT").then(|| "99999".to_string())).is_err());
+
+        config.set("cleanup-interval", "0").unwrap();
+        assert!(config.validate().is_err());
+    }
+}
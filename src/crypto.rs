@@ -0,0 +1,311 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const ADDRESS_PREFIX: &str = "L1";
+const ADDRESS_HASH_LEN: usize = 20;
+const ADDRESS_CHECKSUM_LEN: usize = 4;
+
+/// Most keys a multisig account may have
+pub const MAX_MULTISIG_KEYS: usize = 16;
+
+/// Accounts that mint or pay out on behalf of the protocol and never sign
+const SYSTEM_ADDRESSES: [&str; 7] = [
+    "genesis",
+    "mining_reward",
+    "connection_reward",
+    "social_mining",
+    "system",
+    "social_system",
+    "token_launch_fees",
+];
+
+/// Ed25519 keypair backing an account
+pub struct KeyPair {
+    signing_key: SigningKey,
+}
+
+impl KeyPair {
+    pub fn generate() -> Self {
+        Self {
+            signing_key: SigningKey::from_bytes(&rand::random::<[u8; 32]>()),
+        }
+    }
+
+    pub fn from_secret_hex(secret_hex: &str) -> Result<Self, String> {
+        let bytes: [u8; 32] = hex::decode(secret_hex)
+            .map_err(|_| "Secret key is not valid hex".to_string())?
+            .try_into()
+            .map_err(|_| "Secret key must be 32 bytes".to_string())?;
+        Ok(Self::from_secret_bytes(&bytes))
+    }
+
+    pub fn from_secret_bytes(secret: &[u8; 32]) -> Self {
+        Self {
+            signing_key: SigningKey::from_bytes(secret),
+        }
+    }
+
+    pub fn secret_key_hex(&self) -> String {
+        hex::encode(self.signing_key.to_bytes())
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.signing_key.verifying_key().to_bytes())
+    }
+
+    /// Address controlled by this keypair
+    pub fn address(&self) -> String {
+        address_from_key_bytes(&self.signing_key.verifying_key().to_bytes())
+    }
+
+    /// Sign a message, returning the hex-encoded signature
+    pub fn sign(&self, message: &[u8]) -> String {
+        hex::encode(self.signing_key.sign(message).to_bytes())
+    }
+}
+
+/// Signature fields a client attaches to a transfer request
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TransferAuth {
+    pub timestamp: Option<u64>,
+    pub nonce: Option<u64>,
+    pub public_key: Option<String>,
+    pub signature: Option<String>,
+}
+
+/// M-of-N spending rule of a multisig account; its address is derived from the policy itself,
+/// so a transfer can be checked against the policy it carries without any chain state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultisigPolicy {
+    pub threshold: usize,
+    /// Members' Ed25519 public keys (hex)
+    pub public_keys: Vec<String>,
+}
+
+/// One member's signature on a multisig transfer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultisigSignature {
+    pub public_key: String,
+    pub signature: String,
+}
+
+/// What a transfer from a multisig account carries instead of a single signature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultisigAuth {
+    pub policy: MultisigPolicy,
+    pub signatures: Vec<MultisigSignature>,
+}
+
+impl MultisigPolicy {
+    /// Policy with its keys in canonical (sorted) order, so the same members give the same address
+    pub fn new(threshold: usize, mut public_keys: Vec<String>) -> Result<Self, String> {
+        public_keys.sort();
+        let policy = Self { threshold, public_keys };
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let key_count = self.public_keys.len();
+        if key_count == 0 || key_count > MAX_MULTISIG_KEYS {
+            return Err(format!("A multisig account has 1 to {} keys", MAX_MULTISIG_KEYS));
+        }
+        if self.threshold == 0 || self.threshold > key_count {
+            return Err(format!("Threshold must be between 1 and {}", key_count));
+        }
+        for (i, key) in self.public_keys.iter().enumerate() {
+            parse_public_key(key)?;
+            if self.public_keys[..i].contains(key) {
+                return Err(format!("Key {} appears twice", key));
+            }
+        }
+        Ok(())
+    }
+
+    /// Same format as a single-key address, hashed over "multisig:<threshold>:<key>,<key>,..."
+    pub fn address(&self) -> String {
+        address_from_key_bytes(format!("multisig:{}:{}", self.threshold, self.public_keys.join(",")).as_bytes())
+    }
+
+    /// Check one member's signature of `message`
+    pub fn verify_member(&self, signature: &MultisigSignature, message: &[u8]) -> Result<(), String> {
+        if !self.public_keys.contains(&signature.public_key) {
+            return Err(format!("{} is not a key of this multisig account", signature.public_key));
+        }
+        verify_signature(&signature.public_key, message, &signature.signature)
+    }
+}
+
+/// Whether the address is a protocol account that is exempt from signing
+pub fn is_system_address(address: &str) -> bool {
+    SYSTEM_ADDRESSES.contains(&address) || address.starts_with("token_pool_")
+}
+
+// Address = "L1" + hex(first 20 bytes of SHA-256(public key)) + hex(4-byte checksum)
+fn address_from_key_bytes(public_key: &[u8]) -> String {
+    let digest = Sha256::digest(public_key);
+    let body = hex::encode(&digest[..ADDRESS_HASH_LEN]);
+    format!("{}{}{}", ADDRESS_PREFIX, body, address_checksum(&body))
+}
+
+fn address_checksum(body: &str) -> String {
+    let digest = Sha256::digest(Sha256::digest(format!("{}{}", ADDRESS_PREFIX, body)));
+    hex::encode(&digest[..ADDRESS_CHECKSUM_LEN])
+}
+
+/// Check that an address is a well-formed account address with a valid checksum
+pub fn validate_address(address: &str) -> Result<(), String> {
+    let body = address.strip_prefix(ADDRESS_PREFIX)
+        .ok_or_else(|| format!("Address must start with {}", ADDRESS_PREFIX))?;
+
+    let expected_len = (ADDRESS_HASH_LEN + ADDRESS_CHECKSUM_LEN) * 2;
+    if body.len() != expected_len || !body.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+        return Err("Malformed address".to_string());
+    }
+
+    let (hash, checksum) = body.split_at(ADDRESS_HASH_LEN * 2);
+    if address_checksum(hash) != checksum {
+        return Err("Address checksum mismatch".to_string());
+    }
+
+    Ok(())
+}
+
+/// Derive the account address for a hex-encoded public key
+pub fn address_from_public_key(public_key_hex: &str) -> Result<String, String> {
+    let key = parse_public_key(public_key_hex)?;
+    Ok(address_from_key_bytes(&key.to_bytes()))
+}
+
+fn parse_public_key(public_key_hex: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hex::decode(public_key_hex)
+        .map_err(|_| "Public key is not valid hex".to_string())?
+        .try_into()
+        .map_err(|_| "Public key must be 32 bytes".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| "Invalid public key".to_string())
+}
+
+/// Verify that `signature_hex` is a valid signature of `message` by `public_key_hex`
+pub fn verify_signature(public_key_hex: &str, message: &[u8], signature_hex: &str) -> Result<(), String> {
+    let key = parse_public_key(public_key_hex)?;
+    let bytes: [u8; 64] = hex::decode(signature_hex)
+        .map_err(|_| "Signature is not valid hex".to_string())?
+        .try_into()
+        .map_err(|_| "Signature must be 64 bytes".to_string())?;
+    key.verify(message, &Signature::from_bytes(&bytes))
+        .map_err(|_| "Invalid signature".to_string())
+}
+
+/// Verify that `from` is controlled by `public_key_hex` and that it signed `message`
+pub fn verify_sender(
+    from: &str,
+    public_key: Option<&str>,
+    signature: &str,
+    message: &[u8],
+) -> Result<(), String> {
+    let public_key = public_key
+        .ok_or_else(|| format!("Transfers from {} must be signed", from))?;
+    if address_from_public_key(public_key)? != from {
+        return Err("Public key does not match the sending address".to_string());
+    }
+    verify_signature(public_key, message, signature)
+}
+
+/// Verify that `auth` is the policy behind `from` and that at least its threshold of distinct members signed `message`
+pub fn verify_multisig(from: &str, auth: &MultisigAuth, message: &[u8]) -> Result<(), String> {
+    let policy = &auth.policy;
+    policy.validate()?;
+    if policy.address() != from {
+        return Err("Multisig policy does not match the sending address".to_string());
+    }
+    let mut signers: Vec<&str> = Vec::new();
+    for signature in &auth.signatures {
+        if signers.contains(&signature.public_key.as_str()) {
+            return Err(format!("{} signed more than once", signature.public_key));
+        }
+        policy.verify_member(signature, message)?;
+        signers.push(&signature.public_key);
+    }
+    if signers.len() < policy.threshold {
+        return Err(format!("{} of {} required signatures", signers.len(), policy.threshold));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keys = KeyPair::generate();
+        let signature = keys.sign(b"hello");
+
+        assert!(verify_signature(&keys.public_key_hex(), b"hello", &signature).is_ok());
+        assert!(verify_signature(&keys.public_key_hex(), b"hellO", &signature).is_err());
+    }
+
+    #[test]
+    fn test_verify_sender_requires_matching_address() {
+        let alice = KeyPair::generate();
+        let mallory = KeyPair::generate();
+        let signature = mallory.sign(b"payload");
+
+        assert!(verify_sender(&alice.address(), Some(&mallory.public_key_hex()), &signature, b"payload").is_err());
+        assert!(verify_sender(&mallory.address(), Some(&mallory.public_key_hex()), &signature, b"payload").is_ok());
+        assert!(verify_sender(&mallory.address(), None, &signature, b"payload").is_err());
+    }
+
+    #[test]
+    fn test_secret_key_roundtrip() {
+        let keys = KeyPair::generate();
+        let restored = KeyPair::from_secret_hex(&keys.secret_key_hex()).unwrap();
+        assert_eq!(keys.address(), restored.address());
+    }
+
+    #[test]
+    fn test_address_checksum() {
+        let keys = KeyPair::generate();
+        let address = keys.address();
+        assert!(validate_address(&address).is_ok());
+        assert_eq!(address_from_public_key(&keys.public_key_hex()).unwrap(), address);
+
+        // A single mistyped character is caught by the checksum
+        let mut typo: Vec<char> = address.chars().collect();
+        typo[10] = if typo[10] == 'a' { 'b' } else { 'a' };
+        let typo: String = typo.into_iter().collect();
+        assert!(validate_address(&typo).is_err());
+
+        assert!(validate_address("wallet_alice").is_err());
+        assert!(validate_address(&address.to_uppercase()).is_err());
+        assert!(validate_address(&address[..address.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_multisig_threshold() {
+        let members: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate()).collect();
+        let keys: Vec<String> = members.iter().map(KeyPair::public_key_hex).collect();
+        let policy = MultisigPolicy::new(2, keys.clone()).unwrap();
+        let address = policy.address();
+        assert!(validate_address(&address).is_ok());
+
+        // Key order does not change the account
+        let reversed: Vec<String> = keys.iter().rev().cloned().collect();
+        assert_eq!(MultisigPolicy::new(2, reversed).unwrap().address(), address);
+        assert_ne!(MultisigPolicy::new(3, keys.clone()).unwrap().address(), address);
+
+        let sign = |keys: &KeyPair| MultisigSignature { public_key: keys.public_key_hex(), signature: keys.sign(b"payload") };
+        let auth = |signatures: Vec<MultisigSignature>| MultisigAuth { policy: policy.clone(), signatures };
+
+        assert!(verify_multisig(&address, &auth(vec![sign(&members[0]), sign(&members[2])]), b"payload").is_ok());
+        assert!(verify_multisig(&address, &auth(vec![sign(&members[0])]), b"payload").is_err());
+        assert!(verify_multisig(&address, &auth(vec![sign(&members[0]), sign(&members[0])]), b"payload").is_err());
+        assert!(verify_multisig(&address, &auth(vec![sign(&members[0]), sign(&KeyPair::generate())]), b"payload").is_err());
+        assert!(verify_multisig(&members[0].address(), &auth(vec![sign(&members[0]), sign(&members[1])]), b"payload").is_err());
+
+        assert!(MultisigPolicy::new(0, keys.clone()).is_err());
+        assert!(MultisigPolicy::new(4, keys.clone()).is_err());
+        assert!(MultisigPolicy::new(2, vec![keys[0].clone(), keys[0].clone()]).is_err());
+    }
+}
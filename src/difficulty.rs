@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Block;
+
+/// Leading hex zeros required of blocks mined before difficulty was recorded per block
+const LEGACY_HEX_ZEROS: usize = 2;
+
+/// Expected hashes for a legacy block: one in 16^2
+const LEGACY_WORK: u128 = 256;
+
+/// How block difficulty tracks the target block interval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetargetParams {
+    /// Desired seconds between blocks
+    pub target_block_time: u64,
+    /// Difficulty is recomputed every this many blocks
+    pub retarget_interval: u64,
+    /// Difficulty of the genesis block and the first retarget window
+    pub initial_difficulty: u64,
+    /// Largest factor difficulty may move by in one retarget
+    pub max_adjustment: u64,
+}
+
+impl Default for RetargetParams {
+    fn default() -> Self {
+        Self {
+            target_block_time: 10,
+            retarget_interval: 10,
+            initial_difficulty: 256, // Same work as the old two-hex-zero rule
+            max_adjustment: 4,
+        }
+    }
+}
+
+// 256-bit target for a difficulty, big-endian: roughly 2^256 / difficulty
+fn target_bytes(difficulty: u64) -> [u8; 32] {
+    let mut target = [0xff; 32];
+    target[..8].copy_from_slice(&(u64::MAX / difficulty.max(1)).to_be_bytes());
+    target
+}
+
+/// Whether a hex block hash is at or below the target for `difficulty`
+pub fn meets_target(hash_hex: &str, difficulty: u64) -> bool {
+    match hex::decode(hash_hex) {
+        Ok(hash) if hash.len() == 32 => hash.as_slice() <= target_bytes(difficulty).as_slice(),
+        _ => false,
+    }
+}
+
+/// Proof-of-work check for a block mined before difficulty was recorded in the header
+pub fn meets_legacy_target(hash_hex: &str) -> bool {
+    hash_hex.len() >= LEGACY_HEX_ZEROS && hash_hex[..LEGACY_HEX_ZEROS].bytes().all(|b| b == b'0')
+}
+
+/// Expected number of hashes behind a block; fork choice follows the branch with the most
+pub fn block_work(difficulty: u64) -> u128 {
+    if difficulty == 0 { LEGACY_WORK } else { difficulty as u128 }
+}
+
+/// Difficulty the block following `chain` must be mined at
+pub fn next_difficulty(chain: &[Block], params: &RetargetParams) -> u64 {
+    let Some(last) = chain.last() else {
+        return params.initial_difficulty;
+    };
+    let current = if last.difficulty == 0 { params.initial_difficulty } else { last.difficulty };
+
+    let interval = params.retarget_interval.max(2);
+    if (last.index + 1) % interval != 0 || (chain.len() as u64) < interval {
+        return current;
+    }
+
+    // Compare how long the last window took against how long it should have taken
+    let first = &chain[chain.len() - interval as usize];
+    let expected = params.target_block_time.saturating_mul(interval - 1).max(1);
+    let actual = last.timestamp.saturating_sub(first.timestamp).max(1);
+    let next = current as u128 * expected as u128 / actual as u128;
+
+    let max_adjustment = params.max_adjustment.max(1) as u128;
+    next.clamp(current as u128 / max_adjustment, current as u128 * max_adjustment)
+        .clamp(1, u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain_with_spacing(len: u64, spacing: u64, difficulty: u64) -> Vec<Block> {
+        (0..len)
+            .map(|index| {
+                let mut block = Block::new(index, Vec::new(), "0".to_string(), "miner".to_string());
+                block.timestamp = index * spacing;
+                block.difficulty = difficulty;
+                block
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_target_comparison() {
+        let easy = "7".repeat(64);
+        assert!(meets_target(&easy, 1));
+        assert!(!meets_target(&easy, 4));
+        assert!(meets_target(&format!("00{}", "f".repeat(62)), 256));
+        assert!(!meets_target(&format!("01{}", "0".repeat(62)), 256));
+        assert!(!meets_target("not hex", 1));
+    }
+
+    #[test]
+    fn test_difficulty_holds_between_retargets() {
+        let params = RetargetParams::default();
+        assert_eq!(next_difficulty(&[], &params), params.initial_difficulty);
+        assert_eq!(next_difficulty(&chain_with_spacing(5, 1, 300), &params), 300);
+    }
+
+    #[test]
+    fn test_retarget_follows_block_time() {
+        let params = RetargetParams::default();
+
+        // On schedule: unchanged
+        assert_eq!(next_difficulty(&chain_with_spacing(10, 10, 1000), &params), 1000);
+        // Twice as slow: halved
+        assert_eq!(next_difficulty(&chain_with_spacing(10, 20, 1000), &params), 500);
+        // Blocks in the same second: capped at the maximum adjustment
+        assert_eq!(next_difficulty(&chain_with_spacing(10, 0, 1000), &params), 4000);
+    }
+}
@@ -0,0 +1,174 @@
+use serde::Serialize;
+
+use crate::amount::Amount;
+use crate::chain_spec::EconomicParams;
+
+/// Halving eras listed by GET /rpc/emission
+pub const PROJECTED_ERAS: usize = 8;
+
+/// How new coins enter circulation. The block subsidy halves every `halving_interval` blocks,
+/// and nothing mining, connection or social rewards pay out may take issuance past `max_supply`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmissionSchedule {
+    pub max_supply: Amount,
+    /// Subsidy of the blocks in the first era
+    pub initial_subsidy: Amount,
+    pub halving_interval: u64,
+}
+
+/// One halving era as GET /rpc/emission projects it, assuming only mining issues from now on
+#[derive(Debug, Clone, Serialize)]
+pub struct EraProjection {
+    pub era: u64,
+    pub start_height: u64,
+    pub end_height: u64,
+    pub block_subsidy: Amount,
+    /// Supply issued once the era's last block is mined
+    pub issued_at_end: Amount,
+}
+
+impl EmissionSchedule {
+    /// Without an explicit interval, halvings are spaced so mining alone would approach the cap:
+    /// the eras sum to 2 * initial_subsidy * halving_interval = max_supply
+    pub fn new(params: &EconomicParams) -> Self {
+        let derived = params.max_supply.base_units() / params.mining_reward.base_units().saturating_mul(2).max(1);
+        Self {
+            max_supply: params.max_supply,
+            initial_subsidy: params.mining_reward,
+            halving_interval: params.halving_interval
+                .unwrap_or_else(|| u64::try_from(derived).unwrap_or(u64::MAX))
+                .max(1),
+        }
+    }
+
+    pub fn era(&self, height: u64) -> u64 {
+        height / self.halving_interval
+    }
+
+    /// Subsidy the schedule gives the block at `height`, before the supply cap
+    pub fn block_subsidy(&self, height: u64) -> Amount {
+        let era = self.era(height);
+        if era >= u128::BITS as u64 {
+            return Amount::ZERO;
+        }
+        Amount::from_base_units(self.initial_subsidy.base_units() >> era)
+    }
+
+    pub fn next_halving_height(&self, height: u64) -> u64 {
+        (self.era(height) + 1).saturating_mul(self.halving_interval)
+    }
+
+    /// What is left to issue once `issued` has been
+    pub fn remaining(&self, issued: Amount) -> Amount {
+        self.max_supply.saturating_sub(issued)
+    }
+
+    /// Reward per minute for staying connected; decays exponentially as the supply is used up
+    pub fn connection_reward(&self, issued: Amount) -> Amount {
+        let remaining_percentage = self.remaining(issued).to_f64() / self.max_supply.to_f64();
+        let base_reward = 1.0; // Base reward per minute
+
+        // Exponential decay: reward = base * e^(-decay_rate * supply_used)
+        let decay_rate = 2.0;
+        let supply_used_percentage = 1.0 - remaining_percentage;
+        let reward = base_reward * (-decay_rate * supply_used_percentage).exp();
+
+        // Minimum reward of 0.001, and never more than is left
+        Amount::from_f64(reward.max(0.001))
+            .unwrap_or(Amount::from_base_units(100_000))
+            .min(self.remaining(issued))
+    }
+
+    /// The `eras` halving eras from the one containing `height`, mining every block on top of `issued`
+    pub fn projection(&self, height: u64, issued: Amount, eras: usize) -> Vec<EraProjection> {
+        let mut issued = issued;
+        let mut start_height = height;
+        let mut projection = Vec::with_capacity(eras);
+        for _ in 0..eras {
+            let era = self.era(start_height);
+            let end_height = self.next_halving_height(start_height).saturating_sub(1);
+            let block_subsidy = self.block_subsidy(start_height);
+            let blocks = u128::from(end_height - start_height + 1);
+            let mined = block_subsidy.checked_mul(blocks).unwrap_or(Amount::MAX);
+            issued = issued.saturating_add(mined.min(self.remaining(issued)));
+
+            projection.push(EraProjection { era, start_height, end_height, block_subsidy, issued_at_end: issued });
+            if end_height == u64::MAX {
+                break;
+            }
+            start_height = end_height + 1;
+        }
+        projection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain_spec::ChainSpec;
+    use crate::Blockchain;
+
+    fn schedule(max_supply: u64, mining_reward: u64, halving_interval: Option<u64>) -> EmissionSchedule {
+        EmissionSchedule::new(&EconomicParams {
+            max_supply: Amount::from_l1(max_supply),
+            mining_reward: Amount::from_l1(mining_reward),
+            halving_interval,
+            ..EconomicParams::default()
+        })
+    }
+
+    #[test]
+    fn test_subsidy_halves() {
+        let default = EmissionSchedule::new(&EconomicParams::default());
+        assert_eq!(default.halving_interval, 1_050_000);
+
+        let schedule = schedule(1_000, 10, Some(50));
+        assert_eq!(schedule.block_subsidy(0), Amount::from_l1(10));
+        assert_eq!(schedule.block_subsidy(49), Amount::from_l1(10));
+        assert_eq!(schedule.block_subsidy(50), Amount::from_l1(5));
+        assert_eq!(schedule.block_subsidy(120), Amount::from_base_units(250_000_000));
+        assert_eq!(schedule.block_subsidy(u64::MAX), Amount::ZERO);
+        assert_eq!(schedule.next_halving_height(50), 100);
+    }
+
+    #[test]
+    fn test_projection_stops_at_cap() {
+        // Derived interval: 1000 / (2 * 10) = 50 blocks
+        let schedule = schedule(1_000, 10, None);
+        let projection = schedule.projection(25, Amount::from_l1(100), 3);
+        assert_eq!(projection[0].start_height, 25);
+        assert_eq!(projection[0].end_height, 49);
+        assert_eq!(projection[0].issued_at_end, Amount::from_l1(350));
+        assert_eq!(projection[1].block_subsidy, Amount::from_l1(5));
+        assert_eq!(projection[1].issued_at_end, Amount::from_l1(600));
+        assert_eq!(projection[2].issued_at_end, Amount::from_l1(725));
+
+        // Issuance from other sources leaves less for mining
+        let projection = schedule.projection(0, Amount::from_l1(900), 2);
+        assert_eq!(projection[0].issued_at_end, Amount::from_l1(1_000));
+        assert_eq!(projection[1].issued_at_end, Amount::from_l1(1_000));
+        assert_eq!(schedule.connection_reward(Amount::from_l1(1_000)), Amount::ZERO);
+        assert!(schedule.connection_reward(Amount::ZERO) > schedule.connection_reward(Amount::from_l1(500)));
+    }
+
+    #[test]
+    fn test_issuance_never_passes_cap() {
+        let mut spec = ChainSpec::default();
+        spec.economics.max_supply = Amount::from_l1(25);
+        spec.economics.halving_interval = Some(100);
+        let mut blockchain = Blockchain::with_spec(&spec);
+
+        blockchain.create_system_transaction("social_mining", "alice".to_string(), Amount::from_l1(5)).unwrap();
+        blockchain.mine_pending_transactions("miner".to_string());
+        assert_eq!(blockchain.get_balance("miner"), Amount::from_l1(10));
+
+        // The subsidy shrinks to whatever the pool's pending rewards leave
+        blockchain.create_system_transaction("social_mining", "alice".to_string(), Amount::from_l1(5)).unwrap();
+        assert!(blockchain.create_system_transaction("social_mining", "alice".to_string(), Amount::from_l1(6)).is_err());
+        blockchain.mine_pending_transactions("miner".to_string());
+        assert_eq!(blockchain.get_balance("miner"), Amount::from_l1(15));
+        assert_eq!(blockchain.ledger.circulating_supply(), Amount::from_l1(25));
+        assert!(blockchain.create_system_transaction("social_mining", "alice".to_string(), Amount::from_base_units(1)).is_err());
+        assert!(blockchain.get_emission().block_subsidy.is_zero());
+    }
+}
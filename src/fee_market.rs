@@ -0,0 +1,153 @@
+use serde::Serialize;
+
+use crate::amount::Amount;
+use crate::mempool::MAX_BLOCK_TRANSACTIONS;
+use crate::Block;
+
+/// Base fee of the first block that records one, and the floor it never adjusts below (0.001 L1)
+pub const MIN_BASE_FEE: Amount = Amount::from_base_units(100_000);
+
+/// Pool transactions per block the base fee steers towards: half of what a block may hold
+pub const TARGET_BLOCK_TRANSACTIONS: usize = MAX_BLOCK_TRANSACTIONS / 2;
+
+/// Share of the base fee, in percent, each transaction burns; the rest of its fee goes to the miner
+pub const BASE_FEE_BURN_PERCENT: u128 = 50;
+
+/// The base fee moves by at most 1/8 from one block to the next
+const BASE_FEE_CHANGE_DENOMINATOR: u128 = 8;
+
+/// Recent blocks whose tips the fee estimate is drawn from
+const ESTIMATE_BLOCKS: usize = 20;
+
+/// Confirmation targets GET /rpc/fees/estimate quotes, in blocks, and the percentile of recent
+/// tips each one pays
+const ESTIMATE_TARGETS: [(u64, usize); 3] = [(1, 90), (3, 50), (6, 25)];
+
+/// Recommended fee for getting into one of the next `target_blocks` blocks
+#[derive(Debug, Clone, Serialize)]
+pub struct FeeEstimate {
+    pub target_blocks: u64,
+    pub estimated_seconds: u64,
+    /// Part of `fee` above the base fee, which goes to the miner in full
+    pub priority_fee: Amount,
+    pub fee: Amount,
+}
+
+/// Transactions in `block` that came from the pool, leaving out the miner's reward
+pub fn pool_transaction_count(block: &Block) -> usize {
+    block.transactions.iter().filter(|tx| tx.from != "mining_reward").count()
+}
+
+/// Base fee the block after `parent` must record. It rises when the parent held more than the
+/// target and falls when it held less; a parent from before the fee market starts it at the floor.
+pub fn next_base_fee(parent: &Block) -> Amount {
+    if parent.base_fee.is_zero() {
+        return MIN_BASE_FEE;
+    }
+
+    let used = pool_transaction_count(parent).min(MAX_BLOCK_TRANSACTIONS) as u128;
+    let target = TARGET_BLOCK_TRANSACTIONS as u128;
+    let base_fee = parent.base_fee;
+    let next = if used > target {
+        let delta = base_fee.mul_div(used - target, target * BASE_FEE_CHANGE_DENOMINATOR).unwrap_or(Amount::MAX);
+        base_fee.saturating_add(delta.max(Amount::from_base_units(1)))
+    } else {
+        let delta = base_fee.mul_div(target - used, target * BASE_FEE_CHANGE_DENOMINATOR).unwrap_or(Amount::ZERO);
+        base_fee.saturating_sub(delta)
+    };
+    next.max(MIN_BASE_FEE)
+}
+
+/// How a transaction's fee divides in a block with `base_fee`: what is burned, and what the miner gets
+pub fn split_fee(fee: Amount, base_fee: Amount) -> (Amount, Amount) {
+    let burned = fee.min(base_fee).mul_div(BASE_FEE_BURN_PERCENT, 100).unwrap_or(Amount::ZERO);
+    (burned, fee.saturating_sub(burned))
+}
+
+/// Fees for each confirmation target. A target pays the base fee plus a percentile of the tips
+/// recent blocks included, and at least enough to outbid the pending fees that would fill the
+/// blocks before it, and the pool's `min_fee`.
+pub fn estimate(recent: &[Block], base_fee: Amount, min_fee: Amount, pending_fees: &[Amount], target_block_time: u64) -> Vec<FeeEstimate> {
+    let mut tips: Vec<Amount> = recent.iter().rev()
+        .filter(|block| !block.base_fee.is_zero())
+        .take(ESTIMATE_BLOCKS)
+        .flat_map(|block| block.transactions.iter().filter_map(|tx| tx.fee.map(|fee| fee.saturating_sub(block.base_fee))))
+        .collect();
+    tips.sort_unstable();
+    let mut pending_fees = pending_fees.to_vec();
+    pending_fees.sort_unstable_by(|a, b| b.cmp(a));
+
+    ESTIMATE_TARGETS.iter().map(|&(target_blocks, percentile)| {
+        let tip = match tips.len() {
+            0 => Amount::ZERO,
+            len => tips[(len - 1) * percentile / 100],
+        };
+        let slots = target_blocks as usize * MAX_BLOCK_TRANSACTIONS;
+        let outbid = pending_fees.get(slots - 1)
+            .map_or(Amount::ZERO, |fee| fee.saturating_add(Amount::from_base_units(1)));
+        let fee = base_fee.saturating_add(tip).max(outbid).max(min_fee);
+        FeeEstimate {
+            target_blocks,
+            estimated_seconds: target_blocks * target_block_time,
+            priority_fee: fee.saturating_sub(base_fee),
+            fee,
+        }
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Transaction;
+
+    fn block_with(base_fee: Amount, fees: &[u128]) -> Block {
+        let transactions = fees.iter().enumerate().map(|(i, &fee)| Transaction {
+            from: format!("sender{}", i),
+            to: "bob".to_string(),
+            amount: Amount::from_l1(1),
+            fee: Some(Amount::from_base_units(fee)),
+            ..Default::default()
+        }).collect();
+        let mut block = Block::new(1, transactions, "0".to_string(), "miner".to_string());
+        block.base_fee = base_fee;
+        block
+    }
+
+    #[test]
+    fn test_base_fee_follows_block_fullness() {
+        let base = Amount::from_base_units(800_000);
+        assert_eq!(next_base_fee(&block_with(Amount::ZERO, &[])), MIN_BASE_FEE);
+
+        // At the target it holds; a full block raises it 1/8, an empty one lowers it 1/8
+        assert_eq!(next_base_fee(&block_with(base, &[base.base_units(); TARGET_BLOCK_TRANSACTIONS])), base);
+        assert_eq!(next_base_fee(&block_with(base, &[base.base_units(); MAX_BLOCK_TRANSACTIONS])), Amount::from_base_units(900_000));
+        assert_eq!(next_base_fee(&block_with(base, &[])), Amount::from_base_units(700_000));
+        assert_eq!(next_base_fee(&block_with(MIN_BASE_FEE, &[])), MIN_BASE_FEE);
+    }
+
+    #[test]
+    fn test_fee_split_and_estimate() {
+        let base = Amount::from_base_units(1_000);
+        assert_eq!(split_fee(Amount::from_base_units(1_500), base), (Amount::from_base_units(500), Amount::from_base_units(1_000)));
+        assert_eq!(split_fee(Amount::from_base_units(600), base), (Amount::from_base_units(300), Amount::from_base_units(300)));
+
+        // Tips of 0..=100 in recent blocks; quiet pool
+        let recent = vec![block_with(base, &(0..=100).map(|tip| 1_000 + tip).collect::<Vec<_>>())];
+        let estimates = estimate(&recent, base, base, &[], 30);
+        let priority: Vec<u128> = estimates.iter().map(|e| e.priority_fee.base_units()).collect();
+        assert_eq!(priority, vec![90, 50, 25]);
+        assert_eq!(estimates[1].fee, Amount::from_base_units(1_050));
+        assert_eq!(estimates[2].estimated_seconds, 180);
+
+        // A full block's worth of pending fees has to be outbid for the next block
+        let pending = vec![Amount::from_base_units(5_000); MAX_BLOCK_TRANSACTIONS];
+        let estimates = estimate(&recent, base, base, &pending, 30);
+        assert_eq!(estimates[0].fee, Amount::from_base_units(5_001));
+        assert_eq!(estimates[1].fee, Amount::from_base_units(1_050));
+
+        // A crowded pool's minimum fee is a floor for every target
+        let estimates = estimate(&recent, base, Amount::from_base_units(2_000), &[], 30);
+        assert_eq!(estimates[2].fee, Amount::from_base_units(2_000));
+        assert_eq!(estimates[2].priority_fee, Amount::from_base_units(1_000));
+    }
+}
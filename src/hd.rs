@@ -0,0 +1,143 @@
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+
+use crate::crypto::KeyPair;
+
+/// Coin type in the account path; not registered with SLIP-44
+pub const COIN_TYPE: u32 = 7011;
+
+/// Unused accounts in a row after which discovery stops, as in BIP-44
+pub const GAP_LIMIT: u32 = 20;
+
+const HARDENED: u32 = 0x8000_0000;
+
+/// Fresh English BIP-39 mnemonic of 12, 15, 18, 21 or 24 words
+pub fn generate_mnemonic(word_count: usize) -> Result<String, String> {
+    if !(12..=24).contains(&word_count) || !word_count.is_multiple_of(3) {
+        return Err("Mnemonics have 12, 15, 18, 21 or 24 words".to_string());
+    }
+    let entropy = rand::random::<[u8; 32]>();
+    let mnemonic = Mnemonic::from_entropy(&entropy[..word_count * 4 / 3])
+        .map_err(|e| format!("Failed to create mnemonic: {}", e))?;
+    Ok(mnemonic.to_string())
+}
+
+/// Derivation path of the account at `index`; every level is hardened, as SLIP-10 requires for Ed25519
+pub fn account_path(index: u32) -> String {
+    format!("m/44'/{}'/0'/0'/{}'", COIN_TYPE, index)
+}
+
+/// Key tree grown from a mnemonic seed: one seed, any number of accounts
+pub struct HdWallet {
+    seed: Vec<u8>,
+}
+
+impl HdWallet {
+    /// Check the mnemonic's words and checksum, then stretch it into the BIP-39 seed
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Self, String> {
+        let mnemonic = Mnemonic::parse(phrase.trim()).map_err(|e| format!("Invalid mnemonic: {}", e))?;
+        Ok(Self::from_seed(&mnemonic.to_seed(passphrase)))
+    }
+
+    pub fn from_seed(seed: &[u8]) -> Self {
+        Self { seed: seed.to_vec() }
+    }
+
+    /// Key at a SLIP-10 path given as indices; each one is hardened
+    pub fn derive(&self, path: &[u32]) -> KeyPair {
+        let (mut key, mut chain_code) = split(hmac_sha512(b"ed25519 seed", &self.seed));
+        for index in path {
+            let mut data = Vec::with_capacity(37);
+            data.push(0);
+            data.extend_from_slice(&key);
+            data.extend_from_slice(&(index | HARDENED).to_be_bytes());
+            (key, chain_code) = split(hmac_sha512(&chain_code, &data));
+        }
+        KeyPair::from_secret_bytes(&key)
+    }
+
+    /// Key of the account at `index`, at `account_path(index)`
+    pub fn account(&self, index: u32) -> KeyPair {
+        self.derive(&[44, COIN_TYPE, 0, 0, index])
+    }
+
+    /// How many accounts are in use. `is_used` is asked about GAP_LIMIT addresses at a time;
+    /// the scan ends once that many in a row have never appeared on chain. At least one account is kept.
+    pub fn discover(&self, mut is_used: impl FnMut(&[String]) -> Result<Vec<bool>, String>) -> Result<u32, String> {
+        let mut used_count = 0;
+        let mut next = 0;
+        while next < used_count + GAP_LIMIT {
+            let addresses: Vec<String> = (next..next + GAP_LIMIT).map(|index| self.account(index).address()).collect();
+            let used = is_used(&addresses)?;
+            if let Some(last) = used.iter().rposition(|used| *used) {
+                used_count = next + last as u32 + 1;
+            }
+            next += GAP_LIMIT;
+        }
+        Ok(used_count.max(1))
+    }
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn split(bytes: [u8; 64]) -> ([u8; 32], [u8; 32]) {
+    let mut key = [0u8; 32];
+    let mut chain_code = [0u8; 32];
+    key.copy_from_slice(&bytes[..32]);
+    chain_code.copy_from_slice(&bytes[32..]);
+    (key, chain_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slip10_vectors() {
+        // SLIP-10 Ed25519 test vector 1
+        let wallet = HdWallet::from_seed(&hex::decode("000102030405060708090a0b0c0d0e0f").unwrap());
+        let derive = |path: &[u32]| wallet.derive(path).secret_key_hex();
+        assert_eq!(derive(&[]), "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7");
+        assert_eq!(derive(&[0]), "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3");
+        assert_eq!(derive(&[0, 1]), "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2");
+    }
+
+    #[test]
+    fn test_mnemonic_recovers_same_accounts() {
+        let phrase = generate_mnemonic(12).unwrap();
+        assert_eq!(phrase.split_whitespace().count(), 12);
+
+        let original = HdWallet::from_mnemonic(&phrase, "").unwrap();
+        let recovered = HdWallet::from_mnemonic(&format!("  {}  ", phrase), "").unwrap();
+        assert_eq!(original.account(3).address(), recovered.account(3).address());
+        assert_ne!(original.account(0).address(), original.account(1).address());
+        assert_ne!(original.account(0).address(), HdWallet::from_mnemonic(&phrase, "extra").unwrap().account(0).address());
+
+        // Eleven "abandon"s and "about" is the BIP-39 all-zero vector; a twelfth "abandon" fails the checksum
+        let abandon = ["abandon"; 11].join(" ");
+        assert!(HdWallet::from_mnemonic(&format!("{} about", abandon), "").is_ok());
+        assert!(HdWallet::from_mnemonic(&format!("{} abandon", abandon), "").is_err());
+        assert!(generate_mnemonic(13).is_err());
+    }
+
+    #[test]
+    fn test_discovery_respects_gap_limit() {
+        let wallet = HdWallet::from_mnemonic(&generate_mnemonic(12).unwrap(), "").unwrap();
+        let used: Vec<String> = [0, 4, 23].iter().map(|index| wallet.account(*index).address()).collect();
+
+        let mut batches = 0;
+        let count = wallet.discover(|addresses| {
+            batches += 1;
+            Ok(addresses.iter().map(|address| used.contains(address)).collect())
+        }).unwrap();
+        assert_eq!(count, 24);
+        assert_eq!(batches, 3);
+
+        assert_eq!(wallet.discover(|addresses| Ok(vec![false; addresses.len()])).unwrap(), 1);
+    }
+}
@@ -0,0 +1,538 @@
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::amount::Amount;
+use crate::block_tree::MAX_REORG_DEPTH;
+use crate::crypto;
+use crate::vesting::{self, BalanceLock};
+use crate::{Block, Transaction};
+
+/// Senders that create new coins rather than spending an existing balance
+const MINTING_SOURCES: [&str; 4] = ["genesis", "mining_reward", "connection_reward", "social_mining"];
+
+/// Running totals for one address, updated as each block is applied
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AccountState {
+    pub balance: Amount,
+    pub total_sent: Amount,
+    pub total_received: Amount,
+    pub tx_count: u32,
+    /// Nonce the account's next signed transfer must carry
+    pub nonce: u64,
+    /// Time-locked and vesting credits not yet fully released; part of `balance`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub locks: Vec<BalanceLock>,
+}
+
+/// Position of a transaction on the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TxLocation {
+    pub block_index: u64,
+    pub tx_index: usize,
+}
+
+/// What applying a block overwrote, so a reorg can take the block back off
+#[derive(Debug, Clone)]
+struct BlockUndo {
+    block_hash: String,
+    // Prior state of every account the block touched; `None` if it did not exist yet
+    accounts: Vec<(String, Option<AccountState>)>,
+    circulating_supply: Amount,
+    // Signed payouts the block made
+    payouts: Vec<(String, u64)>,
+}
+
+/// Account state maintained incrementally, one block at a time
+#[derive(Debug, Clone, Default)]
+pub struct Ledger {
+    accounts: HashMap<String, AccountState>,
+    history: HashMap<String, Vec<TxLocation>>,
+    locations: HashMap<String, TxLocation>,
+    circulating_supply: Amount,
+    // Minting that would take circulating_supply past this is skipped; uncapped when unset
+    supply_cap: Option<Amount>,
+    // Undo records for the most recent blocks, newest last
+    undo: VecDeque<BlockUndo>,
+    // Sender and nonce of every signed protocol payout made, so none can be made twice
+    payouts: HashSet<(String, u64)>,
+}
+
+pub fn is_minting_source(address: &str) -> bool {
+    MINTING_SOURCES.contains(&address)
+}
+
+// Protocol payouts besides the coinbase are signed under a nonce of their own
+fn payout_key(tx: &Transaction) -> Option<(String, u64)> {
+    tx.nonce
+        .filter(|_| tx.from != "mining_reward" && crypto::is_system_address(&tx.from))
+        .map(|nonce| (tx.from.clone(), nonce))
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty ledger that refuses to mint past `max_supply`
+    pub fn with_supply_cap(max_supply: Amount) -> Self {
+        Self { supply_cap: Some(max_supply), ..Self::default() }
+    }
+
+    /// Replay a whole chain; only needed once when a node starts
+    pub fn from_blocks(blocks: &[Block], max_supply: Amount) -> Self {
+        let mut ledger = Self::with_supply_cap(max_supply);
+        for block in blocks {
+            ledger.apply_block(block);
+        }
+        ledger
+    }
+
+    /// Apply every transaction in a newly appended block; returns the hashes of those that took
+    /// effect. Skipped transactions stay out of the account history and the location index.
+    pub fn apply_block(&mut self, block: &Block) -> HashSet<String> {
+        let mut touched: Vec<(String, Option<AccountState>)> = Vec::new();
+        for tx in &block.transactions {
+            for address in [&tx.from, &tx.to] {
+                if !touched.iter().any(|(seen, _)| seen == address) {
+                    touched.push((address.clone(), self.accounts.get(address).cloned()));
+                }
+            }
+        }
+        let mut undo = BlockUndo {
+            block_hash: block.hash.clone(),
+            accounts: touched,
+            circulating_supply: self.circulating_supply,
+            payouts: Vec::new(),
+        };
+
+        let mut applied = HashSet::new();
+        for (tx_index, tx) in block.transactions.iter().enumerate() {
+            if let Err(e) = self.apply_transaction(tx, block.index, block.timestamp) {
+                println!("⚠️ Skipping transaction {} in block {}: {}", tx_index, block.index, e);
+                continue;
+            }
+            undo.payouts.extend(payout_key(tx));
+            let location = TxLocation { block_index: block.index, tx_index };
+            applied.insert(self.index_transaction(tx, location));
+        }
+
+        self.undo.push_back(undo);
+        if self.undo.len() > MAX_REORG_DEPTH {
+            self.undo.pop_front();
+        }
+        applied
+    }
+
+    /// Whether every transaction in `block` would take effect if it were applied next; a block
+    /// from a peer must not carry any the ledger would skip. Nothing is changed either way.
+    pub fn check_block(&self, block: &Block) -> Result<(), String> {
+        let mut scratch = Ledger {
+            circulating_supply: self.circulating_supply,
+            supply_cap: self.supply_cap,
+            ..Ledger::default()
+        };
+        for tx in &block.transactions {
+            for address in [&tx.from, &tx.to] {
+                if let Some(account) = self.accounts.get(address) {
+                    scratch.accounts.insert(address.clone(), account.clone());
+                }
+            }
+            scratch.payouts.extend(payout_key(tx).filter(|payout| self.payouts.contains(payout)));
+        }
+        for (tx_index, tx) in block.transactions.iter().enumerate() {
+            scratch.apply_transaction(tx, block.index, block.timestamp)
+                .map_err(|e| format!("Transaction {} would not apply: {}", tx_index, e))?;
+        }
+        Ok(())
+    }
+
+    /// Take the most recently applied block back off, restoring the state before it
+    pub fn revert_block(&mut self, block: &Block) -> Result<(), String> {
+        if self.undo.back().is_none_or(|undo| undo.block_hash != block.hash) {
+            return Err(format!("Block {} is not the latest revertible block", block.index));
+        }
+        let undo = self.undo.pop_back().unwrap();
+
+        for (address, previous) in undo.accounts {
+            match previous {
+                Some(account) => self.accounts.insert(address, account),
+                None => self.accounts.remove(&address),
+            };
+        }
+        self.circulating_supply = undo.circulating_supply;
+        for payout in &undo.payouts {
+            self.payouts.remove(payout);
+        }
+
+        for (tx_index, tx) in block.transactions.iter().enumerate().rev() {
+            let location = TxLocation { block_index: block.index, tx_index };
+            let tx_hash = tx.hash();
+            if self.locations.get(&tx_hash) == Some(&location) {
+                self.locations.remove(&tx_hash);
+            }
+            for address in [&tx.from, &tx.to] {
+                if let Some(history) = self.history.get_mut(address) {
+                    if history.last() == Some(&location) {
+                        history.pop();
+                    }
+                    if history.is_empty() {
+                        self.history.remove(address);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `blocks`, oldest first, are the latest applied blocks and all of them can still be
+    /// reverted
+    pub fn can_revert(&self, blocks: &[Block]) -> bool {
+        blocks.len() <= self.undo.len()
+            && self.undo.iter().rev().zip(blocks.iter().rev()).all(|(undo, block)| undo.block_hash == block.hash)
+    }
+
+    /// How many of the latest blocks can still be reverted
+    pub fn revertible_blocks(&self) -> usize {
+        self.undo.len()
+    }
+
+    // A transaction replayed under the same hash keeps the location it was first mined at
+    fn index_transaction(&mut self, tx: &Transaction, location: TxLocation) -> String {
+        let hash = tx.hash();
+        self.locations.entry(hash.clone()).or_insert(location);
+        self.history.entry(tx.from.clone()).or_default().push(location);
+        if tx.to != tx.from {
+            self.history.entry(tx.to.clone()).or_default().push(location);
+        }
+        hash
+    }
+
+    // Nothing is changed unless every step of the transfer succeeds. Locks are judged
+    // at the height and timestamp of the block carrying the transfer. A fee leaves the sender's
+    // balance and the supply; the block's reward transaction mints back what the miner collects.
+    fn apply_transaction(&mut self, tx: &Transaction, height: u64, time: u64) -> Result<(), String> {
+        let minted = is_minting_source(&tx.from);
+        let mut sender = self.account(&tx.from);
+        let mut recipient = self.account(&tx.to);
+        sender.locks.retain(|lock| !lock.locked(height, time).is_zero());
+
+        // Signed transfers must use the sender's next nonce; blocks from before nonces carry none
+        if let Some(nonce) = tx.nonce.filter(|_| !crypto::is_system_address(&tx.from)) {
+            if nonce != sender.nonce {
+                return Err(format!("nonce {} from {} does not match expected {}", nonce, tx.from, sender.nonce));
+            }
+            sender.nonce += 1;
+        }
+        let payout = payout_key(tx);
+        if let Some((from, nonce)) = payout.as_ref().filter(|payout| self.payouts.contains(payout)) {
+            return Err(format!("payout {} from {} was already made", nonce, from));
+        }
+
+        let circulating_supply = if minted {
+            let circulating_supply = self.circulating_supply.try_add(tx.amount)?;
+            if self.supply_cap.is_some_and(|cap| circulating_supply > cap) {
+                return Err(format!("minting {} would take the supply past its cap", tx.amount));
+            }
+            circulating_supply
+        } else {
+            let fee = tx.fee.unwrap_or_default();
+            let cost = tx.amount.try_add(fee)?;
+            let locked = vesting::locked_total(&sender.locks, height, time);
+            if sender.balance.saturating_sub(locked) < cost {
+                return Err(format!("{} overdraws {} ({} of its balance is locked)", cost, tx.from, locked));
+            }
+            sender.balance = sender.balance.try_sub(cost)?;
+            self.circulating_supply.try_sub(fee)?
+        };
+        let lock = tx.lock.map(|schedule| BalanceLock { amount: tx.amount, schedule });
+        sender.total_sent = sender.total_sent.try_add(tx.amount)?;
+        sender.tx_count += 1;
+
+        if tx.to == tx.from {
+            // A self-transfer only changes the counters
+            sender.total_received = sender.total_received.try_add(tx.amount)?;
+            if !minted {
+                sender.balance = sender.balance.try_add(tx.amount)?;
+            }
+            sender.locks.extend(lock);
+            self.accounts.insert(tx.from.clone(), sender);
+        } else {
+            recipient.balance = recipient.balance.try_add(tx.amount)?;
+            recipient.total_received = recipient.total_received.try_add(tx.amount)?;
+            recipient.tx_count += 1;
+            recipient.locks.extend(lock);
+            self.accounts.insert(tx.from.clone(), sender);
+            self.accounts.insert(tx.to.clone(), recipient);
+        }
+
+        self.circulating_supply = circulating_supply;
+        self.payouts.extend(payout);
+        Ok(())
+    }
+
+    /// Current state of an address (all zeros if it has never been seen)
+    pub fn account(&self, address: &str) -> AccountState {
+        self.accounts.get(address).cloned().unwrap_or_default()
+    }
+
+    pub fn has_account(&self, address: &str) -> bool {
+        self.accounts.contains_key(address)
+    }
+
+    pub fn balance(&self, address: &str) -> Amount {
+        self.account(address).balance
+    }
+
+    /// Part of the balance a block at `height` with timestamp `time` could not spend
+    pub fn locked_balance(&self, address: &str, height: u64, time: u64) -> Amount {
+        self.accounts.get(address)
+            .map_or(Amount::ZERO, |account| vesting::locked_total(&account.locks, height, time))
+    }
+
+    pub fn spendable_balance(&self, address: &str, height: u64, time: u64) -> Amount {
+        self.balance(address).saturating_sub(self.locked_balance(address, height, time))
+    }
+
+    /// Nonce of the next transfer `address` can get confirmed
+    pub fn nonce(&self, address: &str) -> u64 {
+        self.account(address).nonce
+    }
+
+    /// Addresses holding a non-zero balance
+    pub fn balances(&self) -> impl Iterator<Item = (&String, Amount)> {
+        self.accounts.iter()
+            .filter(|(_, account)| !account.balance.is_zero())
+            .map(|(address, account)| (address, account.balance))
+    }
+
+    /// Where each transaction touching `address` sits on the chain, oldest first
+    pub fn history(&self, address: &str) -> &[TxLocation] {
+        self.history.get(address).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Where the transaction with this hash was mined
+    pub fn locate(&self, tx_hash: &str) -> Option<TxLocation> {
+        self.locations.get(tx_hash).copied()
+    }
+
+    pub fn circulating_supply(&self) -> Amount {
+        self.circulating_supply
+    }
+}
+
+// Keeps the `balances` / `circulating_supply` shape of GET /blockchain
+impl Serialize for Ledger {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let balances: BTreeMap<&String, Amount> = self.balances().collect();
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("balances", &balances)?;
+        map.serialize_entry("circulating_supply", &self.circulating_supply)?;
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(from: &str, to: &str, amount: u64) -> Transaction {
+        Transaction {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: Amount::from_l1(amount),
+            timestamp: 1,
+            signature: format!("sig_{}_{}", from, to),
+            public_key: None,
+            nonce: None,
+            ..Default::default()
+        }
+    }
+
+    fn block(index: u64, transactions: Vec<Transaction>) -> Block {
+        Block::new(index, transactions, "0".to_string(), "miner".to_string())
+    }
+
+    #[test]
+    fn test_incremental_matches_replay() {
+        let blocks = vec![
+            block(0, vec![tx("genesis", "alice", 100)]),
+            block(1, vec![tx("alice", "bob", 30), tx("mining_reward", "miner", 10)]),
+            block(2, vec![tx("bob", "alice", 5)]),
+        ];
+
+        let mut ledger = Ledger::new();
+        for block in &blocks {
+            ledger.apply_block(block);
+        }
+
+        let alice = ledger.account("alice");
+        assert_eq!(alice.balance, Amount::from_l1(75));
+        assert_eq!(alice.total_sent, Amount::from_l1(30));
+        assert_eq!(alice.total_received, Amount::from_l1(105));
+        assert_eq!(alice.tx_count, 3);
+        assert_eq!(ledger.circulating_supply(), Amount::from_l1(110));
+
+        let replayed = Ledger::from_blocks(&blocks, Amount::MAX);
+        assert_eq!(replayed.account("bob"), ledger.account("bob"));
+    }
+
+    #[test]
+    fn test_history_index() {
+        let mut ledger = Ledger::new();
+        ledger.apply_block(&block(0, vec![tx("genesis", "alice", 100)]));
+        ledger.apply_block(&block(1, vec![tx("mining_reward", "miner", 10), tx("alice", "bob", 1)]));
+
+        assert_eq!(ledger.history("alice"), &[
+            TxLocation { block_index: 0, tx_index: 0 },
+            TxLocation { block_index: 1, tx_index: 1 },
+        ]);
+        assert_eq!(ledger.history("bob").len(), 1);
+        assert!(ledger.history("nobody").is_empty());
+
+        let transfer = tx("alice", "bob", 1);
+        assert_eq!(ledger.locate(&transfer.hash()), Some(TxLocation { block_index: 1, tx_index: 1 }));
+        assert_eq!(ledger.locate("unknown"), None);
+    }
+
+    #[test]
+    fn test_nonces_must_be_sequential() {
+        let signed = |nonce: u64| Transaction { nonce: Some(nonce), ..tx("alice", "bob", 1) };
+
+        let mut ledger = Ledger::new();
+        ledger.apply_block(&block(0, vec![tx("genesis", "alice", 10), signed(0), signed(1)]));
+        assert_eq!(ledger.nonce("alice"), 2);
+        assert_eq!(ledger.balance("bob"), Amount::from_l1(2));
+
+        // A replayed or skipped-ahead nonce is not applied, and the replay keeps the original's location
+        assert!(ledger.apply_block(&block(1, vec![signed(1), signed(5)])).is_empty());
+        assert_eq!(ledger.nonce("alice"), 2);
+        assert_eq!(ledger.balance("alice"), Amount::from_l1(8));
+        assert_eq!(ledger.locate(&signed(1).hash()), Some(TxLocation { block_index: 0, tx_index: 2 }));
+        assert_eq!(ledger.history("alice").len(), 3);
+    }
+
+    #[test]
+    fn test_payouts_cannot_be_replayed() {
+        let payout = |nonce: u64| Transaction { nonce: Some(nonce), ..tx("genesis", "alice", 10) };
+
+        let mut ledger = Ledger::new();
+        let first = block(0, vec![payout(7), payout(7), payout(8)]);
+        ledger.apply_block(&first);
+        assert_eq!(ledger.balance("alice"), Amount::from_l1(20));
+
+        // Once its block is reverted, the payout can be made again
+        assert!(ledger.apply_block(&block(1, vec![payout(8)])).is_empty());
+        ledger.revert_block(&block(1, vec![payout(8)])).unwrap();
+        ledger.revert_block(&first).unwrap();
+        assert_eq!(ledger.apply_block(&block(0, vec![payout(7)])).len(), 1);
+    }
+
+    #[test]
+    fn test_check_block_refuses_skipped_transactions() {
+        let signed = |nonce: u64| Transaction { nonce: Some(nonce), ..tx("alice", "bob", 4) };
+        let mut ledger = Ledger::with_supply_cap(Amount::from_l1(20));
+        ledger.apply_block(&block(0, vec![tx("genesis", "alice", 10), signed(0)]));
+
+        assert!(ledger.check_block(&block(1, vec![signed(1)])).is_ok());
+        for skipped in [vec![signed(0)], vec![signed(1), signed(1)], vec![signed(1), signed(2)], vec![tx("genesis", "bob", 11)]] {
+            assert!(ledger.check_block(&block(1, skipped)).is_err());
+        }
+        assert_eq!(ledger.balance("alice"), Amount::from_l1(6));
+    }
+
+    #[test]
+    fn test_revert_restores_prior_state() {
+        let blocks = vec![
+            block(0, vec![tx("genesis", "alice", 100)]),
+            block(1, vec![tx("alice", "bob", 30)]),
+            block(2, vec![tx("alice", "carol", 5), tx("mining_reward", "miner", 10)]),
+        ];
+        let mut ledger = Ledger::from_blocks(&blocks, Amount::MAX);
+
+        assert!(ledger.revert_block(&blocks[1]).is_err());
+        assert!(ledger.can_revert(&blocks[1..]) && !ledger.can_revert(&blocks[..2]));
+        ledger.revert_block(&blocks[2]).unwrap();
+        ledger.revert_block(&blocks[1]).unwrap();
+
+        let replayed = Ledger::from_blocks(&blocks[..1], Amount::MAX);
+        assert_eq!(ledger.account("alice"), replayed.account("alice"));
+        assert_eq!(ledger.history("alice"), replayed.history("alice"));
+        assert!(!ledger.has_account("bob") && !ledger.has_account("carol"));
+        assert_eq!(ledger.locate(&tx("alice", "bob", 30).hash()), None);
+        assert_eq!(ledger.circulating_supply(), Amount::from_l1(100));
+        assert_eq!(ledger.revertible_blocks(), 1);
+    }
+
+    #[test]
+    fn test_overdraft_is_skipped() {
+        let mut ledger = Ledger::new();
+        let applied = ledger.apply_block(&block(0, vec![tx("genesis", "alice", 10), tx("alice", "bob", 50)]));
+
+        assert_eq!(ledger.balance("alice"), Amount::from_l1(10));
+        assert_eq!(ledger.balance("bob"), Amount::ZERO);
+        assert_eq!(ledger.circulating_supply(), Amount::from_l1(10));
+
+        // A skipped transfer is not confirmed anywhere
+        assert_eq!(applied, HashSet::from([tx("genesis", "alice", 10).hash()]));
+        assert_eq!(ledger.locate(&tx("alice", "bob", 50).hash()), None);
+        assert!(ledger.history("bob").is_empty());
+        assert_eq!(ledger.history("alice").len(), 1);
+
+        // Minting past the cap is skipped the same way
+        let mut capped = Ledger::with_supply_cap(Amount::from_l1(15));
+        capped.apply_block(&block(0, vec![tx("genesis", "alice", 10), tx("mining_reward", "miner", 6), tx("mining_reward", "miner", 5)]));
+        assert_eq!(capped.balance("miner"), Amount::from_l1(5));
+        assert_eq!(capped.circulating_supply(), Amount::from_l1(15));
+    }
+
+    #[test]
+    fn test_fees_are_charged() {
+        let paying = |nonce: u64| Transaction { fee: Some(Amount::from_l1(1)), nonce: Some(nonce), ..tx("alice", "bob", 5) };
+        let mut ledger = Ledger::new();
+        ledger.apply_block(&block(0, vec![tx("genesis", "alice", 12), paying(0), paying(1)]));
+
+        // The fee leaves the supply, and a sender must cover it too
+        assert_eq!(ledger.balance("alice"), Amount::ZERO);
+        assert_eq!(ledger.balance("bob"), Amount::from_l1(10));
+        assert_eq!(ledger.circulating_supply(), Amount::from_l1(10));
+
+        ledger.apply_block(&block(1, vec![tx("bob", "carol", 10), paying(2)]));
+        assert_eq!(ledger.nonce("alice"), 2);
+    }
+
+    #[test]
+    fn test_locked_credits_cannot_be_spent() {
+        use crate::vesting::LockSchedule;
+
+        let locked = |to: &str, amount: u64, schedule: LockSchedule| Transaction { lock: Some(schedule), ..tx("genesis", to, amount) };
+        let at = |index: u64, timestamp: u64, transactions: Vec<Transaction>| {
+            let mut block = block(index, transactions);
+            block.timestamp = timestamp;
+            block
+        };
+
+        let mut ledger = Ledger::new();
+        ledger.apply_block(&at(0, 1_000, vec![
+            tx("genesis", "alice", 10),
+            locked("alice", 40, LockSchedule::at_height(2)),
+            locked("alice", 100, LockSchedule::vesting(1_000, 100)),
+        ]));
+        assert_eq!(ledger.balance("alice"), Amount::from_l1(150));
+        assert_eq!(ledger.spendable_balance("alice", 1, 1_000), Amount::from_l1(10));
+
+        // Only the unlocked 10 plus what has vested (25 by t=1025) may leave
+        ledger.apply_block(&at(1, 1_025, vec![tx("alice", "bob", 36)]));
+        assert!(!ledger.has_account("bob"));
+        ledger.apply_block(&at(2, 1_025, vec![tx("alice", "bob", 75)]));
+        assert_eq!(ledger.balance("bob"), Amount::from_l1(75));
+        assert_eq!(ledger.locked_balance("alice", 3, 1_025), Amount::from_l1(75));
+
+        // Fully released locks are dropped, and a revert brings them back
+        let spend_all = at(3, 1_100, vec![tx("alice", "carol", 75)]);
+        ledger.apply_block(&spend_all);
+        assert!(ledger.account("alice").locks.is_empty());
+        ledger.revert_block(&spend_all).unwrap();
+        assert_eq!(ledger.account("alice").locks.len(), 1);
+    }
+}
@@ -0,0 +1,7 @@
+//! Account, amount and transaction types shared by the node and the client tools built on it
+
+pub mod amount;
+pub mod crypto;
+pub mod enhanced_transaction;
+pub mod hd;
+pub mod vesting;
@@ -1,24 +1,54 @@
 #![recursion_limit = "512"]
+#![allow(dead_code)]
 
 use sha2::{Digest, Sha256};
 use std::time::{SystemTime, UNIX_EPOCH, Duration};
 use warp::Filter;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use tokio::sync::mpsc;
 use tokio::time;
 use crate::token_launch::TokenHolding;
 extern crate rand; // Add this line
 
+// Shared with the client tools
+use layer1::{amount, crypto, enhanced_transaction, vesting};
+
 // Add the new modules
+mod block_tree;
+mod chain_spec;
+mod cli;
+mod config;
+mod difficulty;
+mod emission;
+mod fee_market;
+mod ledger;
+mod mempool;
+mod merkle;
+mod mining;
+mod multisig;
+mod p2p;
 mod security;
-mod enhanced_transaction;
 mod token_launch;
 mod social_mining;
+mod storage;
+mod sync;
+mod validation;
 
 // Import the new types
+use amount::Amount;
+use block_tree::{BlockTree, ReorgEvent};
+use chain_spec::ChainSpec;
+use cli::Command;
+use config::NodeConfig;
+use crypto::{KeyPair, MultisigAuth, MultisigPolicy, MultisigSignature, TransferAuth};
+use difficulty::RetargetParams;
+use emission::{EmissionSchedule, EraProjection};
+use fee_market::FeeEstimate;
 use security::{SecurityManager, SecurityError, SecurityStats};
-use enhanced_transaction::{EnhancedTransaction, TransactionPool, PoolStats, TransactionReceipt};
+use enhanced_transaction::{EnhancedTransaction, TransactionReceipt, TransactionStatus};
 use token_launch::{
     TokenLaunchSystem, LaunchTokenRequest, BuyTokenRequest, SellTokenRequest,
     UserPortfolioResponse, Token, TokenTrade
@@ -28,15 +58,127 @@ use social_mining::{
     SocialMiningSystem, SocialPostRequest, SocialLikeRequest, SocialCommentRequest,
     SocialActionResponse, SocialStatsResponse
 };
+use ledger::Ledger;
+use mempool::{Admission, Mempool, PoolEntry, PoolStats, MAX_BLOCK_TRANSACTIONS};
+use merkle::ProofStep;
+use mining::{BlockTemplate, TipSignal};
+use multisig::{MultisigRegistry, Proposal};
+use storage::{Storage, PersistedState};
+use validation::ChainValidationError;
+use vesting::{BalanceLock, LockSchedule};
 
 // Original Transaction structure (keep for compatibility)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct Transaction {
     from: String,
     to: String,
-    amount: f64,
+    amount: Amount,
     timestamp: u64,
     signature: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    // Sender's account sequence number; system payouts and pre-nonce blocks carry none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nonce: Option<u64>,
+    // Fee and message of a transfer submitted as an enhanced transaction, which its signature covers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fee: Option<Amount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+    // Member signatures when the sender is a multisig account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    multisig: Option<MultisigAuth>,
+    // Keeps the credited amount from being spent until the schedule releases it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lock: Option<LockSchedule>,
+}
+
+impl Transaction {
+    // Build a user transfer from request fields; the signature, which covers the fee, is checked
+    // when it is queued
+    fn signed(from: String, to: String, amount: Amount, fee: Option<Amount>, auth: TransferAuth) -> Self {
+        Transaction {
+            from,
+            to,
+            amount,
+            timestamp: auth.timestamp
+                .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()),
+            signature: auth.signature.unwrap_or_default(),
+            public_key: auth.public_key,
+            nonce: auth.nonce,
+            fee,
+            ..Default::default()
+        }
+    }
+
+    // Leaf hash committed to by the block's Merkle root; also the transaction's id on chain
+    fn hash(&self) -> String {
+        merkle::leaf_hash(serde_json::to_string(self).unwrap_or_default().as_bytes())
+    }
+
+    // Canonical bytes the sender signs; the enhanced form when the transfer was submitted as one
+    fn signing_payload(&self) -> String {
+        let nonce = self.nonce.unwrap_or_default();
+        let mut payload = match self.fee {
+            Some(fee) => enhanced_transaction::signing_payload(
+                &self.from, &self.to, self.amount, fee, nonce, self.timestamp, self.data.as_deref(),
+            ),
+            None => format!(
+                "layer1-transfer:{}:{}:{}:{}:{}",
+                self.from, self.to, self.amount, self.timestamp, nonce
+            ),
+        };
+        if let Some(lock) = &self.lock {
+            payload.push_str(&lock.signing_suffix());
+        }
+        payload
+    }
+
+    // Legacy form of an enhanced transaction, as recorded in blocks
+    fn from_enhanced(tx: &EnhancedTransaction) -> Self {
+        Transaction {
+            from: tx.from.clone(),
+            to: tx.to.clone(),
+            amount: tx.amount,
+            timestamp: tx.timestamp,
+            signature: tx.signature.clone(),
+            public_key: tx.public_key.clone(),
+            nonce: Some(tx.nonce),
+            fee: Some(tx.fee),
+            data: tx.data.clone(),
+            multisig: tx.multisig.clone(),
+            lock: tx.lock,
+        }
+    }
+
+    fn verify_signature(&self) -> Result<(), String> {
+        if crypto::is_system_address(&self.from) {
+            return Ok(());
+        }
+        if self.nonce.is_none() {
+            return Err(format!("Transfers from {} must include the account nonce", self.from));
+        }
+        if let Some(auth) = &self.multisig {
+            return crypto::verify_multisig(&self.from, auth, self.signing_payload().as_bytes());
+        }
+        crypto::verify_sender(
+            &self.from,
+            self.public_key.as_deref(),
+            &self.signature,
+            self.signing_payload().as_bytes(),
+        )
+    }
+
+    // Protocol payouts carry the payout authority's signature and a nonce that makes each one unique
+    fn verify_payout(&self, authority: &str) -> Result<(), String> {
+        if self.nonce.is_none() {
+            return Err("payouts must carry a nonce".to_string());
+        }
+        if self.public_key.as_deref() != Some(authority) {
+            return Err("not signed by the payout authority".to_string());
+        }
+        crypto::verify_signature(authority, self.signing_payload().as_bytes(), &self.signature)
+    }
 }
 
 // Add this new structure for enhanced transaction requests
@@ -44,31 +186,69 @@ struct Transaction {
 struct EnhancedTransactionRequest {
     from: String,
     to: String,
-    amount: f64,
-    fee: f64,
+    amount: Amount,
+    fee: Amount,
     message: Option<String>,
+    lock: Option<LockSchedule>,
+    #[serde(flatten)]
+    auth: TransferAuth,
+}
+
+// Cancels the sender's waiting transfer with `auth.nonce`; signed like an enhanced transaction
+// of zero to the sender itself
+#[derive(Debug, Deserialize)]
+struct CancelTransactionRequest {
+    from: String,
+    fee: Amount,
+    #[serde(flatten)]
+    auth: TransferAuth,
 }
 
 // Updated Block structure
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Block {
     index: u64,
     timestamp: u64,
     transactions: Vec<Transaction>,
     previous_hash: String,
+    // Blocks mined before Merkle roots were introduced have none and hash their transactions directly
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    merkle_root: String,
     hash: String,
     nonce: u64,
     miner: String,
-    reward: f64,
+    reward: Amount,
+    // Work the hash had to meet; zero for blocks from before retargeting
+    #[serde(default)]
+    difficulty: u64,
+    // Fee each transaction must at least pay, set by the parent's fullness; zero before the fee market
+    #[serde(default)]
+    base_fee: Amount,
+}
+
+// Everything the block hash commits to, without the transactions themselves
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BlockHeader {
+    index: u64,
+    timestamp: u64,
+    previous_hash: String,
+    merkle_root: String,
+    nonce: u64,
+    miner: String,
+    reward: Amount,
+    difficulty: u64,
+    #[serde(default)]
+    base_fee: Amount,
+    hash: String,
 }
 
 // Connection tracking structure
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Connection {
     address: String,
     connected_at: u64,
     last_activity: u64,
-    total_rewards: f64,
+    total_rewards: Amount,
     is_active: bool,
 }
 
@@ -86,14 +266,22 @@ struct AddressLabel {
 struct TransactionRequest {
     from: String,
     to: String,
-    amount: f64,
+    amount: Amount,
+    #[serde(default)]
+    fee: Option<Amount>, // Required, and part of the payload the sender signs
+    #[serde(flatten)]
+    auth: TransferAuth,
 }
 
 #[derive(Deserialize)]
 struct TransactionWithUsernamesRequest {
     from: String,
     to: String,
-    amount: f64,
+    amount: Amount,
+    #[serde(default)]
+    fee: Option<Amount>, // Required, and part of the payload the sender signs
+    #[serde(flatten)]
+    auth: TransferAuth,
 }
 
 #[derive(Deserialize)]
@@ -114,6 +302,8 @@ struct DisconnectRequest {
 #[derive(Deserialize)]
 struct UsernameRegisterRequest {
     username: String,
+    public_key: String,
+    signature: String,
 }
 
 #[derive(Deserialize)]
@@ -125,8 +315,35 @@ struct UsernameResolveRequest {
 struct TipRequest {
     from: String,
     to: String,
-    amount: f64,
+    amount: Amount,
+    #[serde(default)]
+    fee: Option<Amount>, // Required, and part of the payload the sender signs
     message: Option<String>,
+    #[serde(flatten)]
+    auth: TransferAuth,
+}
+
+#[derive(Deserialize)]
+struct MultisigCreateRequest {
+    threshold: usize,
+    public_keys: Vec<String>,
+}
+
+// The node picks the nonce and timestamp; members then sign the proposed transaction
+#[derive(Deserialize)]
+struct MultisigProposeRequest {
+    from: String,
+    to: String,
+    amount: Amount,
+    fee: Amount,
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MultisigSignRequest {
+    proposal_id: String,
+    public_key: String,
+    signature: String,
 }
 
 #[derive(Deserialize)]
@@ -144,25 +361,88 @@ struct AdminUnblacklistRequest {
 #[derive(Serialize)]
 struct BalanceResponse {
     address: String,
-    balance: f64,
+    balance: Amount,
 }
 
 #[derive(Serialize)]
 struct NetworkStats {
-    total_supply: f64,
-    circulating_supply: f64,
-    remaining_supply: f64,
-    current_reward_rate: f64,
+    total_supply: Amount,
+    circulating_supply: Amount,
+    remaining_supply: Amount,
+    current_reward_rate: Amount,
     active_connections: usize,
     total_blocks: usize,
+    difficulty: u64,
+    target_block_time: u64,
+}
+
+// Body of GET /rpc/emission
+#[derive(Serialize)]
+struct EmissionResponse {
+    height: u64, // Height of the next block
+    era: u64,
+    block_subsidy: Amount,
+    halving_interval: u64,
+    next_halving_height: u64,
+    blocks_until_halving: u64,
+    estimated_halving_time: u64, // Unix time, at the target block time
+    max_supply: Amount,
+    circulating_supply: Amount,
+    pending_issuance: Amount, // Minted by transactions still in the pool
+    remaining_supply: Amount,
+    connection_reward_per_minute: Amount,
+    projection: Vec<EraProjection>,
+}
+
+// Body of GET /rpc/fees/estimate
+#[derive(Serialize)]
+struct FeeEstimateResponse {
+    height: u64,       // Height of the next block
+    base_fee: Amount,  // Least a fee-paying transaction must pay to be mined in it
+    min_fee: Amount,   // Least the pool admits, which rises above the base fee as it fills
+    burn_percent: u128, // Share of the base fee burned; the rest of each fee goes to the miner
+    target_block_transactions: usize,
+    max_block_transactions: usize,
+    pending_count: usize,
+    estimates: Vec<FeeEstimate>,
+}
+
+#[derive(Serialize)]
+struct TransactionProofResponse {
+    tx_hash: String,
+    tx_index: usize,
+    transaction: Transaction,
+    header: BlockHeader,
+    proof: Vec<ProofStep>,
+}
+
+#[derive(Serialize)]
+struct AccountNonceResponse {
+    address: String,
+    nonce: u64,           // Nonce to sign the next transfer with
+    confirmed_nonce: u64, // Nonce after the last mined transfer
+    queued_count: usize,
+}
+
+#[derive(Deserialize)]
+struct AccountActivityRequest {
+    addresses: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct AccountActivity {
+    address: String,
+    balance: Amount,
+    transaction_count: u32, // Zero for an address that has never appeared on chain
 }
 
 #[derive(Serialize)]
 struct UserWalletInfo {
     address: String,
-    balance: f64,
-    total_sent: f64,
-    total_received: f64,
+    balance: Amount,
+    locked_balance: Amount,
+    total_sent: Amount,
+    total_received: Amount,
     transaction_count: u32,
 }
 
@@ -176,11 +456,13 @@ struct TransactionHistoryResponse {
 #[derive(Serialize)]
 struct WalletInfoResponse {
     address: String,
-    balance: f64,
+    balance: Amount,
+    locked_balance: Amount,  // Part of the balance the next block could not spend
+    locks: Vec<BalanceLock>, // Time-locked and vesting credits still holding coins back
     username: Option<String>,
     is_verified: bool,
-    total_sent: f64,
-    total_received: f64,
+    total_sent: Amount,
+    total_received: Amount,
     transaction_count: u32,
     connection_info: Option<Connection>,
 }
@@ -195,125 +477,444 @@ impl Block {
         let mut block = Block {
             index,
             timestamp,
+            merkle_root: Self::compute_merkle_root(&transactions),
             transactions,
             previous_hash,
             hash: String::new(),
             nonce: 0,
             miner,
-            reward: 10.0,
+            reward: Amount::from_l1(10),
+            difficulty: 0,
+            base_fee: Amount::ZERO,
         };
         
         block.hash = block.calculate_hash();
         block
     }
 
+    fn with_difficulty(mut self, difficulty: u64) -> Self {
+        self.difficulty = difficulty;
+        self.hash = self.calculate_hash();
+        self
+    }
+
+    fn transaction_hashes(transactions: &[Transaction]) -> Vec<String> {
+        transactions.iter().map(Transaction::hash).collect()
+    }
+
+    fn compute_merkle_root(transactions: &[Transaction]) -> String {
+        merkle::merkle_root(&Self::transaction_hashes(transactions))
+    }
+
+    // Only the header is hashed; the Merkle root stands in for the transactions
     fn calculate_hash(&self) -> String {
-        let transactions_data = serde_json::to_string(&self.transactions).unwrap_or_default();
-        let input = format!(
+        let transactions_data = if self.merkle_root.is_empty() {
+            serde_json::to_string(&self.transactions).unwrap_or_default()
+        } else {
+            self.merkle_root.clone()
+        };
+        let mut input = format!(
             "{}{}{}{}{}{}{}",
             self.index, self.timestamp, transactions_data, 
             self.previous_hash, self.nonce, self.miner, self.reward
         );
+        // Later header fields are labelled, so no two of them can run together into the same input
+        if self.difficulty > 0 {
+            input.push_str(&format!(":difficulty={}", self.difficulty));
+        }
+        if !self.base_fee.is_zero() {
+            input.push_str(&format!(":base_fee={}", self.base_fee.base_units()));
+        }
         let mut hasher = Sha256::new();
         hasher.update(input);
         format!("{:x}", hasher.finalize())
     }
 
-    fn mine_block(&mut self, difficulty: usize) {
-        let target = "0".repeat(difficulty);
-        println!("Mining block {}...", self.index);
-        while &self.hash[..difficulty] != target {
+    fn meets_difficulty(&self) -> bool {
+        if self.difficulty == 0 {
+            difficulty::meets_legacy_target(&self.hash)
+        } else {
+            difficulty::meets_target(&self.hash, self.difficulty)
+        }
+    }
+
+    fn mine_block(&mut self) {
+        println!("Mining block {} at difficulty {}...", self.index, self.difficulty);
+        while !self.meets_difficulty() {
             self.nonce += 1;
             self.hash = self.calculate_hash();
         }
         println!("Block {} mined! Hash: {}", self.index, self.hash);
     }
+
+    fn header(&self) -> BlockHeader {
+        BlockHeader {
+            index: self.index,
+            timestamp: self.timestamp,
+            previous_hash: self.previous_hash.clone(),
+            merkle_root: self.merkle_root.clone(),
+            nonce: self.nonce,
+            miner: self.miner.clone(),
+            reward: self.reward,
+            difficulty: self.difficulty,
+            base_fee: self.base_fee,
+            hash: self.hash.clone(),
+        }
+    }
+}
+
+impl BlockHeader {
+    // A body-less block, enough to validate the header before its transactions arrive
+    fn to_block(&self) -> Block {
+        Block {
+            index: self.index,
+            timestamp: self.timestamp,
+            transactions: Vec::new(),
+            previous_hash: self.previous_hash.clone(),
+            merkle_root: self.merkle_root.clone(),
+            hash: self.hash.clone(),
+            nonce: self.nonce,
+            miner: self.miner.clone(),
+            reward: self.reward,
+            difficulty: self.difficulty,
+            base_fee: self.base_fee,
+        }
+    }
 }
 
 // Enhanced Blockchain structure with security and enhanced transactions
 #[derive(Serialize)]
 struct Blockchain {
+    chain_id: String,
     chain: Vec<Block>,
-    difficulty: usize,
-    pending_transactions: Vec<Transaction>,
-    balances: HashMap<String, f64>,
-    mining_reward: f64,
+    retarget: RetargetParams,
+    #[serde(flatten)]
+    mempool: Mempool,
+    #[serde(flatten)]
+    ledger: Ledger,
+    #[serde(flatten)]
+    emission: EmissionSchedule,
     connections: HashMap<String, Connection>,
-    max_supply: f64,
-    circulating_supply: f64,
+    signup_bonus: Amount,
     address_labels: HashMap<String, AddressLabel>,
     address_to_username: HashMap<String, String>,
     // New security and enhanced transaction fields
     #[serde(skip)] // Skip serialization for complex types
     security_manager: SecurityManager,
     #[serde(skip)]
-    enhanced_tx_pool: TransactionPool,
-    #[serde(skip)]
     token_system: TokenLaunchSystem,
     #[serde(skip)]
     social_mining: SocialMiningSystem,
+    #[serde(skip)]
+    multisig: MultisigRegistry,
+    // Where token launch fees are paid: the spec's treasury, else the unspendable token_launch_fees
+    #[serde(skip)]
+    launch_fee_account: String,
+    // Key protocol payouts must be signed with, and the secret behind it if this node holds it
+    #[serde(skip)]
+    payout_authority: String,
+    #[serde(skip)]
+    payout_keys: Option<KeyPair>,
+    #[serde(skip)]
+    storage: Option<Storage>,
+    #[serde(skip)]
+    tip_signal: TipSignal,
+    #[serde(skip)]
+    mining_requests: Option<mpsc::UnboundedSender<String>>,
+    #[serde(skip)]
+    gossip: Option<mpsc::UnboundedSender<p2p::Message>>,
+    // Every accepted block, including side branches that may later overtake `chain`
+    #[serde(skip)]
+    block_tree: BlockTree,
+    #[serde(skip)]
+    reorgs: VecDeque<ReorgEvent>,
 }
 
+// Reorg events kept for GET /rpc/chain/reorgs
+const MAX_REORG_EVENTS: usize = 50;
+
+// Addresses one POST /rpc/accounts/activity may ask about
+const MAX_ACTIVITY_ADDRESSES: usize = 100;
+
 impl Blockchain {
     fn new() -> Self {
-        let mut blockchain = Blockchain {
+        Self::with_spec(&ChainSpec::default())
+    }
+
+    // A fresh in-memory chain starting from the spec's genesis block
+    fn with_spec(spec: &ChainSpec) -> Self {
+        let mut blockchain = Self::empty(spec);
+        blockchain.append_block(Self::genesis_block(spec));
+        blockchain
+    }
+
+    // Open a chain persisted in data_dir, creating a fresh one if none exists
+    fn open(data_dir: &str, spec: &ChainSpec) -> Result<Self, String> {
+        let storage = Storage::open(data_dir)?;
+        let blocks = storage.load_blocks()?;
+        let state = storage.load_state()?;
+
+        let mut blockchain = Self::empty(spec);
+        blockchain.storage = Some(storage);
+
+        if blocks.is_empty() {
+            blockchain.append_block(Self::genesis_block(spec));
+            println!("🌱 Created new {} chain in {}", spec.chain_id, data_dir);
+        } else {
+            Self::check_genesis(&blocks[0], spec)?;
+            validation::validate_blocks(&blocks, &blockchain.retarget)
+                .map_err(|e| format!("Refusing to load corrupted chain: {}", e))?;
+            blockchain.ledger = Ledger::from_blocks(&blocks, spec.economics.max_supply);
+            blockchain.block_tree = BlockTree::from_chain(&blocks);
+            blockchain.chain = blocks;
+            blockchain.mempool.set_base_fee(blockchain.next_base_fee());
+            if let Some(state) = state {
+                blockchain.restore_state(state);
+            }
+            println!("📂 Loaded {} blocks from {}", blockchain.chain.len(), data_dir);
+        }
+
+        Ok(blockchain)
+    }
+
+    // The genesis block commits to the whole spec through its first transaction, then mints the allocations
+    fn genesis_block(spec: &ChainSpec) -> Block {
+        let genesis_tx = |to: &str, amount: Amount, signature: String| Transaction {
+            from: "genesis".to_string(),
+            to: to.to_string(),
+            amount,
+            timestamp: spec.genesis.timestamp,
+            signature,
+            public_key: None,
+            nonce: None,
+            ..Default::default()
+        };
+
+        let mut transactions = vec![genesis_tx("genesis", Amount::ZERO, format!("chain-spec:{}", spec.digest()))];
+        for (i, allocation) in spec.genesis.allocations.iter().enumerate() {
+            let mut tx = genesis_tx(&allocation.address, allocation.amount, format!("genesis-allocation:{}", i));
+            tx.lock = allocation.lock;
+            transactions.push(tx);
+        }
+
+        let mut genesis_block = Block::new(0, transactions, "0".to_string(), "genesis".to_string());
+        genesis_block.timestamp = spec.genesis.timestamp;
+        genesis_block.reward = Amount::ZERO;
+        genesis_block.with_difficulty(spec.consensus.initial_difficulty)
+    }
+
+    // Stored chains must start from the spec's genesis; ones created before chain specs are kept as they are
+    fn check_genesis(stored: &Block, spec: &ChainSpec) -> Result<(), String> {
+        let expected = Self::genesis_block(spec);
+        if stored.hash == expected.hash {
+            return Ok(());
+        }
+        if stored.transactions.first().is_some_and(|tx| tx.signature == "genesis") {
+            println!("⚠️ Chain data predates chain specs; keeping its genesis {} as {}", stored.hash, spec.chain_id);
+            return Ok(());
+        }
+        Err(format!(
+            "Chain data has genesis {} but chain spec {} gives {}",
+            stored.hash, spec.chain_id, expected.hash
+        ))
+    }
+
+    fn empty(spec: &ChainSpec) -> Self {
+        let mut token_system = TokenLaunchSystem::new();
+        token_system.launch_fee = spec.economics.token_launch_fee;
+
+        let mut multisig = MultisigRegistry::new();
+        let launch_fee_account = match &spec.economics.treasury {
+            Some(treasury) => multisig.create_account(treasury.clone()).expect("treasury is checked with the chain spec"),
+            None => "token_launch_fees".to_string(),
+        };
+
+        Blockchain {
+            chain_id: spec.chain_id.clone(),
             chain: Vec::new(),
-            difficulty: 2,
-            pending_transactions: Vec::new(),
-            balances: HashMap::new(),
-            mining_reward: 10.0,
+            retarget: spec.consensus.clone(),
+            mempool: Mempool::new(),
+            ledger: Ledger::with_supply_cap(spec.economics.max_supply),
+            emission: EmissionSchedule::new(&spec.economics),
             connections: HashMap::new(),
-            max_supply: 21_000_000.0,
-            circulating_supply: 0.0,
+            signup_bonus: spec.economics.signup_bonus,
             address_labels: HashMap::new(),
             address_to_username: HashMap::new(),
             // Initialize security and enhanced features
             security_manager: SecurityManager::new(),
-            enhanced_tx_pool: TransactionPool::new(),
-            token_system: TokenLaunchSystem::new(),
+            token_system,
             social_mining: SocialMiningSystem::new(),
-        };
-        blockchain.create_genesis_block();
-        blockchain
+            multisig,
+            launch_fee_account,
+            payout_authority: spec.payout_authority(),
+            payout_keys: spec.economics.payout_authority.is_none().then(chain_spec::dev_payout_keys),
+            storage: None,
+            tip_signal: TipSignal::default(),
+            mining_requests: None,
+            gossip: None,
+            block_tree: BlockTree::new(),
+            reorgs: VecDeque::new(),
+        }
     }
 
-    fn create_genesis_block(&mut self) {
-        let genesis_tx = Transaction {
-            from: "genesis".to_string(),
-            to: "genesis".to_string(),
-            amount: 0.0,
-            timestamp: 0,
-            signature: "genesis".to_string(),
-        };
-
-        let genesis_block = Block::new(0, vec![genesis_tx], "0".to_string(), "genesis".to_string());
-        self.chain.push(genesis_block);
-        self.update_balances();
+    // Push a block onto the chain, apply it to the ledger and append it to the on-disk log;
+    // returns the hashes of the transactions the ledger applied
+    fn append_block(&mut self, block: Block) -> HashSet<String> {
+        if let Some(storage) = &self.storage {
+            if let Err(e) = storage.append_block(&block) {
+                println!("⚠️ Failed to persist block {}: {}", block.index, e);
+            }
+        }
+        if let Err(e) = self.block_tree.insert(block.clone()) {
+            println!("⚠️ {}", e);
+        }
+        let applied = self.ledger.apply_block(&block);
+        self.chain.push(block);
+        self.mempool.set_base_fee(self.next_base_fee());
+        self.tip_signal.advance();
+        applied
     }
 
-    // Original transaction creation (keep for compatibility)
-    fn create_transaction(&mut self, from: String, to: String, amount: f64) -> Result<String, String> {
-        if from != "genesis" && from != "mining_reward" && from != "connection_reward" && from != "social_mining" {
-            let balance = self.get_balance(&from);
-            if balance < amount {
-                return Err(format!("Insufficient balance. Have: {}, Need: {}", balance, amount));
+    // Snapshot the subsystems that are not rebuilt from the chain
+    fn save_state(&self) {
+        if let Some(storage) = &self.storage {
+            let state = PersistedState {
+                address_labels: self.address_labels.clone(),
+                address_to_username: self.address_to_username.clone(),
+                mempool: self.mempool.snapshot(),
+                pending_transactions: Vec::new(),
+                queued_transactions: Vec::new(),
+                token_system: self.token_system.clone(),
+                social_mining: self.social_mining.clone(),
+                blacklisted_addresses: self.security_manager.blacklisted_addresses(),
+                multisig: self.multisig.clone(),
+            };
+            if let Err(e) = storage.save_state(&state) {
+                println!("⚠️ Failed to persist node state: {}", e);
             }
         }
+    }
 
-        let transaction = Transaction {
-            from: from.clone(),
-            to: to.clone(),
+    fn restore_state(&mut self, state: PersistedState) {
+        self.address_labels = state.address_labels;
+        self.address_to_username = state.address_to_username;
+        // Snapshots from before the unified pool list bare transfers; admit them as new
+        let legacy = state.pending_transactions.into_iter()
+            .chain(state.queued_transactions)
+            .map(|tx| PoolEntry::new(tx.hash(), tx));
+        self.mempool = Mempool::new();
+        self.mempool.set_base_fee(self.next_base_fee());
+        let ledger = &self.ledger;
+        self.mempool.restore(state.mempool.into_iter().chain(legacy).collect(), |address| ledger.nonce(address));
+        // The launch fee comes from the chain spec, not the snapshot
+        let launch_fee = self.token_system.launch_fee;
+        self.token_system = state.token_system;
+        self.token_system.launch_fee = launch_fee;
+        self.social_mining = state.social_mining;
+        self.security_manager.restore_blacklist(state.blacklisted_addresses);
+        // Likewise the treasury policy
+        let treasury = self.multisig.policy(&self.launch_fee_account).cloned();
+        self.multisig = state.multisig;
+        if let Some(treasury) = treasury {
+            let _ = self.multisig.create_account(treasury);
+        }
+    }
+
+    // Original transaction creation; the sender must have signed the transfer
+    fn create_transaction(&mut self, transaction: Transaction) -> Result<String, String> {
+        if crypto::is_system_address(&transaction.from) {
+            return Err("System accounts cannot be spent from directly".to_string());
+        }
+        if !crypto::is_system_address(&transaction.to) {
+            crypto::validate_address(&transaction.to)?;
+        }
+        self.require_fee(transaction.fee)?;
+        transaction.verify_signature()?;
+        let gossip = p2p::Message::NewTransaction { transaction: transaction.clone() };
+        let result = self.queue_transaction(transaction)?;
+        self.announce(gossip);
+        Ok(result)
+    }
+
+    fn create_system_transaction(&mut self, from: &str, to: String, amount: Amount) -> Result<String, String> {
+        let transaction = self.payout(from, to, amount)?;
+        self.queue_transaction(transaction)
+    }
+
+    // Protocol payouts (signup bonus, rewards, token pool payouts) are signed with the payout key,
+    // under a random nonce the ledger accepts only once
+    fn payout(&self, from: &str, to: String, amount: Amount) -> Result<Transaction, String> {
+        let keys = self.payout_keys.as_ref()
+            .ok_or("This node holds no payout key, so it cannot make protocol payouts")?;
+        let mut transaction = Transaction {
+            from: from.to_string(),
+            to,
             amount,
             timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-            signature: format!("sig_{}_{}", from, rand::random::<u64>()),
+            public_key: Some(keys.public_key_hex()),
+            nonce: Some(rand::random()),
+            ..Default::default()
         };
+        transaction.signature = keys.sign(transaction.signing_payload().as_bytes());
+        Ok(transaction)
+    }
+
+    // Take over payouts with the key of the chain spec's payout authority
+    fn set_payout_key(&mut self, keys: KeyPair) -> Result<(), String> {
+        if keys.public_key_hex() != self.payout_authority {
+            return Err("Payout key does not belong to the chain spec's payout authority".to_string());
+        }
+        self.payout_keys = Some(keys);
+        Ok(())
+    }
+
+    fn queue_transaction(&mut self, transaction: Transaction) -> Result<String, String> {
+        let from = transaction.from.clone();
+        if ledger::is_minting_source(&from) {
+            let remaining = self.emission.remaining(self.issued_supply());
+            if transaction.amount > remaining {
+                return Err(format!("Maximum supply reached: {} left to issue, {} requested", remaining, transaction.amount));
+            }
+        } else {
+            // Transfers already waiting in the pool are spoken for
+            self.mempool.check_funds(&transaction, self.spendable_balance(&from))?;
+        }
+
+        // Transfers submitted without an enhanced id are tracked by their hash
+        let next_nonce = self.next_nonce(&from);
+        match self.mempool.add(transaction.hash(), transaction, self.ledger.nonce(&from))? {
+            Admission::Queued => Ok(format!("Transaction queued until nonce {} is used", next_nonce)),
+            Admission::Ready => Ok("Transaction added to pending pool".to_string()),
+        }
+    }
 
-        self.pending_transactions.push(transaction);
-        Ok("Transaction added to pending pool".to_string())
+    // Nonce the sender's next transfer must carry, counting transfers already pending
+    fn next_nonce(&self, address: &str) -> u64 {
+        self.mempool.next_nonce(address, self.ledger.nonce(address))
+    }
+
+    // The fee is part of what the sender signs, so a missing one cannot be filled in for them
+    fn require_fee(&self, fee: Option<Amount>) -> Result<Amount, String> {
+        fee.ok_or_else(|| format!(
+            "A fee is required and is part of the signed transfer; the current base fee is {}", self.next_base_fee()))
+    }
+
+    // Transfers that act immediately (token trades) cannot wait in the future-nonce queue
+    fn require_next_nonce(&self, transaction: &Transaction) -> Result<(), String> {
+        let next_nonce = self.next_nonce(&transaction.from);
+        match transaction.nonce {
+            Some(nonce) if nonce == next_nonce => Ok(()),
+            _ => Err(format!("Expected nonce {} for {}", next_nonce, transaction.from)),
+        }
     }
 
     // New enhanced transaction creation with security
-    fn create_enhanced_transaction(&mut self, req: EnhancedTransactionRequest) -> Result<String, String> {
+    // Returns the id the node assigned along with a status message
+    fn create_enhanced_transaction(&mut self, req: EnhancedTransactionRequest) -> Result<(String, String), String> {
+        if crypto::is_system_address(&req.from) {
+            return Err("System accounts cannot be spent from directly".to_string());
+        }
+
         // Security checks
         match self.security_manager.check_transaction_security(&req.from, &req.to, req.amount) {
             Ok(_) => {},
@@ -334,17 +935,6 @@ impl Blockchain {
             },
         }
 
-        // Balance check including fee
-        if req.from != "genesis" && req.from != "mining_reward" && req.from != "connection_reward" && req.from != "social_mining" {
-            let balance = self.get_balance(&req.from);
-            let total_needed = req.amount + req.fee;
-            if balance < total_needed {
-                self.security_manager.record_failed_attempt(&req.from);
-                return Err(format!("Insufficient balance. Have: {}, Need: {} (including fee: {})", 
-                                 balance, total_needed, req.fee));
-            }
-        }
-
         // Create enhanced transaction
         let mut enhanced_tx = EnhancedTransaction::new(req.from.clone(), req.to.clone(), req.amount, req.fee);
         
@@ -352,28 +942,141 @@ impl Blockchain {
         if let Some(message) = req.message {
             enhanced_tx = enhanced_tx.with_message(message);
         }
+        if let Some(lock) = req.lock {
+            enhanced_tx = enhanced_tx.with_lock(lock);
+        }
 
-        let tx_id = enhanced_tx.id.clone();
-
-        // Add to enhanced pool
-        if let Err(e) = self.enhanced_tx_pool.add_transaction(enhanced_tx.clone()) {
-            return Err(e);
+        // Nonce and timestamp are part of what the client signed
+        let nonce = req.auth.nonce
+            .ok_or_else(|| format!("Missing nonce; fetch it from /rpc/account/{}/nonce", req.from))?;
+        enhanced_tx = enhanced_tx.with_nonce(nonce);
+        if let Some(timestamp) = req.auth.timestamp {
+            enhanced_tx = enhanced_tx.with_timestamp(timestamp);
+        }
+        if let (Some(public_key), Some(signature)) = (req.auth.public_key, req.auth.signature) {
+            enhanced_tx = enhanced_tx.with_signature(public_key, signature);
         }
 
-        // Also add to legacy pool for compatibility
-        let legacy_tx = enhanced_tx.to_legacy_transaction();
-        self.pending_transactions.push(legacy_tx);
+        let tx_id = enhanced_tx.id.clone();
+        let admission = self.add_enhanced_transaction(enhanced_tx);
+        if admission.is_err() {
+            self.security_manager.record_failed_attempt(&req.from);
+        }
+        if admission? == Admission::Queued {
+            let message = format!("Enhanced transaction queued with ID: {}", tx_id);
+            return Ok((tx_id, message));
+        }
 
         println!("🔒 Enhanced transaction created: {} -> {} (Amount: {}, Fee: {}, ID: {})", 
                  req.from, req.to, req.amount, req.fee, tx_id);
 
-        Ok(format!("Enhanced transaction created with ID: {}", tx_id))
+        let message = format!("Enhanced transaction created with ID: {}", tx_id);
+        Ok((tx_id, message))
+    }
+
+    // Admit a signed enhanced transaction, whether built from a local request or gossiped by a peer
+    fn add_enhanced_transaction(&mut self, enhanced_tx: EnhancedTransaction) -> Result<Admission, String> {
+        if crypto::is_system_address(&enhanced_tx.from) {
+            return Err("System accounts cannot be spent from directly".to_string());
+        }
+
+        enhanced_tx.validate()?;
+        // Only the owner of the sending address may spend from it
+        enhanced_tx.verify_signature()?;
+
+        // Balance check including the fee and whatever the sender already has pending
+        let transaction = Transaction::from_enhanced(&enhanced_tx);
+        enhanced_tx.total_cost()?;
+        self.mempool.check_funds(&transaction, self.spendable_balance(&enhanced_tx.from))?;
+
+        // Future nonces wait in the pool until the gap is filled
+        let next_nonce = self.next_nonce(&enhanced_tx.from);
+        let confirmed_nonce = self.ledger.nonce(&enhanced_tx.from);
+        let admission = self.mempool.add(enhanced_tx.id.clone(), transaction, confirmed_nonce)?;
+        self.announce(p2p::Message::NewEnhancedTransaction { transaction: enhanced_tx.clone() });
+        if admission == Admission::Queued {
+            println!("⏳ Enhanced transaction {} queued until nonce {} is used", enhanced_tx.id, next_nonce);
+        } else {
+            println!("📥 Transaction added to pool: {}", enhanced_tx.summary());
+        }
+        Ok(admission)
+    }
+
+    // Replace the sender's waiting transfer with an empty self-transfer that uses up its nonce
+    fn cancel_transaction(&mut self, req: CancelTransactionRequest) -> Result<(String, String), String> {
+        let nonce = req.auth.nonce.ok_or("Missing nonce of the transaction to cancel")?;
+        let cancelled = self.mempool.find(&req.from, nonce)
+            .map(|entry| entry.id.clone())
+            .ok_or_else(|| format!("No pending transaction from {} with nonce {}", req.from, nonce))?;
+
+        let mut cancellation = EnhancedTransaction::cancellation(req.from, nonce, req.fee);
+        if let Some(timestamp) = req.auth.timestamp {
+            cancellation = cancellation.with_timestamp(timestamp);
+        }
+        if let (Some(public_key), Some(signature)) = (req.auth.public_key, req.auth.signature) {
+            cancellation = cancellation.with_signature(public_key, signature);
+        }
+        let tx_id = cancellation.id.clone();
+        self.add_enhanced_transaction(cancellation)?;
+
+        println!("🚫 Transaction {} cancelled by {}", cancelled, tx_id);
+        Ok((tx_id, format!("Transaction {} cancelled", cancelled)))
+    }
+
+    // Register an M-of-N policy so transfers can be proposed from its address
+    fn create_multisig_account(&mut self, req: MultisigCreateRequest) -> Result<String, String> {
+        let policy = MultisigPolicy::new(req.threshold, req.public_keys)?;
+        let address = self.multisig.create_account(policy)?;
+        println!("🔐 Multisig account {} created ({} of {})", address, req.threshold,
+                 self.multisig.policy(&address).map_or(0, |policy| policy.public_keys.len()));
+        self.save_state();
+        Ok(address)
+    }
+
+    // Open a transfer from a multisig account for its members to sign, numbered after any still open
+    fn propose_multisig_transaction(&mut self, req: MultisigProposeRequest) -> Result<Proposal, String> {
+        crypto::validate_address(&req.to)?;
+        let min_fee = self.mempool.min_fee();
+        if req.fee < min_fee {
+            return Err(format!("Transaction fee too low. Minimum: {}", min_fee));
+        }
+
+        let nonce = self.multisig.next_nonce(&req.from, self.next_nonce(&req.from));
+        let mut tx = EnhancedTransaction::new(req.from, req.to, req.amount, req.fee).with_nonce(nonce);
+        if let Some(message) = req.message {
+            tx = tx.with_message(message);
+        }
+        let proposal = self.multisig.propose(tx)?.clone();
+        println!("📝 Multisig proposal {}: {} -> {} ({} L1, nonce {})", proposal.id,
+                 proposal.transaction.from, proposal.transaction.to, proposal.transaction.amount, nonce);
+        self.save_state();
+        Ok(proposal)
+    }
+
+    // Add a member's signature; once enough are in, the transfer goes to the pool like any other.
+    // Returns whether it was submitted, with a status message
+    fn sign_multisig_proposal(&mut self, req: MultisigSignRequest) -> Result<(bool, String), String> {
+        let signature = MultisigSignature { public_key: req.public_key, signature: req.signature };
+        let signed = self.multisig.sign(&req.proposal_id, signature);
+        self.save_state();
+        let Some(tx) = signed? else {
+            let proposal = self.multisig.proposals(None).into_iter().find(|p| p.id == req.proposal_id);
+            let (collected, threshold) = proposal.map_or((0, 0), |p| (p.signatures.len(), p.threshold));
+            return Ok((false, format!("Signature recorded; {} of {} collected", collected, threshold)));
+        };
+
+        // A failed submission keeps the proposal, so it can be retried by signing again
+        self.add_enhanced_transaction(tx)?;
+        self.multisig.remove(&req.proposal_id);
+        self.save_state();
+        println!("🔐 Multisig proposal {} submitted", req.proposal_id);
+        Ok((true, format!("Proposal {} has enough signatures and was submitted", req.proposal_id)))
     }
 
-    // Enhanced mining with security and transaction fees
-    fn mine_enhanced_block(&mut self, miner_address: String) -> Result<String, String> {
+    // Security checks for /rpc/mine before building its template
+    fn enhanced_block_template(&mut self, miner_address: &str) -> Result<BlockTemplate, String> {
         // Security checks for mining
-        match self.security_manager.check_mining_security(&miner_address) {
+        match self.security_manager.check_mining_security(miner_address) {
             Ok(_) => {},
             Err(SecurityError::RateLimitExceeded) => {
                 return Err("Mining rate limit exceeded. Please wait before mining again.".to_string());
@@ -386,86 +1089,318 @@ impl Blockchain {
             },
         }
 
-        if self.pending_transactions.is_empty() {
+        self.block_template(miner_address)
+    }
+
+    // Build an unmined block over the pending pool; cheap enough to do under the lock.
+    // Transactions the state can no longer pay for are failed out of the pool on the way.
+    fn block_template(&mut self, miner_address: &str) -> Result<BlockTemplate, String> {
+        let height = self.height() + 1;
+        let base_fee = self.next_base_fee();
+        // Later than the recent median even when blocks come faster than the clock ticks
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let timestamp = now.max(validation::median_time_past(&self.chain) + 1);
+        let ledger = &self.ledger;
+        let mut spendable: HashMap<String, Amount> = HashMap::new();
+
+        // Highest fees first, within the block size limit; the rest wait for a later block.
+        // Only debits are tracked, so a transfer never relies on credits later in the block.
+        let (selected, failed) = self.mempool.select(MAX_BLOCK_TRANSACTIONS, |entry| {
+            let from = &entry.transaction.from;
+            if ledger::is_minting_source(from) {
+                return Ok(());
+            }
+            let balance = spendable.entry(from.clone())
+                .or_insert_with(|| ledger.balance(from).saturating_sub(ledger.locked_balance(from, height, timestamp)));
+            if *balance < entry.cost() {
+                return Err(format!("insufficient balance when mined ({} spendable, {} needed)", balance, entry.cost()));
+            }
+            *balance = balance.saturating_sub(entry.cost());
+            Ok(())
+        });
+        let selected: Vec<(Transaction, Amount)> = selected.into_iter()
+            .map(|entry| (entry.transaction.clone(), entry.fee()))
+            .collect();
+        for (hash, reason) in failed {
+            self.mempool.fail(&hash, &reason);
+        }
+        if selected.is_empty() {
             return Err("No pending transactions to mine".to_string());
         }
 
-        // Get transactions sorted by priority (fee)
-        let priority_txs = self.enhanced_tx_pool.get_transactions_by_priority();
-        let mut total_fees = 0.0;
-        let mut confirmed_tx_ids = Vec::new();
-
-        // Process enhanced transactions and calculate fees
-        for enhanced_tx in priority_txs.iter().take(100) { // Limit block size
-            total_fees += enhanced_tx.fee;
-            confirmed_tx_ids.push(enhanced_tx.id.clone());
+        // The ledger burns every fee; the miner's share is minted back with the reward
+        let mut total_fees = Amount::ZERO;
+        for (_, fee) in &selected {
+            let (_, tip) = fee_market::split_fee(*fee, base_fee);
+            total_fees = total_fees.try_add(tip)?;
         }
 
-        // Mining reward transaction (includes collected fees)
-        let total_reward = self.mining_reward + total_fees;
-        let reward_tx = Transaction {
+        // Coinbase: subsidy for this height plus the miner's share of fees, within what is left to
+        // issue. Every block carries one, last, even once nothing is left; the block records the
+        // schedule's subsidy as its reward.
+        let previous_block = self.chain.last().unwrap();
+        let remaining = self.emission.remaining(self.issued_supply());
+        let subsidy = self.emission.block_subsidy(height);
+        let total_reward = subsidy.try_add(total_fees)?.min(remaining);
+        let mut transactions: Vec<Transaction> = selected.into_iter().map(|(tx, _)| tx).collect();
+        transactions.push(Transaction {
             from: "mining_reward".to_string(),
-            to: miner_address.clone(),
+            to: miner_address.to_string(),
             amount: total_reward,
             timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
             signature: format!("mining_reward_{}", rand::random::<u64>()),
-        };
-        self.pending_transactions.push(reward_tx);
+            public_key: None,
+            nonce: None,
+            ..Default::default()
+        });
 
-        let previous_block = self.chain.last().unwrap();
-        let mut new_block = Block::new(
-            previous_block.index + 1,
-            self.pending_transactions.clone(),
+        let mut block = Block::new(
+            height,
+            transactions,
             previous_block.hash.clone(),
-            miner_address.clone(),
+            miner_address.to_string(),
         );
-        
-        new_block.mine_block(self.difficulty);
-        self.chain.push(new_block);
-        
-        // Confirm transactions in enhanced pool
-        for tx_id in confirmed_tx_ids {
-            let _ = self.enhanced_tx_pool.confirm_transaction(&tx_id);
+        block.timestamp = timestamp;
+        block.reward = subsidy;
+        block.base_fee = base_fee;
+        let block = block.with_difficulty(self.current_difficulty());
+
+        Ok(BlockTemplate {
+            block,
+            tip: self.tip_signal.current(),
+            tip_signal: self.tip_signal.clone(),
+            total_reward,
+            total_fees,
+        })
+    }
+
+    // Accept a solved template if it still extends the tip; transactions that arrived meanwhile stay pending
+    fn submit_block(&mut self, template: BlockTemplate, block: Block) -> Result<String, String> {
+        let tip_hash = self.chain.last().map(|b| b.hash.as_str()).unwrap_or_default();
+        if block.previous_hash != tip_hash {
+            return Err("Chain tip changed while mining; try again".to_string());
         }
-        
-        self.update_balances();
-        self.pending_transactions.clear();
 
-        println!("⛏️ Enhanced block mined by {} with {} total reward (including {} fees)", 
-                 miner_address, total_reward, total_fees);
+        let (index, miner) = (block.index, block.miner.clone());
+        let gossip = p2p::Message::NewBlock { block: block.clone() };
+        self.accept_block(block)?;
+        self.announce(gossip);
 
-        Ok(format!("Enhanced block mined successfully! Total reward: {}", total_reward))
+        println!("⛏️ Block {} mined by {} with {} total reward (including {} fees)",
+                 index, miner, template.total_reward, template.total_fees);
+
+        Ok(format!("Block {} mined successfully! Total reward: {}", index, template.total_reward))
     }
 
-    // Keep original mining method for compatibility
-    fn mine_pending_transactions(&mut self, miner_address: String) {
-        if self.pending_transactions.is_empty() {
-            println!("No pending transactions to mine");
-            return;
+    // Add a validated block, mined here or received from a peer, to the block tree. It extends
+    // the tip, or is kept on a side branch until that branch has more work than the active chain.
+    fn accept_block(&mut self, block: Block) -> Result<(), String> {
+        if self.block_tree.contains(&block.hash) {
+            return Err(format!("Block {} is already known", block.hash));
         }
 
-        let reward_tx = Transaction {
-            from: "mining_reward".to_string(),
-            to: miner_address.clone(),
-            amount: self.mining_reward,
+        let extends_tip = self.chain.last().is_some_and(|tip| tip.hash == block.previous_hash);
+        let validated = if extends_tip {
+            validation::validate_next_block(&self.chain, &block, &self.retarget)
+        } else {
+            let parents = self.block_tree.ancestors(&block.previous_hash, validation::parents_needed(&self.retarget));
+            if parents.is_empty() {
+                return Err(format!("Rejected block {}: unknown parent {}", block.index, block.previous_hash));
+            }
+            validation::validate_next_block(&parents, &block, &self.retarget)
+        };
+        validated.map_err(|e| format!("Rejected {}", e))?;
+
+        if extends_tip {
+            let included = self.append_block(block);
+
+            let ledger = &self.ledger;
+            self.mempool.settle(&included, |address| ledger.nonce(address));
+            self.save_state();
+            return Ok(());
+        }
+
+        let (index, hash) = (block.index, block.hash.clone());
+        let total_work = self.block_tree.insert(block)?;
+        let tip_work = self.chain.last().map_or(0, |tip| self.block_tree.total_work(&tip.hash));
+        if total_work > tip_work {
+            self.reorganize(&hash)
+        } else {
+            println!("🌿 Block {} ({}) stored on a side branch", index, hash);
+            Ok(())
+        }
+    }
+
+    // Make the branch ending at `new_tip` the active chain: revert the blocks it replaces,
+    // apply its own, and return transactions only the abandoned blocks carried to the pool.
+    // If any block of the branch carries a transaction the ledger would skip, the old blocks are
+    // put back and the active chain is left as it was.
+    fn reorganize(&mut self, new_tip: &str) -> Result<(), String> {
+        let chain = &self.chain;
+        let branch = self.block_tree.branch(new_tip, |block| {
+            chain.get(block.index as usize).is_some_and(|active| active.hash == block.hash)
+        });
+        let fork_height = branch.first()
+            .map(|block| block.index - 1)
+            .ok_or("New tip is already on the active chain")?;
+        let disconnected = self.chain[fork_height as usize + 1..].to_vec();
+        if !self.ledger.can_revert(&disconnected) {
+            return Err(format!("Refusing to reorganize {} blocks deep", disconnected.len()));
+        }
+
+        let old_tip = self.chain.last().map(|block| block.hash.clone()).unwrap_or_default();
+        for block in disconnected.iter().rev() {
+            self.ledger.revert_block(block)?;
+        }
+        let mut confirmed = HashSet::new();
+        for (connected, block) in branch.iter().enumerate() {
+            if let Err(e) = self.ledger.check_block(block) {
+                for applied in branch[..connected].iter().rev() {
+                    self.ledger.revert_block(applied)?;
+                }
+                for block in &disconnected {
+                    self.ledger.apply_block(block);
+                }
+                return Err(format!("Rejected branch at block {}: {}", block.index, e));
+            }
+            confirmed.extend(self.ledger.apply_block(block));
+        }
+        self.chain.truncate(fork_height as usize + 1);
+        self.chain.extend(branch.iter().cloned());
+        self.mempool.set_base_fee(self.next_base_fee());
+        self.tip_signal.advance();
+
+        if let Some(storage) = &self.storage {
+            if let Err(e) = storage.replace_blocks(&self.chain) {
+                println!("⚠️ Failed to persist reorganized chain: {}", e);
+            }
+        }
+
+        // Block rewards belong to the block that earned them and are not carried over
+        let orphaned: Vec<Transaction> = disconnected.iter()
+            .flat_map(|block| &block.transactions)
+            .filter(|tx| tx.from != "mining_reward" && !confirmed.contains(&tx.hash()))
+            .cloned()
+            .collect();
+        let event = ReorgEvent {
+            fork_height,
+            old_tip,
+            new_tip: new_tip.to_string(),
+            disconnected: disconnected.iter().map(|block| block.hash.clone()).collect(),
+            connected: branch.iter().map(|block| block.hash.clone()).collect(),
+            orphaned_transactions: orphaned.len(),
             timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
-            signature: format!("mining_reward_{}", rand::random::<u64>()),
         };
-        self.pending_transactions.push(reward_tx);
 
-        let previous_block = self.chain.last().unwrap();
-        let mut new_block = Block::new(
-            previous_block.index + 1,
-            self.pending_transactions.clone(),
-            previous_block.hash.clone(),
-            miner_address.clone(),
-        );
-        
-        new_block.mine_block(self.difficulty);
-        self.chain.push(new_block);
-        
-        self.update_balances();
-        self.pending_transactions.clear();
+        self.mempool.reinstate(orphaned);
+        let ledger = &self.ledger;
+        self.mempool.settle(&confirmed, |address| ledger.nonce(address));
+
+        println!("🔀 Reorganized at height {}: {} block(s) replaced by {}, {} transaction(s) back in the pool",
+                 event.fork_height, event.disconnected.len(), event.connected.len(), event.orphaned_transactions);
+        if self.reorgs.len() >= MAX_REORG_EVENTS {
+            self.reorgs.pop_front();
+        }
+        self.reorgs.push_back(event);
+        self.save_state();
+        Ok(())
+    }
+
+    // Validate a block from a peer: everything a locally mined block gets, plus its signatures, the
+    // payout authority's on every payout besides the coinbase, a coinbase within subsidy and fees,
+    // and no transaction the ledger would skip, whose tip the coinbase could claim unpaid
+    fn import_block(&mut self, block: Block) -> Result<(), String> {
+        validation::validate_peer_transactions(&block, &self.payout_authority)
+            .and_then(|_| validation::validate_coinbase(&block, self.emission.block_subsidy(block.index)))
+            .map_err(|e| format!("Rejected {}", e))?;
+        // A block on a side branch is checked against its own branch when that is connected
+        if self.chain.last().is_some_and(|tip| tip.hash == block.previous_hash) {
+            self.ledger.check_block(&block).map_err(|e| format!("Rejected block {}: {}", block.index, e))?;
+        }
+        self.accept_block(block)
+    }
+
+    // Import a gossiped block and pass it on to the other peers
+    fn receive_block(&mut self, block: Block) -> Result<(), String> {
+        let gossip = p2p::Message::NewBlock { block: block.clone() };
+        self.import_block(block)?;
+        self.announce(gossip);
+        Ok(())
+    }
+
+    fn height(&self) -> u64 {
+        self.chain.last().map_or(0, |block| block.index)
+    }
+
+    // Hashes a peer can match against its own chain to find where ours diverges:
+    // the last ten blocks, then exponentially sparser back to genesis
+    fn block_locator(&self) -> Vec<String> {
+        let mut locator = Vec::new();
+        let mut height = self.chain.len() as u64;
+        let mut step = 1;
+        while height > 0 {
+            height = height.saturating_sub(step);
+            locator.push(self.chain[height as usize].hash.clone());
+            if locator.len() >= 10 {
+                step *= 2;
+            }
+        }
+        locator
+    }
+
+    // Up to `limit` headers of the active chain following the first locator hash on it
+    fn headers_after(&self, locator: &[String], limit: u64) -> Vec<BlockHeader> {
+        let start = locator.iter()
+            .find_map(|hash| {
+                let block = self.block_tree.get(hash)?;
+                let active = self.chain.get(block.index as usize)?;
+                (active.hash == *hash).then_some(block.index as usize + 1)
+            })
+            .unwrap_or(1);
+        self.chain.iter()
+            .skip(start)
+            .take(usize::try_from(limit).unwrap_or(usize::MAX))
+            .map(Block::header)
+            .collect()
+    }
+
+    // The requested blocks this node has, on any branch, in request order
+    fn blocks_by_hash(&self, hashes: &[String]) -> Vec<Block> {
+        hashes.iter()
+            .filter_map(|hash| self.block_tree.get(hash).cloned())
+            .collect()
+    }
+
+    // Hand a new block or transaction to the peer network, if this node is part of one
+    fn announce(&self, message: p2p::Message) {
+        if let Some(gossip) = &self.gossip {
+            let _ = gossip.send(message);
+        }
+    }
+
+    // Mine on the calling thread; for tests and nodes without a background miner
+    fn mine_pending_transactions(&mut self, miner_address: String) {
+        let template = match self.block_template(&miner_address) {
+            Ok(template) => template,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+
+        let mut block = template.block.clone();
+        block.mine_block();
+        if let Err(e) = self.submit_block(template, block) {
+            println!("⚠️ {}", e);
+        }
+    }
+
+    // Mine whatever is pending without blocking the caller when a background miner is running
+    fn request_block(&mut self, miner_address: &str) {
+        match &self.mining_requests {
+            Some(requests) if requests.send(miner_address.to_string()).is_ok() => {},
+            _ => self.mine_pending_transactions(miner_address.to_string()),
+        }
     }
 
     fn connect_user(&mut self, address: String) -> Result<String, String> {
@@ -499,7 +1434,7 @@ impl Blockchain {
             address: address.clone(),
             connected_at: now,
             last_activity: now,
-            total_rewards: 0.0,
+            total_rewards: Amount::ZERO,
             is_active: true,
         };
         
@@ -518,18 +1453,16 @@ impl Blockchain {
         }
     }
 
-    fn calculate_connection_reward(&self) -> f64 {
-        // Exponential decay based on circulating supply
-        let remaining_percentage = (self.max_supply - self.circulating_supply) / self.max_supply;
-        let base_reward = 1.0; // Base reward per minute
-        
-        // Exponential decay: reward = base * e^(-decay_rate * supply_used)
-        let decay_rate = 2.0;
-        let supply_used_percentage = 1.0 - remaining_percentage;
-        let reward = base_reward * (-decay_rate * supply_used_percentage).exp();
-        
-        // Minimum reward of 0.001
-        reward.max(0.001)
+    fn calculate_connection_reward(&self) -> Amount {
+        self.emission.connection_reward(self.issued_supply())
+    }
+
+    // Circulating supply plus what minting transactions already in the pool will add
+    fn issued_supply(&self) -> Amount {
+        self.mempool.pending().iter()
+            .map(|entry| &entry.transaction)
+            .filter(|tx| ledger::is_minting_source(&tx.from))
+            .fold(self.ledger.circulating_supply(), |issued, tx| issued.saturating_add(tx.amount))
     }
 
     fn process_connection_rewards(&mut self) {
@@ -537,6 +1470,8 @@ impl Blockchain {
         let reward_per_minute = self.calculate_connection_reward();
         
         let mut rewards_given = Vec::new();
+        // Rewards queued this round count against the cap before they are mined
+        let mut issued = self.issued_supply();
         
         for (address, connection) in self.connections.iter_mut() {
             if !connection.is_active {
@@ -547,35 +1482,38 @@ impl Blockchain {
             
             // Give reward every minute (60 seconds)
             if connected_duration >= 60 && (connected_duration % 60) < 5 { // 5 second window
-                if self.circulating_supply < self.max_supply {
-                    connection.total_rewards += reward_per_minute;
-                    *self.balances.entry(address.clone()).or_insert(0.0) += reward_per_minute;
-                    self.circulating_supply += reward_per_minute;
+                // Never pay out past the supply cap
+                let reward_per_minute = reward_per_minute
+                    .min(self.emission.remaining(issued));
+                if !reward_per_minute.is_zero() {
+                    connection.total_rewards = connection.total_rewards.saturating_add(reward_per_minute);
+                    issued = issued.saturating_add(reward_per_minute);
                     
                     rewards_given.push((address.clone(), reward_per_minute));
-                    
-                    // Create reward transaction
-                    let reward_tx = Transaction {
-                        from: "connection_reward".to_string(),
-                        to: address.clone(),
-                        amount: reward_per_minute,
-                        timestamp: now,
-                        signature: "connection_reward".to_string(),
-                    };
-                    self.pending_transactions.push(reward_tx);
                 }
             }
             
             connection.last_activity = now;
         }
         
-        // Log rewards given
+        // Create reward transactions and log rewards given
         for (address, reward) in rewards_given {
-            println!("🎁 Connection reward given: {} received {:.4} L1", address, reward);
+            let queued = self.payout("connection_reward", address.clone(), reward)
+                .and_then(|reward_tx| self.mempool.add(reward_tx.hash(), reward_tx, 0));
+            match queued {
+                Ok(_) => println!("🎁 Connection reward given: {} received {} L1", address, reward),
+                Err(e) => println!("⚠️ Connection reward for {} not queued: {}", address, e),
+            }
         }
     }
 
-    fn register_username(&mut self, username: String) -> Result<(String, String), String> {
+    // Message an address owner signs to point a username at their address
+    fn username_signing_payload(address: &str, current_username: Option<&str>, username: &str) -> String {
+        format!("layer1-username:{}:{}:{}", address, current_username.unwrap_or(""), username)
+    }
+
+    // Label the address derived from public_key with a username; registering again renames it
+    fn register_username(&mut self, username: String, public_key: String, signature: String) -> Result<(String, String), String> {
         // Validate username format
         if username.len() < 3 || username.len() > 20 {
             return Err("Username must be between 3-20 characters".to_string());
@@ -590,13 +1528,13 @@ impl Blockchain {
             return Err("Username is already taken".to_string());
         }
         
-        // Generate wallet address from username
-        let wallet_address = format!("wallet_{}", username);
-        
-        // Check if this generated address already exists (shouldn't happen but safety check)
-        if self.address_to_username.contains_key(&wallet_address) {
-            return Err("Generated address already exists".to_string());
-        }
+        // The wallet address comes from the owner's public key, not the username
+        let wallet_address = crypto::address_from_public_key(&public_key)?;
+        let current_username = self.address_to_username.get(&wallet_address).cloned();
+
+        // Prove ownership of the address; the current label is signed to prevent replays
+        let payload = Self::username_signing_payload(&wallet_address, current_username.as_deref(), &username);
+        crypto::verify_signature(&public_key, payload.as_bytes(), &signature)?;
         
         let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
         
@@ -604,19 +1542,27 @@ impl Blockchain {
             username: username.clone(),
             address: wallet_address.clone(),
             registered_at: now,
-            is_verified: true, // Auto-verify since we control the address generation
+            is_verified: true, // Ownership proven by the signature above
         };
         
         self.address_labels.insert(username.clone(), label);
         self.address_to_username.insert(wallet_address.clone(), username.clone());
+
+        // Renaming only moves the label; the address and its funds are unchanged
+        if let Some(old_username) = current_username {
+            self.address_labels.remove(&old_username);
+            println!("🏷️ Username changed: {} -> {} for {}", old_username, username, wallet_address);
+            self.save_state();
+            return Ok((username, wallet_address));
+        }
         
         // Give initial balance to new wallet (signup bonus)
-        match self.create_transaction("genesis".to_string(), wallet_address.clone(), 1000.0) {
+        match self.create_system_transaction("genesis", wallet_address.clone(), self.signup_bonus) {
             Ok(_) => {
-                println!("📝 Username registered: {} -> {} (with 1000 L1 signup bonus)", username, wallet_address);
+                println!("📝 Username registered: {} -> {} (with {} L1 signup bonus)", username, wallet_address, self.signup_bonus);
                 
                 // Auto-mine the signup bonus transaction
-                self.mine_pending_transactions("system".to_string());
+                self.request_block("system");
                 
                 Ok((username, wallet_address))
             },
@@ -643,10 +1589,10 @@ impl Blockchain {
     }
     
     // Enhanced create_transaction that supports usernames
-    fn create_transaction_with_labels(&mut self, from: String, to: String, amount: f64) -> Result<String, String> {
+    fn create_transaction_with_labels(&mut self, from: String, to: String, amount: Amount, fee: Option<Amount>, auth: TransferAuth) -> Result<String, String> {
         // Resolve 'from' address if it's a username
         let from_address = if from.starts_with('@') || self.address_labels.contains_key(&from) {
-            let username = if from.starts_with('@') { &from[1..] } else { &from };
+            let username = from.strip_prefix('@').unwrap_or(&from);
             match self.resolve_username(username) {
                 Ok(label) => label.address.clone(),
                 Err(e) => return Err(format!("From address resolution failed: {}", e)),
@@ -657,7 +1603,7 @@ impl Blockchain {
         
         // Resolve 'to' address if it's a username
         let to_address = if to.starts_with('@') || self.address_labels.contains_key(&to) {
-            let username = if to.starts_with('@') { &to[1..] } else { &to };
+            let username = to.strip_prefix('@').unwrap_or(&to);
             match self.resolve_username(username) {
                 Ok(label) => label.address.clone(),
                 Err(e) => return Err(format!("To address resolution failed: {}", e)),
@@ -667,7 +1613,7 @@ impl Blockchain {
         };
         
         // Use the existing create_transaction method with resolved addresses
-        self.create_transaction(from_address, to_address, amount)
+        self.create_transaction(Transaction::signed(from_address, to_address, amount, fee, auth))
     }
     
     // Enhanced transaction display with usernames
@@ -693,76 +1639,126 @@ impl Blockchain {
 
     // Get enhanced transaction pool statistics
     fn get_pool_stats(&self) -> PoolStats {
-        self.enhanced_tx_pool.get_stats()
+        self.mempool.stats()
     }
 
     // Get transaction receipt
     fn get_transaction_receipt(&self, tx_id: &str) -> Option<TransactionReceipt> {
-        // Check confirmed transactions
-        for tx in self.enhanced_tx_pool.get_confirmed_transactions() {
-            if tx.id == tx_id {
-                // Find block number
-                let block_number = self.chain.iter().enumerate()
-                    .find(|(_, block)| {
-                        block.transactions.iter().any(|t| {
-                            format!("sig_{}_{}", t.from, t.timestamp.to_string()) == tx.signature
-                        })
-                    })
-                    .map(|(i, _)| i as u64);
-                
-                return Some(TransactionReceipt::new(tx, block_number));
-            }
+        // Any state the pool knows; the block number is filled in once it is mined
+        if let Some(entry) = self.mempool.get(tx_id) {
+            let block_number = self.ledger.locate(&entry.hash).map(|location| location.block_index);
+            return Some(entry.receipt(block_number));
         }
-        None
+
+        // Mined before the pool's history reaches back: only the chain has it, under its hash
+        let location = self.ledger.locate(tx_id)?;
+        let tx = self.chain[location.block_index as usize].transactions[location.tx_index].clone();
+        let mut entry = PoolEntry::new(tx_id.to_string(), tx);
+        entry.status = TransactionStatus::Confirmed;
+        Some(entry.receipt(Some(location.block_index)))
+    }
+
+    // Merkle inclusion proof for a mined transaction, looked up by hash or enhanced transaction id
+    fn get_transaction_proof(&self, id: &str) -> Result<TransactionProofResponse, String> {
+        let tx_hash = match self.mempool.get(id) {
+            Some(entry) => entry.hash.clone(),
+            None => id.to_string(),
+        };
+        let location = self.ledger.locate(&tx_hash)
+            .ok_or_else(|| format!("Transaction {} is not in a mined block", id))?;
+        let block = &self.chain[location.block_index as usize];
+        if block.merkle_root.is_empty() {
+            return Err(format!("Block {} predates Merkle roots", block.index));
+        }
+
+        let proof = merkle::merkle_proof(&Block::transaction_hashes(&block.transactions), location.tx_index)
+            .ok_or_else(|| format!("Transaction {} is not in block {}", id, block.index))?;
+        Ok(TransactionProofResponse {
+            tx_hash,
+            tx_index: location.tx_index,
+            transaction: block.transactions[location.tx_index].clone(),
+            header: block.header(),
+            proof,
+        })
     }
 
     // Cleanup expired transactions and security components
     fn cleanup(&mut self) {
-        let expired_count = self.enhanced_tx_pool.cleanup_expired();
+        let expired_count = self.mempool.cleanup_expired();
         if expired_count > 0 {
             println!("🧹 Cleaned up {} expired transactions", expired_count);
         }
+        let expired_proposals = self.multisig.cleanup_expired();
+        if expired_proposals > 0 {
+            println!("🧹 Dropped {} expired multisig proposals", expired_proposals);
+        }
         
         self.security_manager.cleanup();
+        self.save_state();
+    }
+
+    // Get security statistics
+    fn get_security_stats(&self) -> SecurityStats {
+        self.security_manager.get_security_stats()
+    }
+
+    // Admin methods for security management
+    fn admin_blacklist_address(&mut self, address: String, reason: Option<String>) {
+        self.security_manager.admin_blacklist(address, reason);
+        self.save_state();
+    }
+
+    fn admin_unblacklist_address(&mut self, address: &str) -> bool {
+        let removed = self.security_manager.admin_unblacklist(address);
+        self.save_state();
+        removed
+    }
+
+    // Walk the whole chain checking hashes, links and proof-of-work
+    fn validate_chain(&self) -> Result<(), ChainValidationError> {
+        validation::validate_blocks(&self.chain, &self.retarget)
     }
 
-    // Get security statistics
-    fn get_security_stats(&self) -> SecurityStats {
-        self.security_manager.get_security_stats()
+    fn get_balance(&self, address: &str) -> Amount {
+        self.ledger.balance(address)
     }
 
-    // Admin methods for security management
-    fn admin_blacklist_address(&mut self, address: String, reason: Option<String>) {
-        self.security_manager.admin_blacklist(address, reason);
+    // Locks are judged as the next block would: one above the tip, at the current time
+    fn locked_balance(&self, address: &str) -> Amount {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        self.ledger.locked_balance(address, self.height() + 1, now)
     }
 
-    fn admin_unblacklist_address(&mut self, address: &str) -> bool {
-        self.security_manager.admin_unblacklist(address)
+    // What the next block could take from `address`; excludes time-locked and unvested credits
+    fn spendable_balance(&self, address: &str) -> Amount {
+        self.get_balance(address).saturating_sub(self.locked_balance(address))
     }
 
-    fn update_balances(&mut self) {
-        self.balances.clear();
-        self.circulating_supply = 0.0;
-        
-        for block in &self.chain {
-            for transaction in &block.transactions {
-                if transaction.from != "genesis" && transaction.from != "mining_reward" && transaction.from != "connection_reward" && transaction.from != "social_mining" {
-                    *self.balances.entry(transaction.from.clone()).or_insert(0.0) -= transaction.amount;
-                }
-                
-                *self.balances.entry(transaction.to.clone()).or_insert(0.0) += transaction.amount;
-                
-                if transaction.from == "mining_reward" || transaction.from == "connection_reward" || transaction.from == "genesis" || transaction.from == "social_mining" {
-                    self.circulating_supply += transaction.amount;
-                }
-            }
-        }
-        
-        self.balances.retain(|_, &mut balance| balance > 0.0);
+    // Spendable balance less what the account's transfers already in the pool will take
+    fn available_balance(&self, address: &str) -> Amount {
+        self.spendable_balance(address).saturating_sub(self.mempool.pending_cost(address))
     }
 
-    fn get_balance(&self, address: &str) -> f64 {
-        *self.balances.get(address).unwrap_or(&0.0)
+    fn pending_locks(&self, address: &str) -> Vec<BalanceLock> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        self.ledger.account(address).locks.into_iter()
+            .filter(|lock| !lock.locked(self.height() + 1, now).is_zero())
+            .collect()
+    }
+
+    // Difficulty the next block must be mined at
+    fn current_difficulty(&self) -> u64 {
+        difficulty::next_difficulty(&self.chain, &self.retarget)
+    }
+
+    fn get_account_nonce(&self, address: &str) -> AccountNonceResponse {
+        let queued_count = self.mempool.queued().iter().filter(|entry| entry.transaction.from == address).count();
+        AccountNonceResponse {
+            address: address.to_string(),
+            nonce: self.next_nonce(address),
+            confirmed_nonce: self.ledger.nonce(address),
+            queued_count,
+        }
     }
 
     fn get_connection_info(&self, address: &str) -> Option<&Connection> {
@@ -775,72 +1771,116 @@ impl Blockchain {
 
     fn get_network_stats(&self) -> NetworkStats {
         NetworkStats {
-            total_supply: self.max_supply,
-            circulating_supply: self.circulating_supply,
-            remaining_supply: self.max_supply - self.circulating_supply,
+            total_supply: self.emission.max_supply,
+            circulating_supply: self.ledger.circulating_supply(),
+            remaining_supply: self.emission.remaining(self.ledger.circulating_supply()),
             current_reward_rate: self.calculate_connection_reward(),
             active_connections: self.connections.values().filter(|c| c.is_active).count(),
             total_blocks: self.chain.len(),
+            difficulty: self.current_difficulty(),
+            target_block_time: self.retarget.target_block_time,
+        }
+    }
+
+    fn get_emission(&self) -> EmissionResponse {
+        let height = self.height() + 1;
+        let issued = self.issued_supply();
+        let next_halving_height = self.emission.next_halving_height(height);
+        let blocks_until_halving = next_halving_height - height;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        EmissionResponse {
+            height,
+            era: self.emission.era(height),
+            block_subsidy: self.emission.block_subsidy(height).min(self.emission.remaining(issued)),
+            halving_interval: self.emission.halving_interval,
+            next_halving_height,
+            blocks_until_halving,
+            estimated_halving_time: now.saturating_add(blocks_until_halving.saturating_mul(self.retarget.target_block_time)),
+            max_supply: self.emission.max_supply,
+            circulating_supply: self.ledger.circulating_supply(),
+            pending_issuance: issued.saturating_sub(self.ledger.circulating_supply()),
+            remaining_supply: self.emission.remaining(issued),
+            connection_reward_per_minute: self.emission.connection_reward(issued),
+            projection: self.emission.projection(height, issued, emission::PROJECTED_ERAS),
+        }
+    }
+
+    // Base fee the next block must record
+    fn next_base_fee(&self) -> Amount {
+        self.chain.last().map_or(fee_market::MIN_BASE_FEE, fee_market::next_base_fee)
+    }
+
+    fn get_fee_estimate(&self) -> FeeEstimateResponse {
+        let base_fee = self.next_base_fee();
+        let pending_fees: Vec<Amount> = self.mempool.pending().iter()
+            .filter_map(|entry| entry.transaction.fee)
+            .collect();
+        FeeEstimateResponse {
+            height: self.height() + 1,
+            base_fee,
+            min_fee: self.mempool.min_fee(),
+            burn_percent: fee_market::BASE_FEE_BURN_PERCENT,
+            target_block_transactions: fee_market::TARGET_BLOCK_TRANSACTIONS,
+            max_block_transactions: MAX_BLOCK_TRANSACTIONS,
+            pending_count: self.mempool.pending().len(),
+            estimates: fee_market::estimate(&self.chain, base_fee, self.mempool.min_fee(), &pending_fees, self.retarget.target_block_time),
         }
     }
 
     fn get_all_balances(&self) -> Vec<BalanceResponse> {
-        self.balances
-            .iter()
-            .filter(|(_, &balance)| balance > 0.0)
-            .map(|(address, &balance)| BalanceResponse {
+        self.ledger
+            .balances()
+            .map(|(address, balance)| BalanceResponse {
                 address: address.clone(),
                 balance,
             })
             .collect()
     }
 
-    fn get_user_wallet(&self, user_id: &str) -> Option<UserWalletInfo> {
-        let wallet_address = format!("wallet_{}", user_id);
-        let balance = self.get_balance(&wallet_address);
-        
-        if balance > 0.0 || self.balances.contains_key(&wallet_address) {
-            let mut total_sent = 0.0;
-            let mut total_received = 0.0;
-            let mut transaction_count = 0;
-            
-            for block in &self.chain {
-                for tx in &block.transactions {
-                    if tx.from == wallet_address || tx.to == wallet_address {
-                        transaction_count += 1;
-                        if tx.from == wallet_address && tx.from != "genesis" && tx.from != "mining_reward" {
-                            total_sent += tx.amount;
-                        }
-                        if tx.to == wallet_address {
-                            total_received += tx.amount;
-                        }
-                    }
-                }
-            }
-            
-            Some(UserWalletInfo {
-                address: wallet_address,
-                balance,
-                total_sent,
-                total_received,
-                transaction_count,
-            })
-        } else {
-            None
+    fn get_user_wallet(&self, user: &str) -> Option<UserWalletInfo> {
+        let wallet_address = self.resolve_user_address(user).ok()?;
+        if !self.ledger.has_account(&wallet_address) {
+            return None;
+        }
+
+        let account = self.ledger.account(&wallet_address);
+        Some(UserWalletInfo {
+            balance: account.balance,
+            locked_balance: self.locked_balance(&wallet_address),
+            total_sent: account.total_sent,
+            total_received: account.total_received,
+            transaction_count: account.tx_count,
+            address: wallet_address,
+        })
+    }
+
+    // On-chain footprint of a batch of addresses, for wallets scanning their derived accounts
+    fn get_account_activity(&self, addresses: &[String]) -> Result<Vec<AccountActivity>, String> {
+        if addresses.len() > MAX_ACTIVITY_ADDRESSES {
+            return Err(format!("At most {} addresses per request", MAX_ACTIVITY_ADDRESSES));
         }
+        Ok(addresses.iter().map(|address| {
+            let account = self.ledger.account(address);
+            AccountActivity {
+                address: address.clone(),
+                balance: account.balance,
+                transaction_count: account.tx_count,
+            }
+        }).collect())
     }
 
-    fn create_user_wallet(&self, user_id: &str) -> Result<UserWalletInfo, String> {
-        match self.get_user_wallet(user_id) {
+    fn create_user_wallet(&self, public_key: &str) -> Result<UserWalletInfo, String> {
+        let wallet_address = crypto::address_from_public_key(public_key)?;
+        match self.get_user_wallet(&wallet_address) {
             Some(wallet) => Ok(wallet),
             None => {
-                // Return a new wallet info for users that don't exist yet
-                let wallet_address = format!("wallet_{}", user_id);
+                // Return a new wallet info for keys that haven't been used yet
                 Ok(UserWalletInfo {
                     address: wallet_address,
-                    balance: 0.0,
-                    total_sent: 0.0,
-                    total_received: 0.0,
+                    balance: Amount::ZERO,
+                    locked_balance: Amount::ZERO,
+                    total_sent: Amount::ZERO,
+                    total_received: Amount::ZERO,
                     transaction_count: 0,
                 })
             }
@@ -851,34 +1891,18 @@ impl Blockchain {
         // Try to resolve username first
         if let Ok(label) = self.resolve_username(username) {
             let address = &label.address;
-            let balance = self.get_balance(address);
-            
-            let mut total_sent = 0.0;
-            let mut total_received = 0.0;
-            let mut transaction_count = 0;
-            
-            for block in &self.chain {
-                for tx in &block.transactions {
-                    if tx.from == *address || tx.to == *address {
-                        transaction_count += 1;
-                        if tx.from == *address && tx.from != "genesis" && tx.from != "mining_reward" {
-                            total_sent += tx.amount;
-                        }
-                        if tx.to == *address {
-                            total_received += tx.amount;
-                        }
-                    }
-                }
-            }
+            let account = self.ledger.account(address);
             
             Some(WalletInfoResponse {
                 address: address.clone(),
-                balance,
+                balance: account.balance,
+                locked_balance: self.locked_balance(address),
+                locks: self.pending_locks(address),
                 username: Some(username.to_string()),
                 is_verified: label.is_verified,
-                total_sent,
-                total_received,
-                transaction_count,
+                total_sent: account.total_sent,
+                total_received: account.total_received,
+                transaction_count: account.tx_count,
                 connection_info: self.get_connection_info(address).cloned(),
             })
         } else {
@@ -886,30 +1910,27 @@ impl Blockchain {
         }
     }
 
+    // Transactions touching an address, newest first, looked up through the history index
+    fn indexed_transactions(&self, address: &str) -> impl Iterator<Item = (u64, &Transaction)> {
+        self.ledger.history(address).iter().rev().filter_map(|location| {
+            self.chain.get(location.block_index as usize)
+                .and_then(|block| block.transactions.get(location.tx_index))
+                .map(|tx| (location.block_index, tx))
+        })
+    }
+
     fn get_transaction_history(&self, address: &str) -> TransactionHistoryResponse {
-        let mut transactions = Vec::new();
-        
-        for block in &self.chain {
-            for tx in &block.transactions {
-                if tx.from == address || tx.to == address {
-                    transactions.push(serde_json::json!({
-                        "from": tx.from,
-                        "to": tx.to,
-                        "amount": tx.amount,
-                        "timestamp": tx.timestamp,
-                        "signature": tx.signature,
-                        "block_index": block.index
-                    }));
-                }
-            }
-        }
-        
-        // Sort by timestamp (newest first)
-        transactions.sort_by(|a, _b| {
-            let timestamp_a = a["timestamp"].as_u64().unwrap_or(0);
-            let timestamp_b = a["timestamp"].as_u64().unwrap_or(0);
-            timestamp_b.cmp(&timestamp_a)
-        });
+        let transactions: Vec<serde_json::Value> = self.indexed_transactions(address)
+            .map(|(block_index, tx)| serde_json::json!({
+                "hash": tx.hash(),
+                "from": tx.from,
+                "to": tx.to,
+                "amount": tx.amount,
+                "timestamp": tx.timestamp,
+                "signature": tx.signature,
+                "block_index": block_index
+            }))
+            .collect();
         
         TransactionHistoryResponse {
             address: address.to_string(),
@@ -919,22 +1940,9 @@ impl Blockchain {
     }
 
     fn get_transaction_history_with_labels(&self, address: &str) -> TransactionHistoryResponse {
-        let mut transactions = Vec::new();
-        
-        for block in &self.chain {
-            for tx in &block.transactions {
-                if tx.from == address || tx.to == address {
-                    transactions.push(self.format_transaction_with_labels(tx));
-                }
-            }
-        }
-        
-        // Sort by timestamp (newest first)
-        transactions.sort_by(|a, _b| {
-            let timestamp_a = a["timestamp"].as_u64().unwrap_or(0);
-            let timestamp_b = a["timestamp"].as_u64().unwrap_or(0);
-            timestamp_b.cmp(&timestamp_a)
-        });
+        let transactions: Vec<serde_json::Value> = self.indexed_transactions(address)
+            .map(|(_, tx)| self.format_transaction_with_labels(tx))
+            .collect();
         
         TransactionHistoryResponse {
             address: address.to_string(),
@@ -944,46 +1952,30 @@ impl Blockchain {
     }
 
     fn get_wallet_info(&self, address: &str) -> WalletInfoResponse {
-        let balance = self.get_balance(address);
         let username = self.get_username_by_address(address).cloned();
         let is_verified = username.as_ref()
             .and_then(|u| self.address_labels.get(u))
             .map(|label| label.is_verified)
             .unwrap_or(false);
-        
-        let mut total_sent = 0.0;
-        let mut total_received = 0.0;
-        let mut transaction_count = 0;
-        
-        for block in &self.chain {
-            for tx in &block.transactions {
-                if tx.from == address || tx.to == address {
-                    transaction_count += 1;
-                    if tx.from == address && tx.from != "genesis" && tx.from != "mining_reward" {
-                        total_sent += tx.amount;
-                    }
-                    if tx.to == address {
-                        total_received += tx.amount;
-                    }
-                }
-            }
-        }
+        let account = self.ledger.account(address);
         
         WalletInfoResponse {
             address: address.to_string(),
-            balance,
+            balance: account.balance,
+            locked_balance: self.locked_balance(address),
+            locks: self.pending_locks(address),
             username,
             is_verified,
-            total_sent,
-            total_received,
-            transaction_count,
+            total_sent: account.total_sent,
+            total_received: account.total_received,
+            transaction_count: account.tx_count,
             connection_info: self.get_connection_info(address).cloned(),
         }
     }
 
-    fn send_tip(&mut self, from: String, to: String, amount: f64, message: Option<String>) -> Result<String, String> {
+    fn send_tip(&mut self, from: String, to: String, amount: Amount, fee: Option<Amount>, message: Option<String>, auth: TransferAuth) -> Result<String, String> {
         // First create the transaction
-        let result = self.create_transaction_with_labels(from.clone(), to.clone(), amount);
+        let result = self.create_transaction_with_labels(from.clone(), to.clone(), amount, fee, auth);
         
         match result {
             Ok(_) => {
@@ -1017,7 +2009,14 @@ impl Blockchain {
         };
 
         // Check creator balance using the resolved address
-        let creator_balance = self.get_balance(&creator_address);
+        let creator_balance = self.available_balance(&creator_address);
+
+        // The creator must sign the launch fee transfer before anything is created
+        let launch_fee = self.token_system.launch_fee;
+        let fee = self.require_fee(req.fee)?;
+        let fee_tx = Transaction::signed(creator_address.clone(), self.launch_fee_account.clone(), launch_fee, Some(fee), req.auth.clone());
+        fee_tx.verify_signature()?;
+        self.require_next_nonce(&fee_tx)?;
         
         // Create a new request with the resolved address
         let resolved_req = LaunchTokenRequest {
@@ -1032,18 +2031,25 @@ impl Blockchain {
             website: req.website,
             twitter: req.twitter,
             telegram: req.telegram,
+            fee: req.fee,
+            auth: req.auth,
         };
 
+        // The token only exists once the pool admits its fee
+        let token_system = self.token_system.clone();
         let token = self.token_system.launch_token(resolved_req, creator_balance)?;
         
         // Create transaction for launch fee using resolved address
-        let launch_fee = self.token_system.launch_fee;
-        match self.create_transaction(creator_address, "token_launch_fees".to_string(), launch_fee) {
+        match self.create_transaction(fee_tx) {
             Ok(_) => {
                 println!("💰 Token launch fee collected: {} L1", launch_fee);
+                self.save_state();
                 Ok(token)
             },
-            Err(e) => Err(format!("Failed to collect launch fee: {}", e))
+            Err(e) => {
+                self.token_system = token_system;
+                Err(format!("Failed to collect launch fee: {}", e))
+            }
         }
     }
 
@@ -1061,27 +2067,45 @@ impl Blockchain {
                 .unwrap_or(req.buyer.clone())
         };
 
-        let buyer_balance = self.get_balance(&buyer_address);
+        let buyer_balance = self.available_balance(&buyer_address);
+
+        // The buyer must sign the L1 payment into the pool before the trade executes
+        let fee = self.require_fee(req.fee)?;
+        let payment_tx = Transaction::signed(
+            buyer_address.clone(),
+            format!("token_pool_{}", req.token_symbol),
+            req.l1_amount,
+            Some(fee),
+            req.auth.clone(),
+        );
+        payment_tx.verify_signature()?;
+        self.require_next_nonce(&payment_tx)?;
         
         let resolved_req = BuyTokenRequest {
             token_symbol: req.token_symbol,
             buyer: buyer_address.clone(),
             l1_amount: req.l1_amount,
             max_slippage: req.max_slippage,
+            fee: req.fee,
+            auth: req.auth,
         };
         
+        // The trade is undone if the pool does not admit the payment
+        let token_system = self.token_system.clone();
         let trade = self.token_system.buy_token(resolved_req, buyer_balance)?;
         
         // Create L1 transaction for the purchase
-        let tx_result = self.create_transaction(
-            buyer_address,
-            format!("token_pool_{}", trade.token_symbol),
-            trade.l1_amount
-        );
+        let tx_result = self.create_transaction(payment_tx);
         
         match tx_result {
-            Ok(msg) => Ok((trade, msg)),
-            Err(e) => Err(format!("Failed to process L1 transaction: {}", e))
+            Ok(msg) => {
+                self.save_state();
+                Ok((trade, msg))
+            },
+            Err(e) => {
+                self.token_system = token_system;
+                Err(format!("Failed to process L1 transaction: {}", e))
+            }
         }
     }
 
@@ -1099,32 +2123,52 @@ impl Blockchain {
                 .unwrap_or(req.seller.clone())
         };
 
+        // The seller signs the order as a zero transfer into the pool, which uses up their nonce
+        let fee = self.require_fee(req.fee)?;
+        let pool = format!("token_pool_{}", req.token_symbol);
+        let mut order_tx = Transaction::signed(seller_address.clone(), pool.clone(), Amount::ZERO, Some(fee), req.auth.clone());
+        order_tx.data = Some(req.order());
+        order_tx.verify_signature()?;
+        self.require_next_nonce(&order_tx)?;
+
         let resolved_req = SellTokenRequest {
             token_symbol: req.token_symbol,
             seller: seller_address.clone(),
             token_amount: req.token_amount,
             max_slippage: req.max_slippage,
+            fee: req.fee,
+            auth: req.auth,
         };
         
-        let trade = self.token_system.sell_token(resolved_req)?;
+        // The seller keeps their tokens if the pool cannot pay them out or the order is refused
+        let token_system = self.token_system.clone();
+        let trade = self.token_system.sell_token(resolved_req, self.height() + 1)?;
         
         // Create L1 transaction to give seller their L1
-        let tx_result = self.create_transaction(
-            format!("token_pool_{}", trade.token_symbol),
-            seller_address,
-            trade.l1_amount
-        );
-        
-        match tx_result {
-            Ok(msg) => Ok((trade, msg)),
-            Err(e) => Err(format!("Failed to process L1 payout: {}", e))
+        let payout = self.payout(&pool, seller_address, trade.l1_amount);
+        let payout_hash = payout.as_ref().map(Transaction::hash).unwrap_or_default();
+        if let Err(e) = payout.and_then(|payout| self.queue_transaction(payout)) {
+            self.token_system = token_system;
+            return Err(format!("Failed to process L1 payout: {}", e));
+        }
+
+        match self.create_transaction(order_tx) {
+            Ok(msg) => {
+                self.save_state();
+                Ok((trade, msg))
+            },
+            Err(e) => {
+                self.token_system = token_system;
+                self.mempool.fail(&payout_hash, "its sale order was refused");
+                Err(format!("Failed to submit sale order: {}", e))
+            }
         }
     }
 
     fn get_user_token_portfolio(&self, user: &str) -> UserPortfolioResponse {
         // Resolve user address if it's a username
         let user_address = if user.starts_with('@') || self.address_labels.contains_key(user) {
-            let username = if user.starts_with('@') { &user[1..] } else { user };
+            let username = user.strip_prefix('@').unwrap_or(user);
             match self.resolve_username(username) {
                 Ok(label) => label.address.clone(),
                 Err(_) => user.to_string(),
@@ -1144,8 +2188,8 @@ impl Blockchain {
         
         for holding in &holdings {
             if let Some(token) = self.token_system.get_token_info(&holding.token_symbol) {
-                let current_value = holding.amount * token.price_in_l1;
-                let original_value = holding.amount * holding.average_price;
+                let current_value = holding.amount.to_f64() * token.price_in_l1;
+                let original_value = holding.amount.to_f64() * holding.average_price;
                 total_value_l1 += current_value;
                 total_pnl += current_value - original_value;
             }
@@ -1169,15 +2213,15 @@ impl Blockchain {
         self.social_mining.check_daily_limits(&user_address, &social_mining::SocialActionType::Post)?;
 
         // Calculate reward (fixed 10 tokens for posting)
-        let reward_amount = self.social_mining.calculate_reward(&social_mining::SocialActionType::Post, self.max_supply);
+        let reward_amount = self.social_mining.calculate_reward(&social_mining::SocialActionType::Post, self.emission.max_supply);
 
         // Check if we have enough supply left
-        if self.circulating_supply + reward_amount > self.max_supply {
+        if reward_amount > self.emission.remaining(self.issued_supply()) {
             return Err("Maximum supply reached, no more social rewards available".to_string());
         }
 
         // Create reward transaction
-        match self.create_transaction("social_mining".to_string(), user_address.clone(), reward_amount) {
+        match self.create_system_transaction("social_mining", user_address.clone(), reward_amount) {
             Ok(_) => {
                 // Record the social action
                 let action = social_mining::SocialAction {
@@ -1193,7 +2237,7 @@ impl Blockchain {
                 self.social_mining.update_daily_limits(&user_address, &social_mining::SocialActionType::Post);
 
                 // Auto-mine the reward
-                self.mine_pending_transactions("social_system".to_string());
+                self.request_block("social_system");
 
                 println!("📝 Social Post Reward: {} received {} L1 for post {}", user_address, reward_amount, req.post_id);
 
@@ -1222,15 +2266,15 @@ impl Blockchain {
         self.social_mining.check_daily_limits(&user_address, &social_mining::SocialActionType::Like)?;
 
         // Calculate reward (1/100000 of total supply)
-        let reward_amount = self.social_mining.calculate_reward(&social_mining::SocialActionType::Like, self.max_supply);
+        let reward_amount = self.social_mining.calculate_reward(&social_mining::SocialActionType::Like, self.emission.max_supply);
 
         // Check supply
-        if self.circulating_supply + reward_amount > self.max_supply {
+        if reward_amount > self.emission.remaining(self.issued_supply()) {
             return Err("Maximum supply reached, no more social rewards available".to_string());
         }
 
         // Create reward transaction (reward goes to the POST AUTHOR, not the liker)
-        match self.create_transaction("social_mining".to_string(), post_author_address.clone(), reward_amount) {
+        match self.create_system_transaction("social_mining", post_author_address.clone(), reward_amount) {
             Ok(_) => {
                 // Record the social action
                 let action = social_mining::SocialAction {
@@ -1246,7 +2290,7 @@ impl Blockchain {
                 self.social_mining.update_daily_limits(&user_address, &social_mining::SocialActionType::Like);
 
                 // Auto-mine the reward
-                self.mine_pending_transactions("social_system".to_string());
+                self.request_block("social_system");
 
                 println!("👍 Social Like Reward: {} received {} L1 for like on post {} by {}", 
                          post_author_address, reward_amount, req.post_id, user_address);
@@ -1271,15 +2315,15 @@ impl Blockchain {
         self.social_mining.check_daily_limits(&user_address, &social_mining::SocialActionType::Comment)?;
 
         // Calculate reward (1/100000 of total supply)
-        let reward_amount = self.social_mining.calculate_reward(&social_mining::SocialActionType::Comment, self.max_supply);
+        let reward_amount = self.social_mining.calculate_reward(&social_mining::SocialActionType::Comment, self.emission.max_supply);
 
         // Check supply
-        if self.circulating_supply + reward_amount > self.max_supply {
+        if reward_amount > self.emission.remaining(self.issued_supply()) {
             return Err("Maximum supply reached, no more social rewards available".to_string());
         }
 
         // Create reward transaction (reward goes to the COMMENTER)
-        match self.create_transaction("social_mining".to_string(), user_address.clone(), reward_amount) {
+        match self.create_system_transaction("social_mining", user_address.clone(), reward_amount) {
             Ok(_) => {
                 // Record the social action
                 let action = social_mining::SocialAction {
@@ -1295,7 +2339,7 @@ impl Blockchain {
                 self.social_mining.update_daily_limits(&user_address, &social_mining::SocialActionType::Comment);
 
                 // Auto-mine the reward
-                self.mine_pending_transactions("social_system".to_string());
+                self.request_block("social_system");
 
                 println!("💬 Social Comment Reward: {} received {} L1 for commenting on post {} by {}", 
                          user_address, reward_amount, req.post_id, post_author_address);
@@ -1325,7 +2369,7 @@ impl Blockchain {
     fn resolve_user_address(&self, input: &str) -> Result<String, String> {
         // If it starts with @ or is a known username, resolve it
         if input.starts_with('@') || self.address_labels.contains_key(input) {
-            let username = if input.starts_with('@') { &input[1..] } else { input };
+            let username = input.strip_prefix('@').unwrap_or(input);
             match self.resolve_username(username) {
                 Ok(label) => Ok(label.address.clone()),
                 Err(_) => Err(format!("Username '{}' not found", username))
@@ -1337,15 +2381,111 @@ impl Blockchain {
     }
 }
 
+// Which network to join; without a spec file the node runs the built-in dev chain
+fn load_chain_spec(config: &NodeConfig) -> Result<ChainSpec, String> {
+    match &config.chain_spec {
+        Some(path) => ChainSpec::load(path),
+        None => Ok(ChainSpec::default()),
+    }
+}
+
+// Create the data directory with its genesis block and write out the settings used
+fn init_node(config_path: Option<String>, config: &NodeConfig, force: bool) -> Result<(), String> {
+    let config_path = config_path.unwrap_or_else(|| config::DEFAULT_CONFIG_FILE.to_string());
+    if Path::new(&config_path).exists() && !force {
+        return Err(format!("{} already exists; pass --force to overwrite it", config_path));
+    }
+    let chain_spec = load_chain_spec(config)?;
+    let blockchain = Blockchain::open(&config.data_dir, &chain_spec)?;
+    config.save(&config_path)?;
+    println!("✅ Initialized {} in {} (genesis {}, height {})", chain_spec.chain_id, config.data_dir, blockchain.chain[0].hash, blockchain.height());
+    println!("📝 Config written to {}", config_path);
+    Ok(())
+}
+
+// Dump the stored block log as a JSON array; read straight from disk so stdout stays clean
+fn export_chain(config: &NodeConfig, output: Option<&str>) -> Result<(), String> {
+    if !Path::new(&config.data_dir).is_dir() {
+        return Err(format!("No chain data in {}", config.data_dir));
+    }
+    let blocks = Storage::open(&config.data_dir)?.load_blocks()?;
+    let json = serde_json::to_string_pretty(&blocks).map_err(|e| format!("Failed to serialize blocks: {}", e))?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, json + "\n").map_err(|e| format!("Failed to write {}: {}", path, e))?;
+            eprintln!("📤 Exported {} blocks to {}", blocks.len(), path);
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
-    let blockchain = Arc::new(Mutex::new(Blockchain::new()));
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = cli::parse(&args).unwrap_or_else(|e| {
+        eprintln!("❌ {}\n\n{}", e, cli::USAGE);
+        std::process::exit(2);
+    });
+
+    let result = match command {
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        }
+        Command::NodeRun(options) => match options.resolve() {
+            Ok(config) => run_node(config).await,
+            Err(e) => Err(e),
+        },
+        Command::NodeInit { options, force } => options.resolve()
+            .and_then(|config| init_node(options.config_path(), &config, force)),
+        Command::ChainExport { options, output } => options.resolve()
+            .and_then(|config| export_chain(&config, output.as_deref())),
+        Command::ChainSpec(options) => options.resolve()
+            .and_then(|config| load_chain_spec(&config))
+            .and_then(|spec| serde_json::to_string_pretty(&spec).map_err(|e| e.to_string()))
+            .map(|json| println!("{}", json)),
+    };
+    if let Err(e) = result {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run_node(config: NodeConfig) -> Result<(), String> {
+    let chain_spec = load_chain_spec(&config)?;
+    let mut blockchain = Blockchain::open(&config.data_dir, &chain_spec)
+        .map_err(|e| format!("Failed to open chain data in {}: {}", config.data_dir, e))?;
+    match &config.payout_key_file {
+        Some(path) => {
+            let secret = std::fs::read_to_string(path).map_err(|e| format!("Failed to read payout key {}: {}", path, e))?;
+            blockchain.set_payout_key(KeyPair::from_secret_hex(secret.trim())?)?;
+        }
+        None if blockchain.payout_keys.is_none() => {
+            println!("⚠️ No payout key: this node will make no signup bonus, reward or token sale payouts");
+        }
+        None => {}
+    }
+    let blockchain = Arc::new(Mutex::new(blockchain));
+
+    // Blocks the node mines for itself are solved in the background, off the chain lock
+    let mining_requests = mining::spawn_worker(blockchain.clone());
+    blockchain.lock().unwrap().mining_requests = Some(mining_requests);
+
+    let p2p_config = p2p::NetworkConfig {
+        listen: config.p2p_listen,
+        peers: config.peers.clone(),
+    };
+    let network = p2p::Network::start(blockchain.clone(), p2p_config).await?;
+    let syncer = sync::Syncer::start(network.clone(), blockchain.clone());
+    let genesis_hash = blockchain.lock().unwrap().chain[0].hash.clone();
 
     // Create clones for different endpoint handlers
     let blockchain_clone = blockchain.clone();
     let bc_transaction = blockchain.clone();
     let bc_enhanced_tx = blockchain.clone();
     let bc_tx_usernames = blockchain.clone();
+    let bc_cancel_tx = blockchain.clone();
     let bc_mine = blockchain.clone();
     let bc_enhanced_mine = blockchain.clone();
     let bc_balance = blockchain.clone();
@@ -1354,13 +2494,11 @@ async fn main() {
     let bc_connections = blockchain.clone();
     let bc_stats = blockchain.clone();
     let bc_pool_stats = blockchain.clone();
+    let bc_emission = blockchain.clone();
+    let bc_fees = blockchain.clone();
     let bc_create_wallet = blockchain.clone();
     let bc_get_wallet = blockchain.clone();
     let bc_get_wallet_username = blockchain.clone();
-    let bc_tx_history = blockchain.clone();
-    let bc_tx_history_labels = blockchain.clone();
-    let bc_wallet_info = blockchain.clone();
-    let bc_tx_receipt = blockchain.clone();
     let bc_tip = blockchain.clone();
     let bc_register = blockchain.clone();
     let bc_resolve = blockchain.clone();
@@ -1369,6 +2507,7 @@ async fn main() {
     let bc_buy_token = blockchain.clone();
     let bc_sell_token = blockchain.clone();
     let bc_all_tokens = blockchain.clone();
+    let bc_launch_fee = blockchain.clone();
     let bc_trending_tokens = blockchain.clone();
     let bc_token_info = blockchain.clone();
     let bc_portfolio = blockchain.clone();
@@ -1379,13 +2518,22 @@ async fn main() {
      let bc_stats2 = blockchain.clone();  // For get_all_balances
     let bc_stats3 = blockchain.clone();  // For admin_blacklist  
     let bc_stats4 = blockchain.clone();  // For admin_unblacklist
-    let bc_security_stats = blockchain.clone();  // For get_security_stats
-    let bc_network_stats = blockchain.clone();   // For get_network_stats
-
-    // Start connection reward processing (every 30 seconds)
+    let bc_verify = blockchain.clone();
+    let bc_nonce = blockchain.clone();
+    let bc_tx_proof = blockchain.clone();
+    let bc_receipt = blockchain.clone();
+    let bc_activity = blockchain.clone();
+    let bc_reorgs = blockchain.clone();
+    let bc_multisig_create = blockchain.clone();
+    let bc_multisig_propose = blockchain.clone();
+    let bc_multisig_sign = blockchain.clone();
+    let bc_multisig_proposals = blockchain.clone();
+
+    // Start connection reward processing
     let bc_rewards = blockchain.clone();
+    let reward_interval = Duration::from_secs(config.reward_interval_secs);
     tokio::spawn(async move {
-        let mut interval = time::interval(Duration::from_secs(30));
+        let mut interval = time::interval(reward_interval);
         loop {
             interval.tick().await;
             let mut bc = bc_rewards.lock().unwrap();
@@ -1395,8 +2543,9 @@ async fn main() {
 
     // Cleanup task for security, expired transactions, and social mining
     let blockchain_cleanup = blockchain.clone();
+    let cleanup_interval = Duration::from_secs(config.cleanup_interval_secs);
     tokio::spawn(async move {
-        let mut interval = time::interval(Duration::from_secs(300)); // Every 5 minutes
+        let mut interval = time::interval(cleanup_interval);
         loop {
             interval.tick().await;
             let mut bc = blockchain_cleanup.lock().unwrap();
@@ -1428,7 +2577,7 @@ async fn main() {
         .and(warp::body::json())
         .map(move |req: TransactionRequest| {
             let mut bc = bc_transaction.lock().unwrap();
-            match bc.create_transaction(req.from, req.to, req.amount) {
+            match bc.create_transaction(Transaction::signed(req.from, req.to, req.amount, req.fee, req.auth)) {
                 Ok(msg) => warp::reply::json(&serde_json::json!({
                     "success": true,
                     "message": msg
@@ -1449,9 +2598,31 @@ async fn main() {
         .map(move |req: EnhancedTransactionRequest| {
             let mut bc = bc_enhanced_tx.lock().unwrap();
             match bc.create_enhanced_transaction(req) {
-                Ok(msg) => warp::reply::json(&serde_json::json!({
+                Ok((tx_id, msg)) => warp::reply::json(&serde_json::json!({
                     "success": true,
-                    "message": msg
+                    "message": msg,
+                    "transaction_id": tx_id
+                })),
+                Err(err) => warp::reply::json(&serde_json::json!({
+                    "success": false,
+                    "error": err
+                })),
+            }
+        });
+
+    // POST cancellation of a waiting transfer
+    let cancel_transaction = warp::path("rpc")
+        .and(warp::path("transaction"))
+        .and(warp::path("cancel"))
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(move |req: CancelTransactionRequest| {
+            let mut bc = bc_cancel_tx.lock().unwrap();
+            match bc.cancel_transaction(req) {
+                Ok((tx_id, msg)) => warp::reply::json(&serde_json::json!({
+                    "success": true,
+                    "message": msg,
+                    "transaction_id": tx_id
                 })),
                 Err(err) => warp::reply::json(&serde_json::json!({
                     "success": false,
@@ -1467,7 +2638,7 @@ async fn main() {
         .and(warp::body::json())
         .map(move |req: TransactionWithUsernamesRequest| {
             let mut bc = bc_tx_usernames.lock().unwrap();
-            match bc.create_transaction_with_labels(req.from, req.to, req.amount) {
+            match bc.create_transaction_with_labels(req.from, req.to, req.amount, req.fee, req.auth) {
                 Ok(msg) => warp::reply::json(&serde_json::json!({
                     "success": true,
                     "message": msg
@@ -1483,13 +2654,26 @@ async fn main() {
     let mine_block = warp::path("mine")
         .and(warp::post())
         .and(warp::body::json())
-        .map(move |req: MineRequest| {
-            let mut bc = bc_mine.lock().unwrap();
-            bc.mine_pending_transactions(req.miner_address.clone());
-            warp::reply::json(&serde_json::json!({
-                "success": true,
-                "message": format!("Block mined by {}", req.miner_address)
-            }))
+        .and_then(move |req: MineRequest| {
+            let bc_mine = bc_mine.clone();
+            async move {
+                // The nonce search runs on the blocking pool; the lock is held only to build and submit
+                let template = bc_mine.lock().unwrap().block_template(&req.miner_address);
+                let result = match template {
+                    Ok(template) => mining::mine_template(bc_mine, template).await,
+                    Err(err) => Err(err),
+                };
+                Ok::<_, warp::Rejection>(match result {
+                    Ok(_) => warp::reply::json(&serde_json::json!({
+                        "success": true,
+                        "message": format!("Block mined by {}", req.miner_address)
+                    })),
+                    Err(err) => warp::reply::json(&serde_json::json!({
+                        "success": false,
+                        "error": err
+                    })),
+                })
+            }
         });
 
     // POST enhanced mine block with security
@@ -1497,17 +2681,24 @@ async fn main() {
         .and(warp::path("mine"))
         .and(warp::post())
         .and(warp::body::json())
-        .map(move |req: MineRequest| {
-            let mut bc = bc_enhanced_mine.lock().unwrap();
-            match bc.mine_enhanced_block(req.miner_address) {
-                Ok(msg) => warp::reply::json(&serde_json::json!({
-                    "success": true,
-                    "message": msg
-                })),
-                Err(err) => warp::reply::json(&serde_json::json!({
-                    "success": false,
-                    "error": err
-                })),
+        .and_then(move |req: MineRequest| {
+            let bc_enhanced_mine = bc_enhanced_mine.clone();
+            async move {
+                let template = bc_enhanced_mine.lock().unwrap().enhanced_block_template(&req.miner_address);
+                let result = match template {
+                    Ok(template) => mining::mine_template(bc_enhanced_mine, template).await,
+                    Err(err) => Err(err),
+                };
+                Ok::<_, warp::Rejection>(match result {
+                    Ok(msg) => warp::reply::json(&serde_json::json!({
+                        "success": true,
+                        "message": msg
+                    })),
+                    Err(err) => warp::reply::json(&serde_json::json!({
+                        "success": false,
+                        "error": err
+                    })),
+                })
             }
         });
 
@@ -1518,12 +2709,25 @@ async fn main() {
         .map(move |address: String| {
             let bc = bc_balance.lock().unwrap();
             let balance = bc.get_balance(&address);
+            let locked = bc.locked_balance(&address);
             warp::reply::json(&serde_json::json!({
                 "address": address,
-                "balance": balance
+                "balance": balance,
+                "locked": locked
             }))
         });
 
+    // GET next nonce for an account
+    let get_account_nonce = warp::path("rpc")
+        .and(warp::path("account"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("nonce"))
+        .and(warp::get())
+        .map(move |address: String| {
+            let bc = bc_nonce.lock().unwrap();
+            warp::reply::json(&bc.get_account_nonce(&address))
+        });
+
     // GET all balances
     let get_all_balances = warp::path("rpc")
         .and(warp::path("balances"))
@@ -1595,6 +2799,25 @@ async fn main() {
             warp::reply::json(&bc.get_pool_stats())
         });
 
+    // GET issuance schedule and its projection
+    let get_emission = warp::path("rpc")
+        .and(warp::path("emission"))
+        .and(warp::get())
+        .map(move || {
+            let bc = bc_emission.lock().unwrap();
+            warp::reply::json(&bc.get_emission())
+        });
+
+    // GET recommended fees for confirmation within 1, 3 and 6 blocks
+    let get_fee_estimate = warp::path("rpc")
+        .and(warp::path("fees"))
+        .and(warp::path("estimate"))
+        .and(warp::get())
+        .map(move || {
+            let bc = bc_fees.lock().unwrap();
+            warp::reply::json(&bc.get_fee_estimate())
+        });
+
     // GET security statistics
     let get_security_stats = warp::path("rpc")
         .and(warp::path("security"))
@@ -1605,14 +2828,184 @@ async fn main() {
             warp::reply::json(&bc.get_security_stats())
         });
 
+    // GET full chain verification
+    let verify_chain = warp::path("rpc")
+        .and(warp::path("chain"))
+        .and(warp::path("verify"))
+        .and(warp::get())
+        .map(move || {
+            let bc = bc_verify.lock().unwrap();
+            match bc.validate_chain() {
+                Ok(()) => warp::reply::json(&serde_json::json!({
+                    "valid": true,
+                    "height": bc.chain.len()
+                })),
+                Err(err) => warp::reply::json(&serde_json::json!({
+                    "valid": false,
+                    "height": bc.chain.len(),
+                    "block_index": err.block_index,
+                    "reason": err.reason
+                })),
+            }
+        });
+
+    // GET connected peers
+    let network_peers = network.clone();
+    let get_peers = warp::path("rpc")
+        .and(warp::path("peers"))
+        .and(warp::get())
+        .map(move || {
+            warp::reply::json(&serde_json::json!({
+                "node_id": network_peers.node_id(),
+                "peers": network_peers.peers()
+            }))
+        });
+
+    // GET recent chain reorganizations
+    let get_reorgs = warp::path("rpc")
+        .and(warp::path("chain"))
+        .and(warp::path("reorgs"))
+        .and(warp::get())
+        .map(move || {
+            let bc = bc_reorgs.lock().unwrap();
+            warp::reply::json(&bc.reorgs)
+        });
+
+    // GET sync progress
+    let get_sync_status = warp::path("rpc")
+        .and(warp::path("sync"))
+        .and(warp::path("status"))
+        .and(warp::get())
+        .map(move || warp::reply::json(&syncer.status()));
+
+    // GET Merkle inclusion proof for a mined transaction
+    let get_transaction_proof = warp::path("rpc")
+        .and(warp::path("tx"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("proof"))
+        .and(warp::get())
+        .map(move |id: String| {
+            let bc = bc_tx_proof.lock().unwrap();
+            match bc.get_transaction_proof(&id) {
+                Ok(proof) => warp::reply::json(&proof),
+                Err(err) => warp::reply::json(&serde_json::json!({
+                    "success": false,
+                    "error": err
+                })),
+            }
+        });
+
+    // GET receipt for the enhanced transaction named in the x-tx-id header
+    let get_transaction_receipt = warp::path("rpc")
+        .and(warp::path("transaction"))
+        .and(warp::path("receipt"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::header::<String>("x-tx-id"))
+        .map(move |tx_id: String| {
+            let bc = bc_receipt.lock().unwrap();
+            match bc.get_transaction_receipt(&tx_id) {
+                Some(receipt) => warp::reply::json(&receipt),
+                None => warp::reply::json(&serde_json::json!({
+                    "success": false,
+                    "error": format!("Transaction {} not found", tx_id)
+                })),
+            }
+        });
+
+    // POST account activity lookup for a batch of addresses
+    let get_account_activity = warp::path("rpc")
+        .and(warp::path("accounts"))
+        .and(warp::path("activity"))
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(move |req: AccountActivityRequest| {
+            let bc = bc_activity.lock().unwrap();
+            match bc.get_account_activity(&req.addresses) {
+                Ok(activity) => warp::reply::json(&activity),
+                Err(err) => warp::reply::json(&serde_json::json!({
+                    "success": false,
+                    "error": err
+                })),
+            }
+        });
+
+    // POST register an M-of-N multisig account
+    let create_multisig = warp::path("rpc")
+        .and(warp::path("multisig"))
+        .and(warp::path("create"))
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(move |req: MultisigCreateRequest| {
+            let mut bc = bc_multisig_create.lock().unwrap();
+            match bc.create_multisig_account(req) {
+                Ok(address) => warp::reply::json(&serde_json::json!({
+                    "success": true,
+                    "address": address
+                })),
+                Err(err) => warp::reply::json(&serde_json::json!({
+                    "success": false,
+                    "error": err
+                })),
+            }
+        });
+
+    // POST propose a transfer from a multisig account
+    let propose_multisig = warp::path("rpc")
+        .and(warp::path("multisig"))
+        .and(warp::path("propose"))
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(move |req: MultisigProposeRequest| {
+            let mut bc = bc_multisig_propose.lock().unwrap();
+            match bc.propose_multisig_transaction(req) {
+                Ok(proposal) => warp::reply::json(&proposal),
+                Err(err) => warp::reply::json(&serde_json::json!({
+                    "success": false,
+                    "error": err
+                })),
+            }
+        });
+
+    // POST a member's signature on an open proposal
+    let sign_multisig = warp::path("rpc")
+        .and(warp::path("multisig"))
+        .and(warp::path("sign"))
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(move |req: MultisigSignRequest| {
+            let mut bc = bc_multisig_sign.lock().unwrap();
+            match bc.sign_multisig_proposal(req) {
+                Ok((submitted, msg)) => warp::reply::json(&serde_json::json!({
+                    "success": true,
+                    "submitted": submitted,
+                    "message": msg
+                })),
+                Err(err) => warp::reply::json(&serde_json::json!({
+                    "success": false,
+                    "error": err
+                })),
+            }
+        });
+
+    // GET multisig proposals still collecting signatures
+    let get_multisig_proposals = warp::path("rpc")
+        .and(warp::path("multisig"))
+        .and(warp::path("proposals"))
+        .and(warp::get())
+        .map(move || {
+            let bc = bc_multisig_proposals.lock().unwrap();
+            warp::reply::json(&bc.multisig.proposals(None))
+        });
+
     // POST create wallet
     let create_wallet = warp::path("wallet")
         .and(warp::post())
         .and(warp::body::json())
         .map(move |req: serde_json::Value| {
-            let user_id = req.get("user_id").and_then(|v| v.as_str()).unwrap_or("anonymous");
+            let public_key = req.get("public_key").and_then(|v| v.as_str()).unwrap_or_default();
             let bc = bc_create_wallet.lock().unwrap();
-            match bc.create_user_wallet(user_id) {
+            match bc.create_user_wallet(public_key) {
                 Ok(wallet_info) => warp::reply::json(&wallet_info),
                 Err(err) => warp::reply::json(&serde_json::json!({
                     "success": false,
@@ -1631,12 +3024,12 @@ async fn main() {
                 Some(wallet_info) => warp::reply::json(&wallet_info),
                 None => {
                     // Create a default wallet response for new users
-                    let wallet_address = format!("wallet_{}", address); // Fix: use address instead of user_id
                     let wallet_info = UserWalletInfo {
-                        address: wallet_address,
-                        balance: 0.0,
-                        total_sent: 0.0,
-                        total_received: 0.0,
+                        address,
+                        balance: Amount::ZERO,
+                        locked_balance: Amount::ZERO,
+                        total_sent: Amount::ZERO,
+                        total_received: Amount::ZERO,
                         transaction_count: 0,
                     };
                     warp::reply::json(&wallet_info)
@@ -1667,7 +3060,7 @@ async fn main() {
         .and(warp::body::json())
         .map(move |req: TipRequest| {
             let mut bc = bc_tip.lock().unwrap();
-            match bc.send_tip(req.from, req.to, req.amount, req.message) {
+            match bc.send_tip(req.from, req.to, req.amount, req.fee, req.message, req.auth) {
                 Ok(msg) => warp::reply::json(&serde_json::json!({
                     "success": true,
                     "message": msg
@@ -1686,7 +3079,7 @@ async fn main() {
         .and(warp::body::json())
         .map(move |req: UsernameRegisterRequest| {
             let mut bc = bc_register.lock().unwrap();
-            match bc.register_username(req.username) {
+            match bc.register_username(req.username, req.public_key, req.signature) {
                 Ok((username, address)) => warp::reply::json(&serde_json::json!({
                     "success": true,
                     "username": username,
@@ -1814,6 +3207,18 @@ async fn main() {
             }))
         });
 
+    // GET the token launch fee and the account it is paid to, which the creator's signed transfer names
+    let get_launch_fee = warp::path("rpc")
+        .and(warp::path("launch-fee"))
+        .and(warp::get())
+        .map(move || {
+            let bc = bc_launch_fee.lock().unwrap();
+            warp::reply::json(&serde_json::json!({
+                "launch_fee": bc.token_system.launch_fee,
+                "account": bc.launch_fee_account
+            }))
+        });
+
         // GET all tokens
     let get_all_tokens = warp::path("rpc")
         .and(warp::path("tokens"))
@@ -1919,103 +3324,140 @@ async fn main() {
             warp::reply::json(&bc.get_social_stats())
         });
 
-    // CORS configuration
-    
-    let cors = warp::cors()
-        .allow_any_origin()
-        .allow_headers(vec!["content-type", "x-user-id", "x-username", "x-tx-id"])
-        .allow_methods(vec!["GET", "POST", "DELETE"]);
-
-    // CORS configuration
+    // CORS configuration; browsers on any origin may call the API unless origins are configured
     let cors = warp::cors()
-        .allow_any_origin()
         .allow_headers(vec!["content-type", "x-user-id", "x-username", "x-tx-id"])
         .allow_methods(vec!["GET", "POST", "DELETE"]);
+    let cors = if config.cors_origins.is_empty() {
+        cors.allow_any_origin()
+    } else {
+        cors.allow_origins(config.cors_origins.iter().map(String::as_str))
+    };
 
     println!("🚀 Layer1 Blockchain Server Starting...");
-    println!("📡 Server running on http://0.0.0.0:3030");
-    println!("");
+    println!("📡 Server running on http://{}", config.listen);
+    println!("🌐 P2P listening on port {} (node {}), static peers: {:?}", network.listen_port(), network.node_id(), config.peers);
+    println!("💾 Data directory: {}", config.data_dir);
+    println!("⛓️ Chain {} (genesis {})", chain_spec.chain_id, genesis_hash);
+    if let Some(treasury) = &chain_spec.economics.treasury {
+        println!("🏦 Treasury {} ({} of {})", treasury.address(), treasury.threshold, treasury.public_keys.len());
+    }
+    println!();
     println!("📋 Available API Endpoints:");
-    println!("");
+    println!();
     println!("🔹 Basic Operations:");
     println!("  GET  /health - Health check");
     println!("  GET  /blockchain - Full blockchain state");
     println!("  GET  /stats - Network statistics");
-    println!("");
+    println!();
     println!("💰 Transactions:");
     println!("  POST /transaction - Create basic transaction");
     println!("  POST /rpc/transaction - Create transaction with usernames");
-    println!("");
+    println!();
     println!("🔹 Basic Operations:");
     println!("  GET  /health - Health check");
     println!("  GET  /blockchain - Full blockchain state");
     println!("  GET  /stats - Network statistics");
-    println!("");
+    println!("  GET  /rpc/chain/verify - Verify chain integrity");
+    println!("  GET  /rpc/tx/{{id}}/proof - Merkle inclusion proof for a mined transaction");
+    println!("  GET  /rpc/peers - Connected P2P peers");
+    println!("  GET  /rpc/sync/status - Chain sync progress");
+    println!("  GET  /rpc/chain/reorgs - Recent chain reorganizations");
+    println!();
     println!("💰 Transactions:");
     println!("  POST /transaction - Create basic transaction");
     println!("  POST /rpc/transaction - Create transaction with usernames");
     println!("  POST /rpc/transaction/enhanced - Create enhanced transaction with fees");
+    println!("  POST /rpc/transaction/cancel - Cancel a pending transaction (same nonce, higher fee)");
     println!("  POST /rpc/tip - Send tip with message");
-    println!("");
+    println!("  GET  /rpc/account/{{address}}/nonce - Next nonce to sign with");
+    println!("  POST /rpc/accounts/activity - Balances and transaction counts for a batch of addresses");
+    println!();
+    println!("🔐 Multisig:");
+    println!("  POST /rpc/multisig/create - Register an M-of-N account");
+    println!("  POST /rpc/multisig/propose - Propose a transfer from a multisig account");
+    println!("  POST /rpc/multisig/sign - Add a member's signature; submits once the threshold is met");
+    println!("  GET  /rpc/multisig/proposals - Proposals still collecting signatures");
+    println!();
     println!("⛏️ Mining:");
     println!("  POST /mine - Mine block (basic)");
     println!("  POST /rpc/mine - Mine block (enhanced with security)");
-    println!("");
+    println!("  GET  /rpc/emission - Block subsidy, halving schedule and supply projection");
+    println!("  GET  /rpc/fees/estimate - Base fee and recommended fees by confirmation target");
+    println!();
     println!("👤 User Management:");
     println!("  POST /connect - Connect user to network");
     println!("  POST /disconnect - Disconnect user");
     println!("  GET  /connections - Active connections");
+    println!("  POST /wallet - Wallet info for a public key");
     println!("  GET  /wallet/{{address}} - Wallet info");
+    println!("  POST /rpc/username - Register or rename a username for your address");
     println!("  GET  /rpc/token/{{symbol}} - Token information");
     println!("  GET  /rpc/portfolio/{{user}} - User token portfolio");
-    println!("");
+    println!();
     println!("🔒 Security:");
     println!("  GET  /rpc/security/stats - Security statistics");
     println!("  GET  /rpc/pool/stats - Transaction pool stats");
-    println!("  GET  /rpc/transaction/receipt - Transaction receipt");
+    println!("  GET  /rpc/transaction/receipt - Transaction receipt (id in the x-tx-id header)");
     println!("  POST /admin/blacklist - Admin blacklist address");
     println!("  POST /admin/unblacklist - Admin unblacklist address");
-    println!("");
+    println!();
     println!("🪙 Token Launch & Trading:");
-    println!("  POST /rpc/launch-token - Launch new token (10 L1 fee)");
+    println!("  POST /rpc/launch-token - Launch new token (fee set by the chain spec)");
     println!("  POST /rpc/buy-token - Buy token with L1");
     println!("  POST /rpc/sell-token - Sell token for L1");
+    println!("  GET  /rpc/launch-fee - Token launch fee and the account it is paid to");
     println!("  GET  /rpc/tokens - All launched tokens");
     println!("  GET  /rpc/trending-tokens - Trending tokens");
     println!("  GET  /rpc/token/{{symbol}} - Token information");
     println!("  GET  /rpc/portfolio/{{user}} - User token portfolio");
-    println!("");
+    println!();
     println!("📱 Social Mining:");
     println!("  POST /rpc/social/post - Create post (earn 10 L1)");
     println!("  POST /rpc/social/like - Like post (author earns L1)");
     println!("  POST /rpc/social/comment - Comment (earn L1)");
     println!("  GET  /rpc/social/stats - Social mining statistics");
-    println!("");
+    println!();
     println!("🎯 Features Active:");
     println!("  ✅ Enhanced Security & Rate Limiting");
-    println!("  ✅ Username System with Auto-Wallets");
+    println!("  ✅ Username Labels for Key-Derived Addresses");
     println!("  ✅ Connection Rewards (Auto-mining)");
     println!("  ✅ Token Launch Platform");
     println!("  ✅ Social Mining System");
     println!("  ✅ Multi-layer Transaction Security");
-    println!("");
+    println!();
 
     // Complete routes definition
     let routes = health_check
         .or(get_blockchain)
         .or(create_transaction)
         .or(create_enhanced_transaction)
+        .or(cancel_transaction)
         .or(create_transaction_with_usernames)
         .or(mine_block)
         .or(mine_enhanced_block)
         .or(get_balance)
         .or(get_all_balances)
+        .or(get_account_nonce)
         .or(connect_user)
         .or(disconnect_user)
         .or(get_connections)
         .or(get_stats)
         .or(get_pool_stats)
+        .or(get_emission)
+        .or(get_fee_estimate)
         .or(get_security_stats)
+        .or(verify_chain)
+        .or(get_transaction_proof)
+        .or(get_transaction_receipt)
+        .or(get_account_activity)
+        .or(create_multisig)
+        .or(propose_multisig)
+        .or(sign_multisig)
+        .or(get_multisig_proposals)
+        .or(get_peers)
+        .or(get_sync_status)
+        .or(get_reorgs)
         .or(create_wallet)
         .or(get_wallet)
         .or(get_wallet_by_username)
@@ -2026,6 +3468,7 @@ async fn main() {
         .or(token_launch)
         .or(buy_token)
         .or(sell_token)
+        .or(get_launch_fee)
         .or(get_all_tokens)
         .or(get_trending_tokens)
         .or(get_token_info)
@@ -2039,8 +3482,9 @@ async fn main() {
         .with(cors);
 
     warp::serve(routes)
-        .run(([0, 0, 0, 0], 3030))
+        .run(config.listen)
         .await;
     println!("🛑 Server stopped.");
+    Ok(())
 }
 
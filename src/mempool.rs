@@ -0,0 +1,972 @@
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::amount::Amount;
+use crate::crypto;
+use crate::enhanced_transaction::{TransactionReceipt, TransactionStatus};
+use crate::fee_market;
+use crate::Transaction;
+
+/// Pool transactions a block template takes, besides the miner's reward
+pub const MAX_BLOCK_TRANSACTIONS: usize = 100;
+
+/// Signed transfers the pool holds at most, pending and queued together
+const MAX_POOL_SIZE: usize = 1000;
+
+/// Signed transfers one sender may have waiting, pending and queued together
+const MAX_SENDER_TRANSACTIONS: usize = 64;
+
+/// Multiple of the base fee the minimum fee reaches in a full pool. It climbs linearly from the
+/// base fee once the pool is half full.
+const FULL_POOL_FEE_MULTIPLIER: u128 = 10;
+
+/// Settled transactions kept so their receipts can still be looked up
+const MAX_HISTORY_SIZE: usize = 10_000;
+
+/// How much more than the transfer it replaces, in percent, a replacement must pay
+pub const MIN_FEE_BUMP_PERCENT: u128 = 10;
+
+/// Signed transfers not mined within this many seconds of their timestamp expire
+const EXPIRY_SECS: u64 = 3600;
+
+/// How far ahead of the local clock, in seconds, a signed transfer may be dated. Expiry counts from
+/// the timestamp, so a transfer dated further ahead would wait in the pool indefinitely.
+const MAX_FUTURE_TX_SECS: u64 = 300;
+
+/// Where an admitted transaction ended up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Admission {
+    /// Next in line for its sender and ready to be mined
+    Ready,
+    /// Waiting for the sender's earlier nonces to arrive
+    Queued,
+}
+
+/// A transfer the pool has seen, and where it is in its lifecycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolEntry {
+    /// Enhanced transaction id, or the transaction hash for transfers submitted without one
+    pub id: String,
+    /// Leaf hash the transaction has on chain
+    pub hash: String,
+    pub transaction: Transaction,
+    pub status: TransactionStatus,
+    /// Why the transaction left the pool without being mined
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl PoolEntry {
+    pub fn new(id: String, transaction: Transaction) -> Self {
+        Self {
+            id,
+            hash: transaction.hash(),
+            transaction,
+            status: TransactionStatus::Pending,
+            reason: None,
+        }
+    }
+
+    pub fn fee(&self) -> Amount {
+        self.transaction.fee.unwrap_or_default()
+    }
+
+    /// What the transfer takes from its sender: the amount plus the fee
+    pub fn cost(&self) -> Amount {
+        self.transaction.amount.saturating_add(self.fee())
+    }
+
+    pub fn receipt(&self, block_number: Option<u64>) -> TransactionReceipt {
+        let tx = &self.transaction;
+        TransactionReceipt {
+            transaction_hash: self.hash.clone(),
+            transaction_id: self.id.clone(),
+            block_number,
+            status: self.status.clone(),
+            timestamp: tx.timestamp,
+            from: tx.from.clone(),
+            to: tx.to.clone(),
+            amount: tx.amount,
+            fee: self.fee(),
+            data: tx.data.clone(),
+            reason: self.reason.clone(),
+        }
+    }
+
+    // Protocol payouts are owed by the node and never expire or count against the pool size
+    fn is_signed(&self) -> bool {
+        !crypto::is_system_address(&self.transaction.from)
+    }
+
+    fn is_expired(&self, now: u64) -> bool {
+        self.is_signed() && now.saturating_sub(self.transaction.timestamp) > EXPIRY_SECS
+    }
+}
+
+/// Pool statistics
+#[derive(Serialize, Debug)]
+pub struct PoolStats {
+    pub pending_count: usize,
+    pub queued_count: usize,
+    pub confirmed_count: usize,
+    pub failed_count: usize,
+    pub rejected_count: usize,
+    pub expired_count: usize,
+    pub replaced_count: usize,
+    pub total_transactions: usize,
+    pub average_fee: Amount,
+    pub total_volume: Amount,
+    pub total_fees: Amount,
+    pub min_fee: Amount,
+    pub base_fee: Amount,
+    pub max_pool_size: usize,
+    pub max_sender_transactions: usize,
+}
+
+/// Every transfer waiting to be mined, signed or paid out by the node, and the recent outcome of
+/// those that left. Block templates are built from it alone.
+#[derive(Debug, Clone)]
+pub struct Mempool {
+    // Ready to mine, in arrival order; each sender's transfers are in nonce order
+    pending: Vec<PoolEntry>,
+    // Waiting for the sender's earlier nonces
+    queued: Vec<PoolEntry>,
+    // Confirmed, failed, rejected, expired and replaced, oldest first
+    history: VecDeque<PoolEntry>,
+    // Base fee the next block records
+    base_fee: Amount,
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mempool {
+    pub fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            queued: Vec::new(),
+            history: VecDeque::new(),
+            base_fee: fee_market::MIN_BASE_FEE,
+        }
+    }
+
+    /// Fee a transfer that carries one must at least pay to be admitted: the base fee until the
+    /// pool is half full, then rising with occupancy so cheap spam is the first to be turned away
+    pub fn min_fee(&self) -> Amount {
+        let half = (MAX_POOL_SIZE / 2) as u128;
+        let excess = (self.signed_count() as u128).saturating_sub(half).min(half);
+        self.base_fee
+            .mul_div(half + (FULL_POOL_FEE_MULTIPLIER - 1) * excess, half)
+            .unwrap_or(Amount::MAX)
+    }
+
+    /// Follow the chain tip: transfers paying less than the next block's base fee stay in the
+    /// pool but are not mined until it comes back down
+    pub fn set_base_fee(&mut self, base_fee: Amount) {
+        self.base_fee = base_fee;
+    }
+
+    /// Admit a transaction; `confirmed_nonce` is the sender's next nonce on chain. Signed transfers
+    /// must carry a fee meeting the minimum; only protocol payouts carry none. A signed
+    /// transfer reusing the nonce of one still waiting replaces it if it pays a high enough fee, and
+    /// one arriving at a full pool evicts the cheapest transfer if it outbids it.
+    pub fn add(&mut self, id: String, transaction: Transaction, confirmed_nonce: u64) -> Result<Admission, String> {
+        let entry = PoolEntry::new(id, transaction);
+        let tx = &entry.transaction;
+        if self.entries().any(|existing| existing.id == entry.id || existing.hash == entry.hash) {
+            return Err("Transaction is already in the pool".to_string());
+        }
+        if tx.fee.is_some_and(|fee| fee < self.min_fee()) {
+            return Err(format!("Transaction fee too low. Minimum: {}", self.min_fee()));
+        }
+
+        if entry.is_signed() {
+            if tx.fee.is_none() {
+                return Err(format!("Transaction carries no fee. Minimum: {}", self.min_fee()));
+            }
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            if entry.is_expired(now) {
+                return Err("Transaction is expired".to_string());
+            }
+            if tx.timestamp > now.saturating_add(MAX_FUTURE_TX_SECS) {
+                return Err(format!("Transaction timestamp {} is more than {} seconds ahead of this node's clock",
+                                   tx.timestamp, MAX_FUTURE_TX_SECS));
+            }
+            if tx.nonce.is_some_and(|nonce| self.find(&tx.from, nonce).is_some()) {
+                return self.replace(entry);
+            }
+            let waiting = self.entries().filter(|existing| existing.is_signed() && existing.transaction.from == tx.from).count();
+            if waiting >= MAX_SENDER_TRANSACTIONS {
+                return Err(format!("{} already has {} transactions waiting, the most one sender may have",
+                                   tx.from, MAX_SENDER_TRANSACTIONS));
+            }
+        }
+
+        let mut admission = Admission::Ready;
+        if let Some(nonce) = tx.nonce.filter(|_| entry.is_signed()) {
+            let next_nonce = self.next_nonce(&tx.from, confirmed_nonce);
+            if nonce < next_nonce {
+                return Err(format!("Nonce {} already used; next nonce for {} is {}", nonce, tx.from, next_nonce));
+            }
+            if nonce > next_nonce {
+                admission = Admission::Queued;
+            }
+        }
+        if entry.is_signed() && self.signed_count() >= MAX_POOL_SIZE {
+            self.evict(&entry)?;
+        }
+
+        if admission == Admission::Queued {
+            self.queued.push(entry);
+        } else {
+            let from = entry.transaction.from.clone();
+            self.pending.push(entry);
+            self.promote(&from, confirmed_nonce);
+        }
+        Ok(admission)
+    }
+
+    // Make room in a full pool for `entry` by evicting the cheapest transfer no other waiting one
+    // builds on, which it must outbid as it would a transfer it replaces. Its sender's own
+    // transfers are never evicted for it.
+    fn evict(&mut self, entry: &PoolEntry) -> Result<(), String> {
+        let mut last_nonce: HashMap<&str, u64> = HashMap::new();
+        for waiting in self.entries() {
+            if let Some(nonce) = waiting.transaction.nonce {
+                let last = last_nonce.entry(waiting.transaction.from.as_str()).or_insert(nonce);
+                *last = (*last).max(nonce);
+            }
+        }
+        let cheapest = self.entries()
+            .filter(|waiting| waiting.is_signed() && waiting.transaction.from != entry.transaction.from)
+            .filter(|waiting| waiting.transaction.nonce.is_none_or(|nonce| last_nonce[waiting.transaction.from.as_str()] == nonce))
+            .min_by_key(|waiting| waiting.fee())
+            .map(|waiting| (waiting.hash.clone(), waiting.fee()));
+        let Some((hash, fee)) = cheapest else {
+            return Err("Transaction pool is full".to_string());
+        };
+
+        let required = replacement_fee(fee);
+        if entry.fee() < required {
+            return Err(format!("Transaction pool is full: the cheapest transfer waiting pays {}, a new one must pay at least {}",
+                               fee, required));
+        }
+        let evicted = match self.pending.iter().position(|waiting| waiting.hash == hash) {
+            Some(pos) => self.pending.remove(pos),
+            None => {
+                let pos = self.queued.iter().position(|waiting| waiting.hash == hash).ok_or("No transaction to evict")?;
+                self.queued.remove(pos)
+            }
+        };
+        println!("🧹 Transaction {} evicted from the full pool by {} (fee {} -> {})", evicted.id, entry.id, fee, entry.fee());
+        let reason = format!("evicted from the full pool by {}, which pays a fee of {}", entry.id, entry.fee());
+        self.archive(evicted, TransactionStatus::Rejected, Some(reason));
+        Ok(())
+    }
+
+    // Put `entry` in the place of the waiting transfer with its sender and nonce, if it pays enough more
+    fn replace(&mut self, entry: PoolEntry) -> Result<Admission, String> {
+        let same_nonce = |existing: &PoolEntry| {
+            existing.transaction.from == entry.transaction.from && existing.transaction.nonce == entry.transaction.nonce
+        };
+        let (slot, admission) = match self.pending.iter().position(same_nonce) {
+            Some(pos) => (&mut self.pending[pos], Admission::Ready),
+            None => {
+                let pos = self.queued.iter().position(same_nonce).ok_or("No transaction to replace")?;
+                (&mut self.queued[pos], Admission::Queued)
+            }
+        };
+
+        let required = replacement_fee(slot.fee());
+        if entry.fee() < required {
+            return Err(format!("Replacement fee too low: transaction {} pays {}, a replacement must pay at least {}",
+                               slot.id, slot.fee(), required));
+        }
+        println!("🔁 Transaction {} replaced by {} (fee {} -> {})", slot.id, entry.id, slot.fee(), entry.fee());
+        let reason = format!("replaced by {}, which pays a fee of {}", entry.id, entry.fee());
+        let replaced = std::mem::replace(slot, entry);
+        self.archive(replaced, TransactionStatus::Replaced, Some(reason));
+        Ok(admission)
+    }
+
+    /// The sender's waiting transfer with `nonce`, which a new transfer with that nonce would replace
+    pub fn find(&self, from: &str, nonce: u64) -> Option<&PoolEntry> {
+        self.entries().find(|entry| {
+            entry.is_signed() && entry.transaction.from == from && entry.transaction.nonce == Some(nonce)
+        })
+    }
+
+    /// What the sender's transfers still in the pool, pending or queued, will take from it
+    pub fn pending_cost(&self, address: &str) -> Amount {
+        self.entries()
+            .filter(|entry| entry.transaction.from == address)
+            .map(PoolEntry::cost)
+            .sum()
+    }
+
+    /// Whether `spendable` covers `transaction` on top of everything its sender already has waiting,
+    /// less the transfer it would replace
+    pub fn check_funds(&self, transaction: &Transaction, spendable: Amount) -> Result<(), String> {
+        let cost = transaction.amount.try_add(transaction.fee.unwrap_or_default())?;
+        let replaced = transaction.nonce
+            .and_then(|nonce| self.find(&transaction.from, nonce))
+            .map_or(Amount::ZERO, PoolEntry::cost);
+        let pending = self.pending_cost(&transaction.from).saturating_sub(replaced);
+        if pending.saturating_add(cost) > spendable {
+            return Err(format!("Insufficient balance. Have: {} spendable, {} already pending, Need: {}",
+                               spendable, pending, cost));
+        }
+        Ok(())
+    }
+
+    /// Nonce the sender's next transfer must carry, counting transfers already pending
+    pub fn next_nonce(&self, address: &str, confirmed_nonce: u64) -> u64 {
+        self.pending.iter()
+            .filter(|entry| entry.is_signed() && entry.transaction.from == address)
+            .filter_map(|entry| entry.transaction.nonce)
+            .map(|nonce| nonce + 1)
+            .max()
+            .unwrap_or(0)
+            .max(confirmed_nonce)
+    }
+
+    // Move queued transfers into pending once the nonces before them are in
+    fn promote(&mut self, from: &str, confirmed_nonce: u64) {
+        loop {
+            let next_nonce = self.next_nonce(from, confirmed_nonce);
+            let Some(pos) = self.queued.iter()
+                .position(|entry| entry.transaction.from == from && entry.transaction.nonce == Some(next_nonce))
+            else {
+                break;
+            };
+            let entry = self.queued.remove(pos);
+            println!("📥 Queued transaction {} promoted", entry.id);
+            self.pending.push(entry);
+        }
+    }
+
+    /// Up to `limit` pending transactions for the next block, in pool order. The highest fees are
+    /// taken first, but never a sender's transfer ahead of the ones before it by nonce, and none
+    /// paying less than the base fee. Each one taken must pass `admit` against the state the transactions before it leave; the hashes of
+    /// those that do not are returned with the reason, and their senders' later transfers wait.
+    pub fn select(
+        &self,
+        limit: usize,
+        mut admit: impl FnMut(&PoolEntry) -> Result<(), String>,
+    ) -> (Vec<&PoolEntry>, Vec<(String, String)>) {
+        let mut lanes: HashMap<&str, VecDeque<usize>> = HashMap::new();
+        for (i, entry) in self.pending.iter().enumerate() {
+            lanes.entry(entry.transaction.from.as_str()).or_default().push_back(i);
+        }
+
+        let mut chosen = Vec::new();
+        let mut failed = Vec::new();
+        while chosen.len() < limit {
+            let best = lanes.values()
+                .filter_map(|lane| lane.front().copied())
+                .max_by_key(|&i| (self.pending[i].fee(), Reverse(i)));
+            let Some(i) = best else {
+                break;
+            };
+            let entry = &self.pending[i];
+            if entry.is_signed() && entry.transaction.fee.is_none_or(|fee| fee < self.base_fee) {
+                lanes.remove(entry.transaction.from.as_str());
+                continue;
+            }
+            if let Err(reason) = admit(entry) {
+                failed.push((entry.hash.clone(), reason));
+                lanes.remove(entry.transaction.from.as_str());
+                continue;
+            }
+            if let Some(lane) = lanes.get_mut(entry.transaction.from.as_str()) {
+                lane.pop_front();
+            }
+            chosen.push(i);
+        }
+
+        chosen.sort_unstable();
+        (chosen.into_iter().map(|i| &self.pending[i]).collect(), failed)
+    }
+
+    /// Fail a pending transaction that could not be mined. The sender's later nonces go back to
+    /// the queue, so the nonce it used can be sent again.
+    pub fn fail(&mut self, hash: &str, reason: &str) {
+        let Some(pos) = self.pending.iter().position(|entry| entry.hash == hash) else {
+            return;
+        };
+        let entry = self.pending.remove(pos);
+        println!("❌ Transaction {} failed: {}", entry.id, reason);
+
+        self.hold_back(&entry);
+        self.archive(entry, TransactionStatus::Failed, Some(reason.to_string()));
+    }
+
+    // Move the sender's pending transfers after `gone`, which left unmined, back to the queue
+    fn hold_back(&mut self, gone: &PoolEntry) {
+        if let Some(nonce) = gone.transaction.nonce.filter(|_| gone.is_signed()) {
+            let from = &gone.transaction.from;
+            let (later, rest): (Vec<PoolEntry>, Vec<PoolEntry>) = std::mem::take(&mut self.pending).into_iter()
+                .partition(|other| other.transaction.from == *from && other.transaction.nonce.is_some_and(|n| n > nonce));
+            self.pending = rest;
+            self.queued.extend(later);
+        }
+    }
+
+    /// Settle the pool against a new tip: transactions it `included` are confirmed, those whose
+    /// nonce the chain has moved past fail, and queued transfers whose turn has come are promoted
+    pub fn settle(&mut self, included: &HashSet<String>, confirmed_nonce: impl Fn(&str) -> u64) {
+        let mut settled = Vec::new();
+        let mut keep = |entry: &PoolEntry| {
+            let stale = entry.is_signed() && entry.transaction.nonce.is_some_and(|nonce| nonce < confirmed_nonce(&entry.transaction.from));
+            if included.contains(&entry.hash) {
+                settled.push((entry.clone(), TransactionStatus::Confirmed));
+                false
+            } else if stale {
+                settled.push((entry.clone(), TransactionStatus::Failed));
+                false
+            } else {
+                true
+            }
+        };
+        self.pending.retain(&mut keep);
+        self.queued.retain(&mut keep);
+
+        for (entry, status) in settled {
+            let mut reason = None;
+            if status == TransactionStatus::Failed {
+                println!("❌ Transaction {} failed: its nonce was used by another transfer", entry.id);
+                reason = Some("its nonce was used by another transfer".to_string());
+            }
+            self.archive(entry, status, reason);
+        }
+
+        let senders: HashSet<String> = self.queued.iter().map(|entry| entry.transaction.from.clone()).collect();
+        for sender in senders {
+            self.promote(&sender, confirmed_nonce(&sender));
+        }
+    }
+
+    /// Put transactions from abandoned blocks back ahead of newer pending ones, keeping their order
+    /// and, for those the pool had confirmed, their ids
+    pub fn reinstate(&mut self, orphaned: Vec<Transaction>) {
+        let mut returned = Vec::new();
+        for transaction in orphaned {
+            let hash = transaction.hash();
+            if self.pending.iter().chain(&returned).any(|entry: &PoolEntry| entry.hash == hash) {
+                continue;
+            }
+            let entry = match self.history.iter().position(|entry| entry.hash == hash) {
+                Some(pos) => {
+                    let mut entry = self.history.remove(pos).unwrap();
+                    entry.status = TransactionStatus::Pending;
+                    entry.reason = None;
+                    println!("↩️ Transaction {} back in pool after reorg", entry.id);
+                    entry
+                }
+                None => PoolEntry::new(hash, transaction),
+            };
+            returned.push(entry);
+        }
+        self.pending.splice(0..0, returned);
+    }
+
+    /// Expire signed transfers that have waited too long, holding back their senders' later
+    /// nonces; returns how many went
+    pub fn cleanup_expired(&mut self) -> usize {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let mut expired = Vec::new();
+        let mut keep = |entry: &PoolEntry| {
+            if entry.is_expired(now) {
+                expired.push(entry.clone());
+                false
+            } else {
+                true
+            }
+        };
+        self.pending.retain(&mut keep);
+        self.queued.retain(&mut keep);
+
+        let count = expired.len();
+        for entry in expired {
+            println!("⌛ Transaction {} expired", entry.id);
+            self.hold_back(&entry);
+            let reason = format!("not mined within {} seconds of its timestamp", EXPIRY_SECS);
+            self.archive(entry, TransactionStatus::Expired, Some(reason));
+        }
+        count
+    }
+
+    // Record how a transaction left the pool and why, forgetting the oldest outcomes past the
+    // history limit
+    fn archive(&mut self, mut entry: PoolEntry, status: TransactionStatus, reason: Option<String>) {
+        entry.status = status;
+        entry.reason = reason;
+        if self.history.len() >= MAX_HISTORY_SIZE {
+            self.history.pop_front();
+        }
+        self.history.push_back(entry);
+    }
+
+    /// Pending, queued or settled transaction by id or on-chain hash
+    pub fn get(&self, id: &str) -> Option<&PoolEntry> {
+        self.entries()
+            .chain(self.history.iter().rev())
+            .find(|entry| entry.id == id || entry.hash == id)
+    }
+
+    pub fn pending(&self) -> &[PoolEntry] {
+        &self.pending
+    }
+
+    pub fn queued(&self) -> &[PoolEntry] {
+        &self.queued
+    }
+
+    // Transactions still waiting, pending first
+    fn entries(&self) -> impl Iterator<Item = &PoolEntry> {
+        self.pending.iter().chain(&self.queued)
+    }
+
+    // Signed transfers waiting, which is what the pool size and per-sender limits count
+    fn signed_count(&self) -> usize {
+        self.entries().filter(|entry| entry.is_signed()).count()
+    }
+
+    /// Transactions still waiting, for the state snapshot
+    pub fn snapshot(&self) -> Vec<PoolEntry> {
+        self.entries().cloned().collect()
+    }
+
+    /// Reload a snapshot taken by `snapshot`; each entry is admitted again in order
+    pub fn restore(&mut self, entries: Vec<PoolEntry>, confirmed_nonce: impl Fn(&str) -> u64) {
+        for entry in entries {
+            let nonce = confirmed_nonce(&entry.transaction.from);
+            if let Err(e) = self.add(entry.id.clone(), entry.transaction, nonce) {
+                println!("⚠️ Dropping saved transaction {}: {}", entry.id, e);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let total_fees: Amount = self.pending.iter().map(PoolEntry::fee).sum();
+        let total_volume: Amount = self.pending.iter().map(|entry| entry.transaction.amount).sum();
+        let settled = |status: TransactionStatus| self.history.iter().filter(|entry| entry.status == status).count();
+
+        PoolStats {
+            pending_count: self.pending.len(),
+            queued_count: self.queued.len(),
+            confirmed_count: settled(TransactionStatus::Confirmed),
+            failed_count: settled(TransactionStatus::Failed),
+            rejected_count: settled(TransactionStatus::Rejected),
+            expired_count: settled(TransactionStatus::Expired),
+            replaced_count: settled(TransactionStatus::Replaced),
+            total_transactions: self.pending.len() + self.queued.len() + self.history.len(),
+            average_fee: total_fees.checked_div(self.pending.len() as u128).unwrap_or(Amount::ZERO),
+            total_volume,
+            total_fees,
+            min_fee: self.min_fee(),
+            base_fee: self.base_fee,
+            max_pool_size: MAX_POOL_SIZE,
+            max_sender_transactions: MAX_SENDER_TRANSACTIONS,
+        }
+    }
+}
+
+/// Lowest fee that replaces a waiting transfer paying `fee`: MIN_FEE_BUMP_PERCENT more, and never
+/// the same fee again
+pub fn replacement_fee(fee: Amount) -> Amount {
+    fee.mul_div(100 + MIN_FEE_BUMP_PERCENT, 100)
+        .unwrap_or(Amount::MAX)
+        .max(fee.saturating_add(Amount::from_base_units(1)))
+}
+
+// Keeps the `pending_transactions` / `queued_transactions` shape of GET /blockchain
+impl Serialize for Mempool {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let pending: Vec<&Transaction> = self.pending.iter().map(|entry| &entry.transaction).collect();
+        let queued: Vec<&Transaction> = self.queued.iter().map(|entry| &entry.transaction).collect();
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("pending_transactions", &pending)?;
+        map.serialize_entry("queued_transactions", &queued)?;
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+    use crate::enhanced_transaction::EnhancedTransaction;
+    use crate::crypto::TransferAuth;
+    use crate::mining;
+    use crate::{Blockchain, CancelTransactionRequest};
+
+    fn l1(whole: u64) -> Amount {
+        Amount::from_l1(whole)
+    }
+
+    fn transfer(keys: &KeyPair, nonce: u64, fee: u64) -> (String, Transaction) {
+        let tx = EnhancedTransaction::new(keys.address(), "bob".to_string(), l1(10), l1(fee))
+            .with_nonce(nonce)
+            .sign(keys);
+        (tx.id.clone(), Transaction::from_enhanced(&tx))
+    }
+
+    fn add(pool: &mut Mempool, (id, tx): (String, Transaction)) -> Result<Admission, String> {
+        pool.add(id, tx, 0)
+    }
+
+    #[test]
+    fn test_duplicates_and_low_fees_rejected() {
+        let mut pool = Mempool::new();
+        let alice = KeyPair::generate();
+        let (id, tx) = transfer(&alice, 0, 1);
+        pool.add(id.clone(), tx.clone(), 0).unwrap();
+
+        // Same id or same transaction
+        assert!(add(&mut pool, (id.clone(), transfer(&KeyPair::generate(), 0, 1).1)).is_err());
+        assert!(add(&mut pool, ("other".to_string(), tx)).is_err());
+
+        let mut cheap = transfer(&KeyPair::generate(), 0, 1);
+        cheap.1.fee = Some(Amount::from_base_units(1));
+        assert!(add(&mut pool, cheap).is_err());
+        // Signed transfers without a fee would skip the fee market altogether
+        let mut free = transfer(&KeyPair::generate(), 0, 1);
+        free.1.fee = None;
+        assert!(add(&mut pool, free).unwrap_err().starts_with("Transaction carries no fee"));
+        assert_eq!(pool.get(&id).map(|entry| entry.status.clone()), Some(TransactionStatus::Pending));
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        let mut pool = Mempool::new();
+        let alice = KeyPair::generate();
+        let with_fee = |nonce: u64, fee: Amount| {
+            let tx = EnhancedTransaction::new(alice.address(), "carol".to_string(), l1(10), fee)
+                .with_nonce(nonce)
+                .sign(&alice);
+            (tx.id.clone(), Transaction::from_enhanced(&tx))
+        };
+        let (stuck, tx) = with_fee(0, l1(10));
+        pool.add(stuck.clone(), tx, 0).unwrap();
+        let (queued, tx) = with_fee(2, l1(1));
+        pool.add(queued.clone(), tx, 0).unwrap();
+
+        // A replacement must pay 10% more
+        assert_eq!(replacement_fee(l1(10)), l1(11));
+        let err = add(&mut pool, with_fee(0, Amount::from_base_units(1_099_999_999))).unwrap_err();
+        assert!(err.contains("at least 11"), "{}", err);
+        let (bumped, tx) = with_fee(0, l1(11));
+        assert_eq!(pool.add(bumped.clone(), tx, 0), Ok(Admission::Ready));
+        assert_eq!(pool.get(&stuck).unwrap().status, TransactionStatus::Replaced);
+        assert_eq!(pool.pending()[0].id, bumped);
+
+        // Queued transfers are replaced where they wait
+        let (cancel, tx) = with_fee(2, l1(2));
+        assert_eq!(pool.add(cancel.clone(), tx, 0), Ok(Admission::Queued));
+        assert_eq!(pool.queued()[0].id, cancel);
+        assert_eq!(pool.stats().replaced_count, 2);
+        assert_eq!(pool.pending_cost(&alice.address()), l1(33));
+    }
+
+    #[test]
+    fn test_future_nonce_is_queued() {
+        let mut pool = Mempool::new();
+        let alice = KeyPair::generate();
+
+        // Account is at nonce 3: older nonces are stale, later ones wait
+        assert!(pool.add("stale".to_string(), transfer(&alice, 2, 1).1, 3).is_err());
+        assert_eq!(pool.add("later".to_string(), transfer(&alice, 4, 1).1, 3), Ok(Admission::Queued));
+        assert!(pool.pending().is_empty());
+
+        assert_eq!(pool.add("next".to_string(), transfer(&alice, 3, 1).1, 3), Ok(Admission::Ready));
+        assert_eq!(pool.pending().len(), 2);
+        assert!(pool.queued().is_empty());
+        assert_eq!(pool.next_nonce(&alice.address(), 3), 5);
+    }
+
+    #[test]
+    fn test_selection_respects_fee_priority_and_nonce_order() {
+        let mut pool = Mempool::new();
+        let (alice, bob, carol) = (KeyPair::generate(), KeyPair::generate(), KeyPair::generate());
+        add(&mut pool, transfer(&alice, 0, 1)).unwrap();
+        add(&mut pool, transfer(&alice, 1, 9)).unwrap();
+        add(&mut pool, transfer(&bob, 0, 5)).unwrap();
+        add(&mut pool, transfer(&carol, 0, 3)).unwrap();
+
+        // Bob pays most up front; Alice's 9 only counts once her nonce 0 is in
+        let fees = |selected: Vec<&PoolEntry>| selected.iter().map(|entry| entry.fee()).collect::<Vec<_>>();
+        assert_eq!(fees(pool.select(1, |_| Ok(())).0), vec![l1(5)]);
+        assert_eq!(fees(pool.select(2, |_| Ok(())).0), vec![l1(5), l1(3)]);
+        assert_eq!(fees(pool.select(3, |_| Ok(())).0), vec![l1(1), l1(5), l1(3)]);
+        assert_eq!(fees(pool.select(10, |_| Ok(())).0), vec![l1(1), l1(9), l1(5), l1(3)]);
+    }
+
+    #[test]
+    fn test_settled_transactions_keep_receipts() {
+        let mut pool = Mempool::new();
+        let (alice, bob) = (KeyPair::generate(), KeyPair::generate());
+        let (mined, tx) = transfer(&alice, 0, 1);
+        let hash = tx.hash();
+        pool.add(mined.clone(), tx.clone(), 0).unwrap();
+        let (replaced, _) = transfer(&bob, 0, 1);
+        add(&mut pool, (replaced.clone(), transfer(&bob, 0, 1).1)).unwrap();
+        let (queued, _) = transfer(&bob, 2, 1);
+        add(&mut pool, (queued.clone(), transfer(&bob, 2, 1).1)).unwrap();
+
+        // Alice's transfer is mined; another of Bob's took nonces 0 and 1 on chain
+        pool.settle(&HashSet::from([hash.clone()]), |address| if address == bob.address() { 2 } else { 1 });
+        assert_eq!(pool.get(&mined).unwrap().status, TransactionStatus::Confirmed);
+        assert_eq!(pool.get(&hash).unwrap().id, mined);
+        assert_eq!(pool.get(&replaced).unwrap().status, TransactionStatus::Failed);
+        assert_eq!(pool.pending().iter().map(|entry| entry.id.clone()).collect::<Vec<_>>(), vec![queued]);
+
+        // A reorg that drops the block brings the transfer back under its id
+        pool.reinstate(vec![tx]);
+        assert_eq!(pool.pending()[0].id, mined);
+        assert_eq!(pool.get(&mined).unwrap().status, TransactionStatus::Pending);
+
+        let stats = pool.stats();
+        assert_eq!((stats.pending_count, stats.failed_count, stats.confirmed_count), (2, 1, 0));
+        assert_eq!(stats.total_fees, l1(2));
+    }
+
+    #[test]
+    fn test_block_takes_fees_of_selected_transactions_only() {
+        let mut blockchain = Blockchain::new();
+        let senders = [KeyPair::generate(), KeyPair::generate()];
+        for sender in &senders {
+            blockchain.create_system_transaction("genesis", sender.address(), l1(1_000)).unwrap();
+        }
+        blockchain.mine_pending_transactions("miner".to_string());
+        let genesis_hash = blockchain.chain[1].transactions[0].hash();
+
+        // Two senders take turns, as one may not have a block's worth waiting
+        let ids: Vec<String> = (0..=MAX_BLOCK_TRANSACTIONS).map(|i| {
+            let sender = &senders[i % 2];
+            let tx = EnhancedTransaction::new(sender.address(), "bob".to_string(), l1(1), l1(1))
+                .with_nonce((i / 2) as u64)
+                .sign(sender);
+            blockchain.add_enhanced_transaction(tx.clone()).unwrap();
+            tx.id
+        }).collect();
+
+        // One transfer over the limit waits, and its fee is not paid out; the miner gets each
+        // selected fee less the burned half of the base fee
+        let template = blockchain.block_template("miner").unwrap();
+        assert_eq!(template.block.transactions.len(), MAX_BLOCK_TRANSACTIONS + 1);
+        assert_eq!(template.block.base_fee, fee_market::MIN_BASE_FEE);
+        let (burned, tip) = fee_market::split_fee(l1(1), fee_market::MIN_BASE_FEE);
+        assert_eq!(template.total_fees, tip.mul_div(MAX_BLOCK_TRANSACTIONS as u128, 1).unwrap());
+        let supply = blockchain.ledger.circulating_supply().try_add(template.block.reward).unwrap();
+        let solved = mining::solve(template.block.clone(), &template.tip_signal, template.tip).unwrap();
+        blockchain.submit_block(template, solved).unwrap();
+        let burned = burned.mul_div(MAX_BLOCK_TRANSACTIONS as u128, 1).unwrap();
+        assert_eq!(blockchain.ledger.circulating_supply(), supply.saturating_sub(burned));
+
+        let mined = blockchain.get_transaction_receipt(&ids[0]).unwrap();
+        assert_eq!((mined.status, mined.block_number), (TransactionStatus::Confirmed, Some(2)));
+        let waiting = blockchain.get_transaction_receipt(&ids[MAX_BLOCK_TRANSACTIONS]).unwrap();
+        assert_eq!((waiting.status, waiting.block_number), (TransactionStatus::Pending, None));
+
+        // Transfers without an enhanced id have receipts under their hash
+        let payout = blockchain.get_transaction_receipt(&genesis_hash).unwrap();
+        assert_eq!((payout.status, payout.block_number), (TransactionStatus::Confirmed, Some(1)));
+    }
+
+    #[test]
+    fn test_transfers_must_be_dated_near_the_local_clock() {
+        let mut pool = Mempool::new();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let dated = |nonce: u64, timestamp: u64| Transaction {
+            from: "alice".to_string(),
+            to: "bob".to_string(),
+            amount: l1(1),
+            fee: Some(fee_market::MIN_BASE_FEE),
+            timestamp,
+            nonce: Some(nonce),
+            ..Default::default()
+        };
+
+        let err = pool.add("stale".to_string(), dated(0, now - EXPIRY_SECS - 60), 0).unwrap_err();
+        assert_eq!(err, "Transaction is expired");
+        // Dated far ahead, it would never expire
+        let err = pool.add("ahead".to_string(), dated(0, now + MAX_FUTURE_TX_SECS + 60), 0).unwrap_err();
+        assert!(err.contains("ahead of this node's clock"), "{}", err);
+        assert_eq!(pool.add("skewed".to_string(), dated(0, now + 60), 0), Ok(Admission::Ready));
+        assert_eq!(pool.cleanup_expired(), 0);
+    }
+
+    #[test]
+    fn test_full_pool_evicts_cheapest_and_caps_senders() {
+        let mut pool = Mempool::new();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let raw = |from: &str, nonce: u64, fee: Amount| Transaction {
+            from: from.to_string(),
+            to: "bob".to_string(),
+            amount: l1(1),
+            fee: Some(fee),
+            timestamp: now,
+            nonce: Some(nonce),
+            ..Default::default()
+        };
+        let base = fee_market::MIN_BASE_FEE;
+
+        // One sender fills its share of the pool and no more, though it may still replace
+        for nonce in 0..MAX_SENDER_TRANSACTIONS as u64 {
+            pool.add(format!("alice{}", nonce), raw("alice", nonce, base), 0).unwrap();
+        }
+        let err = pool.add("alice-over".to_string(), raw("alice", MAX_SENDER_TRANSACTIONS as u64, base), 0).unwrap_err();
+        assert!(err.contains("transactions waiting"), "{}", err);
+        assert_eq!(pool.add("alice3-bump".to_string(), raw("alice", 3, replacement_fee(base)), 0), Ok(Admission::Ready));
+        assert_eq!(pool.get("alice3").unwrap().reason.as_deref(), Some("replaced by alice3-bump, which pays a fee of 0.0011"));
+
+        // The minimum fee climbs from the base fee once the pool is half full
+        let mut senders = 0;
+        while pool.signed_count() < MAX_POOL_SIZE {
+            if pool.signed_count() == MAX_POOL_SIZE * 3 / 4 {
+                assert_eq!(pool.min_fee(), base.mul_div(55, 10).unwrap());
+            }
+            pool.add(format!("sender{}", senders), raw(&format!("sender{}", senders), 0, l1(1)), 0).unwrap();
+            senders += 1;
+        }
+        assert_eq!(pool.min_fee(), base.mul_div(FULL_POOL_FEE_MULTIPLIER, 1).unwrap());
+        assert!(pool.add("cheap".to_string(), raw("carol", 0, base), 0).unwrap_err().contains("fee too low"));
+
+        // A full pool evicts the cheapest transfer nothing builds on: Alice's last, not her first
+        assert_eq!(pool.add("whale".to_string(), raw("whale", 0, l1(2)), 0), Ok(Admission::Ready));
+        assert_eq!(pool.signed_count(), MAX_POOL_SIZE);
+        let last = format!("alice{}", MAX_SENDER_TRANSACTIONS - 1);
+        let evicted = pool.get(&last).unwrap().receipt(None);
+        assert_eq!(evicted.status, TransactionStatus::Rejected);
+        assert!(evicted.reason.unwrap().starts_with("evicted from the full pool by whale"));
+        assert_eq!(pool.get("alice0").unwrap().status, TransactionStatus::Pending);
+
+        // Transfers left waiting too long expire with the reason on their receipt
+        pool.pending[0].transaction.timestamp = now - EXPIRY_SECS - 1;
+        assert_eq!(pool.cleanup_expired(), 1);
+        let expired = pool.get("alice0").unwrap().receipt(None);
+        assert_eq!(expired.status, TransactionStatus::Expired);
+        assert_eq!(expired.reason.as_deref(), Some("not mined within 3600 seconds of its timestamp"));
+        assert!(pool.pending().iter().all(|entry| entry.transaction.from != "alice"));
+        assert_eq!(pool.stats().rejected_count, 1);
+    }
+
+    #[test]
+    fn test_pending_spends_count_against_balance() {
+        let mut pool = Mempool::new();
+        let alice = KeyPair::generate();
+        let (_, first) = transfer(&alice, 0, 1);
+        let (_, second) = transfer(&alice, 1, 1);
+
+        // Each transfer costs 11 L1; 20 covers one but not both
+        pool.check_funds(&first, l1(20)).unwrap();
+        pool.add("first".to_string(), first, 0).unwrap();
+        assert_eq!(pool.pending_cost(&alice.address()), l1(11));
+        assert!(pool.check_funds(&second, l1(20)).is_err());
+        assert!(pool.check_funds(&second, l1(22)).is_ok());
+    }
+
+    #[test]
+    fn test_failed_transfer_holds_back_later_nonces() {
+        let mut pool = Mempool::new();
+        let (alice, bob) = (KeyPair::generate(), KeyPair::generate());
+        let (first, tx) = transfer(&alice, 0, 5);
+        let hash = tx.hash();
+        pool.add(first.clone(), tx, 0).unwrap();
+        add(&mut pool, transfer(&alice, 1, 5)).unwrap();
+        add(&mut pool, transfer(&bob, 0, 1)).unwrap();
+
+        let (selected, failed) = pool.select(10, |entry| {
+            if entry.transaction.from == alice.address() { Err("no funds".to_string()) } else { Ok(()) }
+        });
+        assert_eq!(selected.len(), 1);
+        assert_eq!(failed, vec![(hash.clone(), "no funds".to_string())]);
+
+        // Nonce 0 can be sent again; nonce 1 waits for it
+        pool.fail(&hash, "no funds");
+        assert_eq!(pool.get(&first).unwrap().status, TransactionStatus::Failed);
+        assert_eq!(pool.queued().len(), 1);
+        assert_eq!(pool.next_nonce(&alice.address(), 0), 0);
+        add(&mut pool, transfer(&alice, 0, 1)).unwrap();
+        assert_eq!(pool.pending().len(), 3);
+    }
+
+    #[test]
+    fn test_block_assembly_fails_overdrawn_transfers() {
+        let mut blockchain = Blockchain::new();
+        let alice = KeyPair::generate();
+        blockchain.create_system_transaction("genesis", alice.address(), l1(20)).unwrap();
+        blockchain.mine_pending_transactions("miner".to_string());
+
+        let (_, tx) = transfer(&alice, 0, 1);
+        let first_hash = tx.hash();
+        blockchain.queue_transaction(tx).unwrap();
+        let second = EnhancedTransaction::new(alice.address(), "bob".to_string(), l1(10), l1(1))
+            .with_nonce(1)
+            .sign(&alice);
+        let err = blockchain.add_enhanced_transaction(second.clone()).unwrap_err();
+        assert!(err.contains("already pending"), "{}", err);
+
+        // Admitted behind the pool's back, the second transfer fails when the block is built
+        blockchain.mempool.add(second.id.clone(), Transaction::from_enhanced(&second), 0).unwrap();
+        blockchain.mine_pending_transactions("miner".to_string());
+        assert_eq!(blockchain.get_transaction_receipt(&first_hash).unwrap().status, TransactionStatus::Confirmed);
+        assert_eq!(blockchain.get_transaction_receipt(&second.id).unwrap().status, TransactionStatus::Failed);
+        assert_eq!(blockchain.get_balance("bob"), l1(10));
+        assert!(blockchain.mempool.pending().is_empty());
+    }
+
+    #[test]
+    fn test_missing_fee_reports_the_base_fee() {
+        let mut blockchain = Blockchain::new();
+        let (alice, bob) = (KeyPair::generate(), KeyPair::generate());
+        let unpriced = Transaction::signed(alice.address(), bob.address(), l1(1), None, TransferAuth::default());
+
+        // Caught before the signature check, since a client that left out the fee did not sign one
+        let err = blockchain.create_transaction(unpriced).unwrap_err();
+        assert!(err.starts_with("A fee is required"), "{}", err);
+        assert!(err.ends_with(&blockchain.next_base_fee().to_string()), "{}", err);
+    }
+
+    #[test]
+    fn test_cancelled_transfer_only_uses_its_nonce() {
+        let mut blockchain = Blockchain::new();
+        let alice = KeyPair::generate();
+        blockchain.create_system_transaction("genesis", alice.address(), l1(20)).unwrap();
+        blockchain.mine_pending_transactions("miner".to_string());
+
+        let stuck = EnhancedTransaction::new(alice.address(), "bob".to_string(), l1(10), l1(1))
+            .with_nonce(0)
+            .sign(&alice);
+        blockchain.add_enhanced_transaction(stuck.clone()).unwrap();
+
+        let cancellation = EnhancedTransaction::cancellation(alice.address(), 0, l1(2)).sign(&alice);
+        let request = |tx: &EnhancedTransaction| CancelTransactionRequest {
+            from: tx.from.clone(),
+            fee: tx.fee,
+            auth: TransferAuth {
+                timestamp: Some(tx.timestamp),
+                nonce: Some(tx.nonce),
+                public_key: tx.public_key.clone(),
+                signature: Some(tx.signature.clone()),
+            },
+        };
+        let forged = EnhancedTransaction::cancellation(alice.address(), 0, l1(2)).sign(&KeyPair::generate());
+        assert!(blockchain.cancel_transaction(request(&forged)).is_err());
+        let (cancel_id, _) = blockchain.cancel_transaction(request(&cancellation)).unwrap();
+
+        blockchain.mine_pending_transactions("miner".to_string());
+        assert_eq!(blockchain.get_transaction_receipt(&stuck.id).unwrap().status, TransactionStatus::Replaced);
+        assert_eq!(blockchain.get_transaction_receipt(&cancel_id).unwrap().status, TransactionStatus::Confirmed);
+        assert_eq!(blockchain.get_balance("bob"), Amount::ZERO);
+        assert_eq!(blockchain.ledger.nonce(&alice.address()), 1);
+        assert!(blockchain.cancel_transaction(request(&cancellation)).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+// Leaves and inner nodes hash under different prefixes so one can never pass for the other
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Side of the running hash a proof sibling is concatenated on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// One level of an inclusion proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProofStep {
+    pub hash: String,
+    pub side: Side,
+}
+
+/// Leaf hash: hex(SHA-256(0x00 || data))
+pub fn leaf_hash(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
+
+// Inner node: hex(SHA-256(0x01 || left hex || right hex))
+fn node_hash(left: &str, right: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+// Pair up one level; an unpaired last node is carried up unchanged rather than duplicated
+fn next_level(level: &[String]) -> Vec<String> {
+    level.chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => single.clone(),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Root over the given leaf hashes; an empty set commits to the hash of no data
+pub fn merkle_root(leaves: &[String]) -> String {
+    if leaves.is_empty() {
+        return leaf_hash(&[]);
+    }
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.remove(0)
+}
+
+/// Sibling hashes from the leaf at `index` up to the root
+pub fn merkle_proof(leaves: &[String], mut index: usize) -> Option<Vec<ProofStep>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if let Some(hash) = level.get(sibling) {
+            let side = if sibling < index { Side::Left } else { Side::Right };
+            proof.push(ProofStep { hash: hash.clone(), side });
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Recompute the root from a leaf and its proof; what a light client runs
+pub fn verify_proof(leaf: &str, proof: &[ProofStep], root: &str) -> bool {
+    let computed = proof.iter().fold(leaf.to_string(), |acc, step| match step.side {
+        Side::Left => node_hash(&step.hash, &acc),
+        Side::Right => node_hash(&acc, &step.hash),
+    });
+    computed == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<String> {
+        (0..count).map(|i| leaf_hash(format!("tx{}", i).as_bytes())).collect()
+    }
+
+    #[test]
+    fn test_single_leaf_is_root() {
+        let leaves = leaves(1);
+        assert_eq!(merkle_root(&leaves), leaves[0]);
+        assert_eq!(merkle_proof(&leaves, 0), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_every_proof_verifies() {
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = merkle_proof(&leaves, index).unwrap();
+                assert!(verify_proof(leaf, &proof, &root), "leaf {} of {}", index, count);
+            }
+            assert!(merkle_proof(&leaves, count).is_none());
+        }
+    }
+
+    #[test]
+    fn test_wrong_leaf_or_root_rejected() {
+        let leaves = leaves(5);
+        let root = merkle_root(&leaves);
+        let proof = merkle_proof(&leaves, 2).unwrap();
+
+        assert!(!verify_proof(&leaves[3], &proof, &root));
+        assert!(!verify_proof(&leaves[2], &proof, &merkle_root(&leaves[..4])));
+    }
+}
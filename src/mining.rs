@@ -0,0 +1,151 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+use crate::amount::Amount;
+use crate::{Block, Blockchain};
+
+/// Nonces tried between checks for a new chain tip
+const TIP_CHECK_INTERVAL: u64 = 4096;
+
+/// Attempts the background worker makes before giving up on a request
+const MAX_ATTEMPTS: usize = 3;
+
+/// Counter bumped whenever the chain tip moves, so running nonce searches can tell they went stale
+#[derive(Debug, Clone, Default)]
+pub struct TipSignal(Arc<AtomicU64>);
+
+impl TipSignal {
+    pub fn current(&self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn advance(&self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// An unmined block built on the current tip, plus what to do once it is accepted
+pub struct BlockTemplate {
+    pub block: Block,
+    pub tip: u64,
+    pub tip_signal: TipSignal,
+    pub total_reward: Amount,
+    pub total_fees: Amount,
+}
+
+/// Search nonces until the block meets its difficulty; `None` if the tip moved first
+pub fn solve(mut block: Block, tip_signal: &TipSignal, tip: u64) -> Option<Block> {
+    println!("Mining block {} at difficulty {}...", block.index, block.difficulty);
+    while !block.meets_difficulty() {
+        if block.nonce.is_multiple_of(TIP_CHECK_INTERVAL) && tip_signal.current() != tip {
+            println!("Abandoning block {}: chain tip changed", block.index);
+            return None;
+        }
+        block.nonce += 1;
+        block.hash = block.calculate_hash();
+    }
+    println!("Block {} mined! Hash: {}", block.index, block.hash);
+    Some(block)
+}
+
+/// Solve a template on the blocking pool and submit it; the chain lock is only taken to submit
+pub async fn mine_template(blockchain: Arc<Mutex<Blockchain>>, template: BlockTemplate) -> Result<String, String> {
+    let (block, tip_signal, tip) = (template.block.clone(), template.tip_signal.clone(), template.tip);
+    let solved = tokio::task::spawn_blocking(move || solve(block, &tip_signal, tip))
+        .await
+        .map_err(|e| format!("Mining task failed: {}", e))?
+        .ok_or_else(|| "Chain tip changed while mining; try again".to_string())?;
+
+    let mut bc = blockchain.lock().unwrap();
+    bc.submit_block(template, solved)
+}
+
+/// Background worker for blocks the node mines on its own (signup bonuses, social rewards)
+pub fn spawn_worker(blockchain: Arc<Mutex<Blockchain>>) -> mpsc::UnboundedSender<String> {
+    let (sender, mut requests) = mpsc::unbounded_channel::<String>();
+
+    tokio::spawn(async move {
+        while let Some(miner_address) = requests.recv().await {
+            // One block picks up everything pending, so queued requests can be folded in
+            while requests.try_recv().is_ok() {}
+
+            for _ in 0..MAX_ATTEMPTS {
+                let template = blockchain.lock().unwrap().block_template(&miner_address);
+                let Ok(template) = template else {
+                    break; // Nothing left to mine
+                };
+                match mine_template(blockchain.clone(), template).await {
+                    Ok(_) => break,
+                    Err(e) => println!("⚠️ Background mining for {}: {}", miner_address, e),
+                }
+            }
+        }
+    });
+
+    sender
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+    use crate::enhanced_transaction::EnhancedTransaction;
+    use crate::fee_market;
+
+    fn chain_with_pending() -> Blockchain {
+        let mut blockchain = Blockchain::new();
+        blockchain.create_system_transaction("genesis", "alice".to_string(), Amount::from_l1(10)).unwrap();
+        blockchain
+    }
+
+    #[test]
+    fn test_search_abandoned_when_tip_moves() {
+        let tip_signal = TipSignal::default();
+        let block = Block::new(1, Vec::new(), "0".to_string(), "miner".to_string()).with_difficulty(u64::MAX);
+        let tip = tip_signal.current();
+        tip_signal.advance();
+
+        assert!(solve(block, &tip_signal, tip).is_none());
+    }
+
+    #[test]
+    fn test_stale_template_rejected_on_submit() {
+        let mut blockchain = chain_with_pending();
+        let template = blockchain.block_template("miner").unwrap();
+        let solved = solve(template.block.clone(), &TipSignal::default(), 0).unwrap();
+
+        // Someone else's block lands while we were searching
+        blockchain.mine_pending_transactions("other".to_string());
+        assert!(blockchain.submit_block(template, solved).is_err());
+        assert_eq!(blockchain.chain.len(), 2);
+    }
+
+    #[test]
+    fn test_transactions_added_while_mining_stay_pending() {
+        let mut blockchain = chain_with_pending();
+        let template = blockchain.block_template("miner").unwrap();
+        blockchain.create_system_transaction("genesis", "bob".to_string(), Amount::from_l1(5)).unwrap();
+
+        let solved = solve(template.block.clone(), &template.tip_signal, template.tip).unwrap();
+        assert!(blockchain.submit_block(template, solved).is_ok());
+        assert_eq!(blockchain.get_balance("alice"), Amount::from_l1(10));
+        assert_eq!(blockchain.mempool.pending().len(), 1);
+    }
+
+    #[test]
+    fn test_miner_is_paid_subsidy_and_tips() {
+        let sender = KeyPair::generate();
+        let mut blockchain = Blockchain::new();
+        blockchain.create_system_transaction("genesis", sender.address(), Amount::from_l1(10)).unwrap();
+        blockchain.mine_pending_transactions("funder".to_string());
+        let tx = EnhancedTransaction::new(sender.address(), "bob".to_string(), Amount::from_l1(1), Amount::from_l1(1))
+            .sign(&sender);
+        blockchain.add_enhanced_transaction(tx).unwrap();
+
+        blockchain.mine_pending_transactions("miner".to_string());
+        let (_, tip) = fee_market::split_fee(Amount::from_l1(1), blockchain.chain[2].base_fee);
+        let expected = blockchain.emission.block_subsidy(2).saturating_add(tip);
+        assert_eq!(blockchain.get_balance("miner"), expected);
+    }
+}
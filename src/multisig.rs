@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::crypto::{MultisigAuth, MultisigPolicy, MultisigSignature};
+use crate::enhanced_transaction::EnhancedTransaction;
+
+/// Open proposals one multisig account may have at a time
+const MAX_PROPOSALS_PER_ACCOUNT: usize = 32;
+
+/// A transfer from a multisig account waiting for its members' signatures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Proposal {
+    /// Also the id the transaction is submitted under
+    pub id: String,
+    /// Unsigned transfer; each member signs its `signing_payload()`
+    pub transaction: EnhancedTransaction,
+    pub threshold: usize,
+    pub signatures: Vec<MultisigSignature>,
+    pub created_at: u64,
+}
+
+/// Multisig accounts this node knows the policy of, and the proposals collecting signatures for them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MultisigRegistry {
+    accounts: HashMap<String, MultisigPolicy>,
+    proposals: Vec<Proposal>,
+}
+
+impl MultisigRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember a policy so transfers can be proposed from its address; registering it again is harmless
+    pub fn create_account(&mut self, policy: MultisigPolicy) -> Result<String, String> {
+        policy.validate()?;
+        let address = policy.address();
+        self.accounts.insert(address.clone(), policy);
+        Ok(address)
+    }
+
+    pub fn policy(&self, address: &str) -> Option<&MultisigPolicy> {
+        self.accounts.get(address)
+    }
+
+    /// Open a proposal for a transfer from a registered account; its nonce and timestamp must already be set
+    pub fn propose(&mut self, transaction: EnhancedTransaction) -> Result<&Proposal, String> {
+        let policy = self.accounts.get(&transaction.from)
+            .ok_or_else(|| format!("{} is not a known multisig account", transaction.from))?;
+        transaction.validate()?;
+        let open = self.proposals.iter().filter(|p| p.transaction.from == transaction.from).count();
+        if open >= MAX_PROPOSALS_PER_ACCOUNT {
+            return Err(format!("{} already has {} open proposals", transaction.from, open));
+        }
+
+        self.proposals.push(Proposal {
+            id: transaction.id.clone(),
+            threshold: policy.threshold,
+            transaction,
+            signatures: Vec::new(),
+            created_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        });
+        Ok(self.proposals.last().unwrap())
+    }
+
+    /// Nonce for the next proposal from `address`: after both the chain's and those still collecting signatures
+    pub fn next_nonce(&self, address: &str, chain_next_nonce: u64) -> u64 {
+        self.proposals.iter()
+            .filter(|p| p.transaction.from == address)
+            .map(|p| p.transaction.nonce + 1)
+            .max()
+            .unwrap_or(0)
+            .max(chain_next_nonce)
+    }
+
+    /// Record a member's signature, replacing one they gave before. Once the threshold is met,
+    /// returns the transaction with the collected signatures attached, ready to submit.
+    pub fn sign(&mut self, id: &str, signature: MultisigSignature) -> Result<Option<EnhancedTransaction>, String> {
+        let proposal = self.proposals.iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("No open proposal {}", id))?;
+        let policy = self.accounts.get(&proposal.transaction.from)
+            .ok_or_else(|| format!("{} is not a known multisig account", proposal.transaction.from))?;
+        policy.verify_member(&signature, proposal.transaction.signing_payload().as_bytes())?;
+
+        proposal.signatures.retain(|existing| existing.public_key != signature.public_key);
+        proposal.signatures.push(signature);
+        if proposal.signatures.len() < policy.threshold {
+            return Ok(None);
+        }
+
+        let auth = MultisigAuth { policy: policy.clone(), signatures: proposal.signatures.clone() };
+        Ok(Some(proposal.transaction.clone().with_multisig(auth)))
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<Proposal> {
+        let pos = self.proposals.iter().position(|p| p.id == id)?;
+        Some(self.proposals.remove(pos))
+    }
+
+    /// Open proposals, oldest first, optionally only those from one account
+    pub fn proposals(&self, address: Option<&str>) -> Vec<&Proposal> {
+        self.proposals.iter()
+            .filter(|p| address.is_none_or(|address| p.transaction.from == address))
+            .collect()
+    }
+
+    /// Drop proposals the pool would now refuse as expired; returns how many went
+    pub fn cleanup_expired(&mut self) -> usize {
+        let before = self.proposals.len();
+        self.proposals.retain(|p| !p.transaction.is_expired());
+        before - self.proposals.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::chain_spec::{ChainSpec, GenesisAllocation};
+    use crate::crypto::KeyPair;
+    use crate::{Blockchain, Transaction};
+
+    fn sign(keys: &KeyPair, proposal: &Proposal) -> MultisigSignature {
+        MultisigSignature {
+            public_key: keys.public_key_hex(),
+            signature: keys.sign(proposal.transaction.signing_payload().as_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_signatures_collected_until_threshold() {
+        let members: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate()).collect();
+        let policy = MultisigPolicy::new(2, members.iter().map(KeyPair::public_key_hex).collect()).unwrap();
+        let mut registry = MultisigRegistry::new();
+        let address = registry.create_account(policy).unwrap();
+
+        let recipient = KeyPair::generate().address();
+        let tx = EnhancedTransaction::new(address.clone(), recipient.clone(), Amount::from_l1(5), Amount::from_l1(1));
+        assert!(registry.propose(EnhancedTransaction::new(recipient, address.clone(), Amount::from_l1(5), Amount::ZERO)).is_err());
+        let proposal = registry.propose(tx.with_nonce(registry.next_nonce(&address, 0))).unwrap().clone();
+        assert_eq!(registry.next_nonce(&address, 0), 1);
+
+        // Outsiders cannot sign, and a member signing twice still counts once
+        assert!(registry.sign(&proposal.id, sign(&KeyPair::generate(), &proposal)).is_err());
+        assert!(registry.sign(&proposal.id, sign(&members[1], &proposal)).unwrap().is_none());
+        assert!(registry.sign(&proposal.id, sign(&members[1], &proposal)).unwrap().is_none());
+
+        let signed = registry.sign(&proposal.id, sign(&members[2], &proposal)).unwrap().unwrap();
+        assert!(signed.verify_signature().is_ok());
+        assert_eq!(registry.proposals(Some(&address)).len(), 1);
+        registry.remove(&proposal.id);
+        assert!(registry.proposals(None).is_empty());
+    }
+
+    #[test]
+    fn test_multisig_transfer_imports_on_peer() {
+        let members: Vec<KeyPair> = (0..2).map(|_| KeyPair::generate()).collect();
+        let policy = MultisigPolicy::new(2, members.iter().map(KeyPair::public_key_hex).collect()).unwrap();
+        let mut spec = ChainSpec::default();
+        spec.genesis.allocations.push(GenesisAllocation { address: policy.address(), amount: Amount::from_l1(50), lock: None });
+        let mut node = Blockchain::with_spec(&spec);
+        let mut peer = Blockchain::with_spec(&spec);
+        let address = node.multisig.create_account(policy).unwrap();
+
+        let recipient = KeyPair::generate().address();
+        let tx = EnhancedTransaction::new(address.clone(), recipient.clone(), Amount::from_l1(20), Amount::from_l1(1));
+        let proposal = node.multisig.propose(tx).unwrap().clone();
+        for member in &members {
+            if let Some(signed) = node.multisig.sign(&proposal.id, sign(member, &proposal)).unwrap() {
+                node.add_enhanced_transaction(signed).unwrap();
+            }
+        }
+        node.mine_pending_transactions("miner".to_string());
+        assert_eq!(node.get_balance(&recipient), Amount::from_l1(20));
+
+        // The block carries what the members signed, so a peer can check it without the registry
+        let block = &node.chain[1];
+        assert!(block.transactions.iter().any(|tx| tx.multisig.is_some()));
+        for block in &node.chain[1..] {
+            peer.import_block(block.clone()).unwrap();
+        }
+        assert_eq!(peer.get_balance(&recipient), Amount::from_l1(20));
+
+        let mut forged: Transaction = block.transactions.iter().find(|tx| tx.multisig.is_some()).unwrap().clone();
+        forged.multisig.as_mut().unwrap().signatures.pop();
+        assert!(forged.verify_signature().is_err());
+    }
+}
@@ -0,0 +1,492 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time;
+
+use crate::enhanced_transaction::EnhancedTransaction;
+use crate::{Block, BlockHeader, Blockchain, Transaction};
+
+/// Protocol version this node speaks, and the oldest one it still accepts from peers
+pub const PROTOCOL_VERSION: u32 = 2;
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// First version that serves headers and blocks to syncing peers
+pub const SYNC_VERSION: u32 = 2;
+
+/// Most headers or blocks a peer returns for one request
+pub const MAX_HEADERS_PER_REQUEST: u64 = 500;
+pub const MAX_BLOCKS_PER_REQUEST: usize = 16;
+
+const MAX_FRAME_LEN: u32 = 16 * 1024 * 1024;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const SEEN_CAPACITY: usize = 10_000;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Wire messages; each frame is a 4-byte big-endian length followed by the JSON body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Hello(Hello),
+    Disconnect { reason: String },
+    NewBlock { block: Block },
+    NewTransaction { transaction: Transaction },
+    NewEnhancedTransaction { transaction: EnhancedTransaction },
+    /// Headers after the first `locator` hash the peer has on its active chain
+    GetHeaders { request_id: u64, locator: Vec<String>, limit: u64 },
+    Headers { request_id: u64, headers: Vec<BlockHeader> },
+    GetBlocks { request_id: u64, hashes: Vec<String> },
+    Blocks { request_id: u64, blocks: Vec<Block> },
+}
+
+/// First message on every connection, sent by both sides
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hello {
+    pub version: u32,
+    pub min_version: u32,
+    pub node_id: String,
+    pub listen_port: u16,
+    pub genesis_hash: String,
+    pub height: u64,
+}
+
+/// Where this node listens and which peers it keeps dialing
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    pub listen: SocketAddr,
+    pub peers: Vec<String>,
+}
+
+/// A connected peer as reported by GET /rpc/peers
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerInfo {
+    pub node_id: String,
+    pub address: String,
+    pub listen_address: String,
+    pub version: u32,
+    pub height: u64,
+    pub inbound: bool,
+}
+
+pub async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Message) -> Result<(), String> {
+    let payload = serde_json::to_vec(message)
+        .map_err(|e| format!("Failed to encode message: {}", e))?;
+    let len = u32::try_from(payload.len()).ok()
+        .filter(|len| *len <= MAX_FRAME_LEN)
+        .ok_or_else(|| format!("Message of {} bytes is too large", payload.len()))?;
+
+    writer.write_all(&len.to_be_bytes()).await
+        .and(writer.write_all(&payload).await)
+        .and(writer.flush().await)
+        .map_err(|e| format!("Failed to send message: {}", e))
+}
+
+pub async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Message, String> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len).await
+        .map_err(|e| format!("Connection closed: {}", e))?;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME_LEN {
+        return Err(format!("Frame of {} bytes exceeds the {} byte limit", len, MAX_FRAME_LEN));
+    }
+
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload).await
+        .map_err(|e| format!("Connection closed: {}", e))?;
+    serde_json::from_slice(&payload).map_err(|e| format!("Malformed message: {}", e))
+}
+
+/// Highest protocol version both sides speak, or why the peer cannot be used
+pub fn negotiate(ours: &Hello, theirs: &Hello) -> Result<u32, String> {
+    if theirs.node_id == ours.node_id {
+        return Err("Connected to self".to_string());
+    }
+    if theirs.genesis_hash != ours.genesis_hash {
+        return Err("Peer is on a different network (genesis mismatch)".to_string());
+    }
+
+    let version = ours.version.min(theirs.version);
+    if version < ours.min_version || version < theirs.min_version {
+        return Err(format!(
+            "No common protocol version (ours {}-{}, theirs {}-{})",
+            ours.min_version, ours.version, theirs.min_version, theirs.version
+        ));
+    }
+    Ok(version)
+}
+
+// Identifies a gossiped payload so it is processed and relayed only once
+fn gossip_key(message: &Message) -> Option<String> {
+    match message {
+        Message::NewBlock { block } => Some(format!("block:{}", block.hash)),
+        Message::NewTransaction { transaction } => Some(format!("tx:{}", transaction.hash())),
+        Message::NewEnhancedTransaction { transaction } => Some(format!("enhanced:{}", transaction.id)),
+        _ => None,
+    }
+}
+
+/// Bounded set of recently seen gossip keys, oldest evicted first
+#[derive(Default)]
+struct SeenSet {
+    keys: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl SeenSet {
+    fn contains(&self, key: &str) -> bool {
+        self.keys.contains(key)
+    }
+
+    // False if the key was already present
+    fn insert(&mut self, key: String) -> bool {
+        if !self.keys.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > SEEN_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+struct Peer {
+    info: PeerInfo,
+    outbox: mpsc::UnboundedSender<Message>,
+}
+
+/// Peer connections and block/transaction gossip for one node
+pub struct Network {
+    node_id: String,
+    listen_port: u16,
+    blockchain: Arc<Mutex<Blockchain>>,
+    peers: Mutex<HashMap<String, Peer>>,
+    seen: Mutex<SeenSet>,
+    // Outstanding requests by id, with the peer expected to answer
+    requests: Mutex<HashMap<u64, (String, oneshot::Sender<Message>)>>,
+    next_request: AtomicU64,
+}
+
+impl Network {
+    /// Listen for peers, keep dialing the static peer list and gossip what the local node accepts
+    pub async fn start(blockchain: Arc<Mutex<Blockchain>>, config: NetworkConfig) -> Result<Arc<Self>, String> {
+        let listener = TcpListener::bind(config.listen).await
+            .map_err(|e| format!("Failed to listen for peers on {}: {}", config.listen, e))?;
+        let listen_port = listener.local_addr().map(|addr| addr.port()).unwrap_or(config.listen.port());
+
+        let network = Arc::new(Network {
+            node_id: format!("{:016x}", rand::random::<u64>()),
+            listen_port,
+            blockchain: blockchain.clone(),
+            peers: Mutex::new(HashMap::new()),
+            seen: Mutex::new(SeenSet::default()),
+            requests: Mutex::new(HashMap::new()),
+            next_request: AtomicU64::new(0),
+        });
+
+        // Blocks and transactions the chain accepts come back here to be relayed
+        let (gossip, mut announcements) = mpsc::unbounded_channel();
+        blockchain.lock().unwrap().gossip = Some(gossip);
+        let relay = network.clone();
+        tokio::spawn(async move {
+            while let Some(message) = announcements.recv().await {
+                relay.relay(message);
+            }
+        });
+
+        let acceptor = network.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, address)) => {
+                        let network = acceptor.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = network.run_peer(stream, address.to_string(), true).await {
+                                println!("🔌 Peer {} disconnected: {}", address, e);
+                            }
+                        });
+                    }
+                    Err(e) => println!("⚠️ Failed to accept peer connection: {}", e),
+                }
+            }
+        });
+
+        for address in config.peers {
+            tokio::spawn(network.clone().maintain_outbound(address));
+        }
+
+        Ok(network)
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    pub fn listen_port(&self) -> u16 {
+        self.listen_port
+    }
+
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        self.peers.lock().unwrap().values().map(|peer| peer.info.clone()).collect()
+    }
+
+    /// Send a request to one peer and wait for its answer
+    pub async fn request(&self, peer_id: &str, build: impl FnOnce(u64) -> Message) -> Result<Message, String> {
+        let request_id = self.next_request.fetch_add(1, Ordering::SeqCst);
+        let (sender, response) = oneshot::channel();
+        self.requests.lock().unwrap().insert(request_id, (peer_id.to_string(), sender));
+
+        let result = if self.send_to(peer_id, build(request_id)) {
+            match time::timeout(REQUEST_TIMEOUT, response).await {
+                Ok(Ok(message)) => Ok(message),
+                Ok(Err(_)) => Err(format!("Request to peer {} was dropped", peer_id)),
+                Err(_) => Err(format!("Peer {} did not answer in time", peer_id)),
+            }
+        } else {
+            Err(format!("Peer {} is not connected", peer_id))
+        };
+
+        self.requests.lock().unwrap().remove(&request_id);
+        result
+    }
+
+    fn send_to(&self, peer_id: &str, message: Message) -> bool {
+        self.peers.lock().unwrap().get(peer_id)
+            .is_some_and(|peer| peer.outbox.send(message).is_ok())
+    }
+
+    // Redial a static peer whenever its connection drops
+    async fn maintain_outbound(self: Arc<Self>, address: String) {
+        loop {
+            let already_connected = self.peers.lock().unwrap().values()
+                .any(|peer| peer.info.listen_address == address);
+            if !already_connected {
+                match TcpStream::connect(&address).await {
+                    Ok(stream) => {
+                        if let Err(e) = self.clone().run_peer(stream, address.clone(), false).await {
+                            println!("🔌 Peer {} disconnected: {}", address, e);
+                        }
+                    }
+                    Err(e) => println!("⚠️ Could not reach peer {}: {}", address, e),
+                }
+            }
+            time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    fn hello(&self) -> Hello {
+        let bc = self.blockchain.lock().unwrap();
+        Hello {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+            node_id: self.node_id.clone(),
+            listen_port: self.listen_port,
+            genesis_hash: bc.chain.first().map(|block| block.hash.clone()).unwrap_or_default(),
+            height: bc.height(),
+        }
+    }
+
+    // Handshake, then read messages until the connection drops
+    async fn run_peer(self: Arc<Self>, stream: TcpStream, address: String, inbound: bool) -> Result<(), String> {
+        let remote_ip = stream.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_default();
+        let (mut reader, mut writer) = stream.into_split();
+
+        let ours = self.hello();
+        write_message(&mut writer, &Message::Hello(ours.clone())).await?;
+        let theirs = match time::timeout(HANDSHAKE_TIMEOUT, read_message(&mut reader)).await {
+            Ok(Ok(Message::Hello(hello))) => hello,
+            Ok(Ok(_)) => return Err("Peer did not start with a hello".to_string()),
+            Ok(Err(e)) => return Err(e),
+            Err(_) => return Err("Handshake timed out".to_string()),
+        };
+
+        let (outbox, mut outgoing) = mpsc::unbounded_channel();
+        let registered = negotiate(&ours, &theirs).and_then(|version| {
+            let mut peers = self.peers.lock().unwrap();
+            if peers.contains_key(&theirs.node_id) {
+                return Err(format!("Already connected to node {}", theirs.node_id));
+            }
+            let info = PeerInfo {
+                node_id: theirs.node_id.clone(),
+                address: address.clone(),
+                listen_address: format!("{}:{}", remote_ip, theirs.listen_port),
+                version,
+                height: theirs.height,
+                inbound,
+            };
+            peers.insert(theirs.node_id.clone(), Peer { info, outbox });
+            Ok(version)
+        });
+        let version = match registered {
+            Ok(version) => version,
+            Err(reason) => {
+                let _ = write_message(&mut writer, &Message::Disconnect { reason: reason.clone() }).await;
+                return Err(reason);
+            }
+        };
+        println!("🤝 Connected to peer {} at {} (protocol v{}, height {})", theirs.node_id, address, version, theirs.height);
+
+        let writer_task = tokio::spawn(async move {
+            while let Some(message) = outgoing.recv().await {
+                if write_message(&mut writer, &message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let result = loop {
+            match read_message(&mut reader).await {
+                Ok(Message::Disconnect { reason }) => break Err(format!("Peer closed the connection: {}", reason)),
+                Ok(message) => self.handle_message(&theirs.node_id, message),
+                Err(e) => break Err(e),
+            }
+        };
+
+        self.peers.lock().unwrap().remove(&theirs.node_id);
+        writer_task.abort();
+        result
+    }
+
+    fn handle_message(&self, from: &str, message: Message) {
+        match message {
+            Message::GetHeaders { request_id, locator, limit } => {
+                let headers = self.blockchain.lock().unwrap()
+                    .headers_after(&locator, limit.min(MAX_HEADERS_PER_REQUEST));
+                self.send_to(from, Message::Headers { request_id, headers });
+            }
+            Message::GetBlocks { request_id, mut hashes } => {
+                hashes.truncate(MAX_BLOCKS_PER_REQUEST);
+                let blocks = self.blockchain.lock().unwrap().blocks_by_hash(&hashes);
+                self.send_to(from, Message::Blocks { request_id, blocks });
+            }
+            Message::Headers { request_id, .. } | Message::Blocks { request_id, .. } => {
+                self.complete_request(from, request_id, message);
+            }
+            Message::NewBlock { .. } | Message::NewTransaction { .. } | Message::NewEnhancedTransaction { .. } => {
+                self.handle_gossip(from, message);
+            }
+            Message::Hello(_) | Message::Disconnect { .. } => {}
+        }
+    }
+
+    // Hand a response to whoever is waiting on it, if it came from the peer that was asked
+    fn complete_request(&self, from: &str, request_id: u64, message: Message) {
+        let mut requests = self.requests.lock().unwrap();
+        if requests.get(&request_id).is_some_and(|(peer_id, _)| peer_id == from) {
+            if let Some((_, waiter)) = requests.remove(&request_id) {
+                let _ = waiter.send(message);
+            }
+        }
+    }
+
+    fn handle_gossip(&self, from: &str, message: Message) {
+        let Some(key) = gossip_key(&message) else {
+            return;
+        };
+        if self.seen.lock().unwrap().contains(&key) {
+            return;
+        }
+        if let Message::NewBlock { block } = &message {
+            if let Some(peer) = self.peers.lock().unwrap().get_mut(from) {
+                peer.info.height = peer.info.height.max(block.index);
+            }
+        }
+
+        // Anything accepted is announced by the chain and relayed from there
+        let result = {
+            let mut bc = self.blockchain.lock().unwrap();
+            match message {
+                // Blocks whose parent we lack are left for the sync task, which fetches what is missing
+                Message::NewBlock { block } if !bc.block_tree.contains(&block.previous_hash) => return,
+                Message::NewBlock { block } => bc.receive_block(block),
+                Message::NewTransaction { transaction } => bc.create_transaction(transaction).map(|_| ()),
+                Message::NewEnhancedTransaction { transaction } => bc.add_enhanced_transaction(transaction).map(|_| ()),
+                _ => Ok(()),
+            }
+        };
+
+        if let Err(e) = result {
+            // Remember rejects too, so the same payload is not re-checked for every peer that sends it
+            self.seen.lock().unwrap().insert(key.clone());
+            println!("⚠️ Ignored {} from peer {}: {}", key, from, e);
+        }
+    }
+
+    // Send a newly accepted block or transaction to every peer, once
+    fn relay(&self, message: Message) {
+        let Some(key) = gossip_key(&message) else {
+            return;
+        };
+        if !self.seen.lock().unwrap().insert(key) {
+            return;
+        }
+        for peer in self.peers.lock().unwrap().values() {
+            let _ = peer.outbox.send(message.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hello(node_id: &str, min_version: u32, version: u32) -> Hello {
+        Hello {
+            version,
+            min_version,
+            node_id: node_id.to_string(),
+            listen_port: 3031,
+            genesis_hash: "genesis".to_string(),
+            height: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_frame_roundtrip() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        write_message(&mut client, &Message::Hello(hello("a", 1, 1))).await.unwrap();
+        write_message(&mut client, &Message::Disconnect { reason: "bye".to_string() }).await.unwrap();
+
+        assert!(matches!(read_message(&mut server).await, Ok(Message::Hello(h)) if h.node_id == "a"));
+        assert!(matches!(read_message(&mut server).await, Ok(Message::Disconnect { reason }) if reason == "bye"));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame_rejected() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(&(MAX_FRAME_LEN + 1).to_be_bytes()).await.unwrap();
+        assert!(read_message(&mut server).await.is_err());
+    }
+
+    #[test]
+    fn test_version_negotiation() {
+        let ours = hello("a", 1, 3);
+        assert_eq!(negotiate(&ours, &hello("b", 1, 2)), Ok(2));
+        assert_eq!(negotiate(&ours, &hello("b", 2, 5)), Ok(3));
+        assert!(negotiate(&ours, &hello("b", 4, 5)).is_err());
+        assert!(negotiate(&ours, &hello("a", 1, 3)).is_err());
+
+        let other_network = Hello { genesis_hash: "other".to_string(), ..hello("b", 1, 3) };
+        assert!(negotiate(&ours, &other_network).is_err());
+    }
+
+    #[test]
+    fn test_seen_set_evicts_oldest() {
+        let mut seen = SeenSet::default();
+        assert!(seen.insert("first".to_string()));
+        assert!(!seen.insert("first".to_string()));
+        for i in 0..SEEN_CAPACITY {
+            seen.insert(i.to_string());
+        }
+        assert!(!seen.contains("first"));
+        assert!(seen.contains(&(SEEN_CAPACITY - 1).to_string()));
+    }
+}
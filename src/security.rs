@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
+use crate::amount::Amount;
+use crate::crypto;
 
 /// Basic security error types
 #[derive(Debug)]
@@ -31,7 +33,7 @@ impl RateLimiter {
         let window_start = now - self.window_duration;
 
         // Get or create request history for this identifier
-        let request_times = self.requests.entry(identifier.to_string()).or_insert_with(Vec::new);
+        let request_times = self.requests.entry(identifier.to_string()).or_default();
 
         // Remove old requests outside the window
         request_times.retain(|&time| time > window_start);
@@ -70,8 +72,8 @@ impl RateLimiter {
 
 /// Transaction validator with basic security checks
 pub struct TransactionValidator {
-    min_amount: f64,
-    max_amount: f64,
+    min_amount: Amount,
+    max_amount: Amount,
     max_transaction_size: usize,
     blacklisted_addresses: std::collections::HashSet<String>,
     suspicious_patterns: Vec<String>,
@@ -80,8 +82,8 @@ pub struct TransactionValidator {
 impl TransactionValidator {
     pub fn new() -> Self {
         Self {
-            min_amount: 0.0001,
-            max_amount: 1_000_000.0,
+            min_amount: Amount::from_base_units(10_000), // 0.0001 L1
+            max_amount: Amount::from_l1(1_000_000),
             max_transaction_size: 1024, // 1KB for now
             blacklisted_addresses: std::collections::HashSet::new(),
             suspicious_patterns: vec![
@@ -92,9 +94,9 @@ impl TransactionValidator {
         }
     }
 
-    pub fn validate_transaction(&self, from: &str, to: &str, amount: f64) -> Result<(), SecurityError> {
+    pub fn validate_transaction(&self, from: &str, to: &str, amount: Amount) -> Result<(), SecurityError> {
         // Amount validation
-        if amount <= 0.0 {
+        if amount.is_zero() {
             return Err(SecurityError::InvalidTransaction("Amount must be positive".to_string()));
         }
 
@@ -132,7 +134,7 @@ impl TransactionValidator {
         Ok(())
     }
 
-    fn validate_address(&self, address: &str) -> Result<(), SecurityError> {
+    pub fn validate_address(&self, address: &str) -> Result<(), SecurityError> {
         if address.is_empty() {
             return Err(SecurityError::InvalidTransaction("Empty address".to_string()));
         }
@@ -142,16 +144,12 @@ impl TransactionValidator {
         }
 
         // Allow system addresses
-        if ["genesis", "mining_reward", "connection_reward", "system"].contains(&address) {
+        if crypto::is_system_address(address) {
             return Ok(());
         }
 
-        // Basic format validation for wallet addresses
-        if address.starts_with("wallet_") && address.len() < 8 {
-            return Err(SecurityError::InvalidTransaction("Invalid wallet address format".to_string()));
-        }
-
-        Ok(())
+        // Account addresses are derived from a public key and carry a checksum
+        crypto::validate_address(address).map_err(SecurityError::InvalidTransaction)
     }
 
     fn contains_suspicious_pattern(&self, address: &str) -> bool {
@@ -204,7 +202,7 @@ impl SecurityManager {
     }
 
     /// Comprehensive transaction security check
-    pub fn check_transaction_security(&mut self, from: &str, to: &str, amount: f64) -> Result<(), SecurityError> {
+    pub fn check_transaction_security(&mut self, from: &str, to: &str, amount: Amount) -> Result<(), SecurityError> {
         // Check if address is temporarily blocked due to failed attempts
         if let Some(&attempts) = self.failed_attempts.get(from) {
             if attempts >= self.max_failed_attempts {
@@ -295,6 +293,18 @@ impl SecurityManager {
                  address, reason.unwrap_or_else(|| "No reason provided".to_string()));
     }
 
+    /// Blacklisted addresses, for persisting across restarts
+    pub fn blacklisted_addresses(&self) -> Vec<String> {
+        self.validator.get_blacklisted_addresses().into_iter().cloned().collect()
+    }
+
+    /// Restore a previously persisted blacklist
+    pub fn restore_blacklist(&mut self, addresses: Vec<String>) {
+        for address in addresses {
+            self.validator.add_to_blacklist(address);
+        }
+    }
+
     /// Admin function to remove from blacklist
     pub fn admin_unblacklist(&mut self, address: &str) -> bool {
         let removed = self.validator.remove_from_blacklist(address);
@@ -326,7 +336,11 @@ pub struct RateLimitStats {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::thread;
+    use crate::crypto::KeyPair;
+
+    fn address() -> String {
+        KeyPair::generate().address()
+    }
 
     #[test]
     fn test_rate_limiting() {
@@ -346,48 +360,54 @@ mod tests {
     #[test]
     fn test_transaction_validation() {
         let validator = TransactionValidator::new();
+        let (alice, bob) = (address(), address());
         
         // Valid transaction
-        assert!(validator.validate_transaction("alice", "bob", 10.0).is_ok());
+        assert!(validator.validate_transaction(&alice, &bob, Amount::from_l1(10)).is_ok());
         
         // Invalid: self-transaction
-        assert!(validator.validate_transaction("alice", "alice", 10.0).is_err());
+        assert!(validator.validate_transaction(&alice, &alice, Amount::from_l1(10)).is_err());
         
-        // Invalid: negative amount
-        assert!(validator.validate_transaction("alice", "bob", -10.0).is_err());
+        // Invalid: zero amount
+        assert!(validator.validate_transaction(&alice, &bob, Amount::ZERO).is_err());
         
         // Invalid: too small amount
-        assert!(validator.validate_transaction("alice", "bob", 0.00001).is_err());
+        assert!(validator.validate_transaction(&alice, &bob, "0.00001".parse().unwrap()).is_err());
+
+        // Invalid: not a checksummed account address
+        assert!(validator.validate_transaction("wallet_alice", &bob, Amount::from_l1(10)).is_err());
     }
 
     #[test]
     fn test_blacklisting() {
         let mut validator = TransactionValidator::new();
+        let (badguy, alice) = (address(), address());
         
         // Should work initially
-        assert!(validator.validate_transaction("badguy", "alice", 10.0).is_ok());
+        assert!(validator.validate_transaction(&badguy, &alice, Amount::from_l1(10)).is_ok());
         
         // Add to blacklist
-        validator.add_to_blacklist("badguy".to_string());
+        validator.add_to_blacklist(badguy.clone());
         
         // Should fail now
-        assert!(validator.validate_transaction("badguy", "alice", 10.0).is_err());
-        assert!(validator.validate_transaction("alice", "badguy", 10.0).is_err());
+        assert!(validator.validate_transaction(&badguy, &alice, Amount::from_l1(10)).is_err());
+        assert!(validator.validate_transaction(&alice, &badguy, Amount::from_l1(10)).is_err());
     }
 
     #[test]
     fn test_security_manager() {
         let mut security = SecurityManager::new();
+        let (alice, bob, attacker) = (address(), address(), address());
         
         // Should work initially
-        assert!(security.check_transaction_security("alice", "bob", 10.0).is_ok());
+        assert!(security.check_transaction_security(&alice, &bob, Amount::from_l1(10)).is_ok());
         
         // Simulate multiple failures
         for _ in 0..6 {
-            security.record_failed_attempt("attacker");
+            security.record_failed_attempt(&attacker);
         }
         
         // Should be blocked now
-        assert!(security.check_transaction_security("attacker", "bob", 10.0).is_err());
+        assert!(security.check_transaction_security(&attacker, &bob, Amount::from_l1(10)).is_err());
     }
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
+use crate::amount::Amount;
 
 // Core Social Action
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,7 +11,7 @@ pub struct SocialAction {
     pub post_id: String,
     pub target_user: Option<String>, // For likes/comments - who gets the reward
     pub timestamp: u64,
-    pub reward_amount: f64,
+    pub reward_amount: Amount,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,13 +22,13 @@ pub enum SocialActionType {
 }
 
 // Main Social Mining System
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SocialMiningSystem {
     pub actions: Vec<SocialAction>,
     pub daily_limits: HashMap<String, DailyLimits>, // user_address -> limits
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DailyLimits {
     pub date: String,
     pub posts: u64,
@@ -62,7 +63,7 @@ pub struct SocialCommentRequest {
 pub struct SocialActionResponse {
     pub success: bool,
     pub message: String,
-    pub reward_amount: f64,
+    pub reward_amount: Amount,
     pub action_type: String,
 }
 
@@ -71,7 +72,7 @@ pub struct SocialStatsResponse {
     pub total_posts: u64,
     pub total_likes: u64,
     pub total_comments: u64,
-    pub total_rewards_distributed: f64,
+    pub total_rewards_distributed: Amount,
     pub top_earners: Vec<UserEarnings>,
 }
 
@@ -79,7 +80,7 @@ pub struct SocialStatsResponse {
 pub struct UserEarnings {
     pub user_address: String,
     pub username: Option<String>,
-    pub total_earnings: f64,
+    pub total_earnings: Amount,
     pub posts_count: u64,
 }
 
@@ -154,11 +155,11 @@ impl SocialMiningSystem {
     }
 
     // Calculate reward amount based on action type
-    pub fn calculate_reward(&self, action_type: &SocialActionType, max_supply: f64) -> f64 {
+    pub fn calculate_reward(&self, action_type: &SocialActionType, max_supply: Amount) -> Amount {
         match action_type {
-            SocialActionType::Post => 10.0, // Fixed 10 L1 for posts
-            SocialActionType::Like => max_supply / 100000.0, // 1/100000 of max supply
-            SocialActionType::Comment => max_supply / 100000.0, // 1/100000 of max supply
+            SocialActionType::Post => Amount::from_l1(10), // Fixed 10 L1 for posts
+            SocialActionType::Like => max_supply.mul_div(1, 100_000).unwrap_or(Amount::ZERO), // 1/100000 of max supply
+            SocialActionType::Comment => max_supply.mul_div(1, 100_000).unwrap_or(Amount::ZERO), // 1/100000 of max supply
         }
     }
 
@@ -175,11 +176,12 @@ impl SocialMiningSystem {
         let total_rewards_distributed = self.actions.iter().map(|a| a.reward_amount).sum();
 
         // Calculate top earners
-        let mut earnings: HashMap<String, f64> = HashMap::new();
+        let mut earnings: HashMap<String, Amount> = HashMap::new();
         let mut post_counts: HashMap<String, u64> = HashMap::new();
 
         for action in &self.actions {
-            *earnings.entry(action.user_address.clone()).or_insert(0.0) += action.reward_amount;
+            let earned = earnings.entry(action.user_address.clone()).or_default();
+            *earned = earned.saturating_add(action.reward_amount);
             if matches!(action.action_type, SocialActionType::Post) {
                 *post_counts.entry(action.user_address.clone()).or_insert(0) += 1;
             }
@@ -195,7 +197,7 @@ impl SocialMiningSystem {
             })
             .collect();
 
-        top_earners.sort_by(|a, b| b.total_earnings.partial_cmp(&a.total_earnings).unwrap());
+        top_earners.sort_by_key(|e| std::cmp::Reverse(e.total_earnings));
         top_earners.truncate(10); // Top 10
 
         SocialStatsResponse {
@@ -208,7 +210,7 @@ impl SocialMiningSystem {
     }
 
     // Get user's social earnings
-    pub fn get_user_earnings(&self, user_address: &str) -> f64 {
+    pub fn get_user_earnings(&self, user_address: &str) -> Amount {
         self.actions
             .iter()
             .filter(|action| action.user_address == user_address)
@@ -220,7 +222,7 @@ impl SocialMiningSystem {
     pub fn cleanup_old_actions(&mut self) {
         if self.actions.len() > 1000 {
             let keep_count = 1000;
-            self.actions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+            self.actions.sort_by_key(|a| std::cmp::Reverse(a.timestamp));
             self.actions.truncate(keep_count);
             println!("🧹 Social Mining: Cleaned up old actions, keeping latest {}", keep_count);
         }
@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::mempool::PoolEntry;
+use crate::multisig::MultisigRegistry;
+use crate::social_mining::SocialMiningSystem;
+use crate::token_launch::TokenLaunchSystem;
+use crate::{AddressLabel, Block, Transaction};
+
+const BLOCKS_FILE: &str = "blocks.log";
+const STATE_FILE: &str = "state.json";
+
+/// Snapshot of the node subsystems that are not derived from the chain itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistedState {
+    pub address_labels: HashMap<String, AddressLabel>,
+    pub address_to_username: HashMap<String, String>,
+    /// Transfers still waiting in the mempool, pending and queued
+    #[serde(default)]
+    pub mempool: Vec<PoolEntry>,
+    // Written by nodes before the unified mempool; read once so their transfers are not lost
+    #[serde(default, skip_serializing)]
+    pub pending_transactions: Vec<Transaction>,
+    #[serde(default, skip_serializing)]
+    pub queued_transactions: Vec<Transaction>,
+    pub token_system: TokenLaunchSystem,
+    pub social_mining: SocialMiningSystem,
+    pub blacklisted_addresses: Vec<String>,
+    #[serde(default)]
+    pub multisig: MultisigRegistry,
+}
+
+/// Durable on-disk storage: an append-only block log plus a state snapshot
+pub struct Storage {
+    data_dir: PathBuf,
+}
+
+impl Storage {
+    /// Open (and create if needed) the data directory
+    pub fn open(data_dir: impl AsRef<Path>) -> Result<Self, String> {
+        let data_dir = data_dir.as_ref().to_path_buf();
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| format!("Failed to create data directory {}: {}", data_dir.display(), e))?;
+        Ok(Self { data_dir })
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    fn blocks_path(&self) -> PathBuf {
+        self.data_dir.join(BLOCKS_FILE)
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.data_dir.join(STATE_FILE)
+    }
+
+    /// Append a mined block to the block log (one JSON document per line)
+    pub fn append_block(&self, block: &Block) -> Result<(), String> {
+        let mut line = serde_json::to_string(block)
+            .map_err(|e| format!("Failed to serialize block {}: {}", block.index, e))?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.blocks_path())
+            .map_err(|e| format!("Failed to open block log: {}", e))?;
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.sync_data())
+            .map_err(|e| format!("Failed to write block {}: {}", block.index, e))
+    }
+
+    /// Replace the whole block log, e.g. after a reorg (temp file + rename)
+    pub fn replace_blocks(&self, blocks: &[Block]) -> Result<(), String> {
+        let mut data = String::new();
+        for block in blocks {
+            data.push_str(&serde_json::to_string(block)
+                .map_err(|e| format!("Failed to serialize block {}: {}", block.index, e))?);
+            data.push('\n');
+        }
+        let tmp_path = self.data_dir.join(format!("{}.tmp", BLOCKS_FILE));
+
+        let mut file = fs::File::create(&tmp_path)
+            .map_err(|e| format!("Failed to create block log: {}", e))?;
+        file.write_all(data.as_bytes())
+            .and_then(|_| file.sync_data())
+            .map_err(|e| format!("Failed to write block log: {}", e))?;
+        fs::rename(&tmp_path, self.blocks_path())
+            .map_err(|e| format!("Failed to replace block log: {}", e))
+    }
+
+    /// Load every block from the block log, in the order they were appended. A partial final
+    /// record left by a crash mid-append is cut off, so the next append starts on a fresh line.
+    pub fn load_blocks(&self) -> Result<Vec<Block>, String> {
+        let path = self.blocks_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        // Read raw bytes: a torn write can end partway through a multi-byte character
+        let contents = fs::read(&path)
+            .map_err(|e| format!("Failed to read block log: {}", e))?;
+        let complete_len = contents.iter().rposition(|&byte| byte == b'\n').map_or(0, |pos| pos + 1);
+        if complete_len < contents.len() {
+            println!("⚠️ Dropping truncated block record at end of {}", path.display());
+            OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_len(complete_len as u64).and_then(|_| file.sync_data()))
+                .map_err(|e| format!("Failed to truncate block log: {}", e))?;
+        }
+
+        let mut blocks = Vec::new();
+        for (i, line) in contents[..complete_len].split(|&byte| byte == b'\n').enumerate() {
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            let block = serde_json::from_slice::<Block>(line)
+                .map_err(|e| format!("Corrupted block record on line {}: {}", i + 1, e))?;
+            blocks.push(block);
+        }
+
+        Ok(blocks)
+    }
+
+    /// Write the state snapshot atomically (temp file + rename)
+    pub fn save_state(&self, state: &PersistedState) -> Result<(), String> {
+        let data = serde_json::to_vec(state)
+            .map_err(|e| format!("Failed to serialize state: {}", e))?;
+        let tmp_path = self.data_dir.join(format!("{}.tmp", STATE_FILE));
+
+        let mut file = fs::File::create(&tmp_path)
+            .map_err(|e| format!("Failed to create state file: {}", e))?;
+        file.write_all(&data)
+            .and_then(|_| file.sync_data())
+            .map_err(|e| format!("Failed to write state file: {}", e))?;
+        fs::rename(&tmp_path, self.state_path())
+            .map_err(|e| format!("Failed to replace state file: {}", e))
+    }
+
+    pub fn load_state(&self) -> Result<Option<PersistedState>, String> {
+        let path = self.state_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data = fs::read(&path)
+            .map_err(|e| format!("Failed to read state file: {}", e))?;
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| format!("Corrupted state file: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_storage() -> Storage {
+        let dir = std::env::temp_dir().join(format!("layer1-storage-{}", uuid::Uuid::new_v4()));
+        Storage::open(dir).unwrap()
+    }
+
+    fn test_block(index: u64) -> Block {
+        let tx = Transaction {
+            from: "genesis".to_string(),
+            to: "alice".to_string(),
+            amount: "12.5".parse().unwrap(),
+            timestamp: 1,
+            signature: "genesis".to_string(),
+            public_key: None,
+            nonce: None,
+            ..Default::default()
+        };
+        Block::new(index, vec![tx], "0".to_string(), "miner".to_string())
+    }
+
+    #[test]
+    fn test_block_log_roundtrip() {
+        let storage = temp_storage();
+        assert!(storage.load_blocks().unwrap().is_empty());
+
+        storage.append_block(&test_block(0)).unwrap();
+        storage.append_block(&test_block(1)).unwrap();
+
+        let blocks = storage.load_blocks().unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].index, 1);
+        assert_eq!(blocks[1].hash, blocks[1].calculate_hash());
+
+        fs::remove_dir_all(storage.data_dir()).unwrap();
+    }
+
+    #[test]
+    fn test_replace_blocks_rewrites_log() {
+        let storage = temp_storage();
+        for index in 0..3 {
+            storage.append_block(&test_block(index)).unwrap();
+        }
+
+        storage.replace_blocks(&[test_block(0), test_block(7)]).unwrap();
+        let blocks = storage.load_blocks().unwrap();
+        assert_eq!(blocks.iter().map(|block| block.index).collect::<Vec<_>>(), vec![0, 7]);
+
+        fs::remove_dir_all(storage.data_dir()).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_tail_is_ignored() {
+        let storage = temp_storage();
+        storage.append_block(&test_block(0)).unwrap();
+
+        let mut file = OpenOptions::new().append(true).open(storage.blocks_path()).unwrap();
+        file.write_all(b"{\"index\":1,\"timest").unwrap();
+
+        assert_eq!(storage.load_blocks().unwrap().len(), 1);
+
+        // Torn partway through a multi-byte character
+        let mut file = OpenOptions::new().append(true).open(storage.blocks_path()).unwrap();
+        file.write_all(&"{\"miner\":\"ü".as_bytes()[..11]).unwrap();
+        assert_eq!(storage.load_blocks().unwrap().len(), 1);
+
+        // The partial record is gone, so the next block gets a line of its own
+        storage.append_block(&test_block(1)).unwrap();
+        let blocks = storage.load_blocks().unwrap();
+        assert_eq!(blocks.iter().map(|block| block.index).collect::<Vec<_>>(), vec![0, 1]);
+
+        fs::remove_dir_all(storage.data_dir()).unwrap();
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let storage = temp_storage();
+        assert!(storage.load_state().unwrap().is_none());
+
+        let state = PersistedState {
+            address_labels: HashMap::new(),
+            address_to_username: HashMap::from([("wallet_alice".to_string(), "alice".to_string())]),
+            mempool: Vec::new(),
+            pending_transactions: Vec::new(),
+            queued_transactions: Vec::new(),
+            token_system: TokenLaunchSystem::new(),
+            social_mining: SocialMiningSystem::new(),
+            blacklisted_addresses: vec!["badguy".to_string()],
+            multisig: MultisigRegistry::new(),
+        };
+        storage.save_state(&state).unwrap();
+
+        let loaded = storage.load_state().unwrap().unwrap();
+        assert_eq!(loaded.blacklisted_addresses, vec!["badguy".to_string()]);
+        assert_eq!(loaded.address_to_username.get("wallet_alice"), Some(&"alice".to_string()));
+
+        fs::remove_dir_all(storage.data_dir()).unwrap();
+    }
+}
@@ -0,0 +1,309 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time;
+
+use crate::p2p::{self, Message, Network, PeerInfo};
+use crate::validation;
+use crate::{Block, BlockHeader, Blockchain};
+
+/// How often an idle or caught-up node checks whether a peer has moved ahead
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Where the node is in catching up with its peers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    /// No peer that can serve the chain is connected
+    Idle,
+    /// Fetching and validating headers from the best peer
+    Headers,
+    /// Downloading the bodies of validated headers
+    Blocks,
+    /// At or above the height of every peer
+    Synced,
+}
+
+/// Body of GET /rpc/sync/status
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncStatus {
+    pub state: SyncState,
+    pub current_height: u64,
+    pub target_height: u64,
+    pub peers: usize,
+}
+
+/// Initial block download, then keeping up with peers that get ahead
+pub struct Syncer {
+    network: Arc<Network>,
+    blockchain: Arc<Mutex<Blockchain>>,
+    state: Mutex<SyncState>,
+    target_height: AtomicU64,
+}
+
+impl Syncer {
+    /// Start syncing in the background; the returned handle reports progress
+    pub fn start(network: Arc<Network>, blockchain: Arc<Mutex<Blockchain>>) -> Arc<Self> {
+        let syncer = Arc::new(Syncer {
+            network,
+            blockchain,
+            state: Mutex::new(SyncState::Idle),
+            target_height: AtomicU64::new(0),
+        });
+        tokio::spawn(syncer.clone().run());
+        syncer
+    }
+
+    pub fn status(&self) -> SyncStatus {
+        let current_height = self.blockchain.lock().unwrap().height();
+        SyncStatus {
+            state: *self.state.lock().unwrap(),
+            current_height,
+            target_height: self.target_height.load(Ordering::SeqCst).max(current_height),
+            peers: self.network.peers().len(),
+        }
+    }
+
+    async fn run(self: Arc<Self>) {
+        loop {
+            match self.step().await {
+                Ok(true) => continue, // More to fetch; no need to wait
+                Ok(false) => {}
+                Err(e) => println!("⚠️ Sync: {}", e),
+            }
+            time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    fn set_state(&self, state: SyncState, target_height: u64) {
+        *self.state.lock().unwrap() = state;
+        self.target_height.store(target_height, Ordering::SeqCst);
+    }
+
+    // One round against the highest peer: a batch of headers, then their blocks. True if blocks were imported.
+    async fn step(&self) -> Result<bool, String> {
+        let peers: Vec<PeerInfo> = self.network.peers().into_iter()
+            .filter(|peer| peer.version >= p2p::SYNC_VERSION)
+            .collect();
+        let current_height = self.blockchain.lock().unwrap().height();
+
+        let Some(best) = peers.iter().max_by_key(|peer| peer.height) else {
+            self.set_state(SyncState::Idle, current_height);
+            return Ok(false);
+        };
+        if best.height <= current_height {
+            self.set_state(SyncState::Synced, current_height);
+            return Ok(false);
+        }
+
+        self.set_state(SyncState::Headers, best.height);
+        let headers = self.fetch_headers(best).await?;
+        if headers.is_empty() {
+            // Everything the peer sent is already in our block tree
+            self.set_state(SyncState::Synced, current_height);
+            return Ok(false);
+        }
+
+        self.set_state(SyncState::Blocks, best.height);
+        let blocks = self.fetch_blocks(&peers, &headers).await?;
+
+        let mut bc = self.blockchain.lock().unwrap();
+        for block in blocks {
+            bc.import_block(block)?;
+        }
+        println!("🔄 Synced to height {} of {}", bc.height(), best.height);
+        Ok(true)
+    }
+
+    // Headers are checked against the block they fork from before any body is requested;
+    // only the ones we do not already have are returned
+    async fn fetch_headers(&self, peer: &PeerInfo) -> Result<Vec<BlockHeader>, String> {
+        let locator = self.blockchain.lock().unwrap().block_locator();
+        let response = self.network
+            .request(&peer.node_id, |request_id| Message::GetHeaders {
+                request_id,
+                locator,
+                limit: p2p::MAX_HEADERS_PER_REQUEST,
+            })
+            .await?;
+        let Message::Headers { headers, .. } = response else {
+            return Err(format!("Peer {} answered with something other than headers", peer.node_id));
+        };
+        let Some(first) = headers.first() else {
+            return Err(format!("Peer {} claims height {} but sent no headers", peer.node_id, peer.height));
+        };
+
+        let bc = self.blockchain.lock().unwrap();
+        let mut parents = bc.block_tree.ancestors(&first.previous_hash, validation::parents_needed(&bc.retarget));
+        if parents.is_empty() {
+            return Err(format!("Peer {} sent headers that do not connect to our chain", peer.node_id));
+        }
+        for header in &headers {
+            let block = header.to_block();
+            validation::validate_header(&parents, &block, &bc.retarget)
+                .map_err(|e| format!("Peer {} sent an invalid header: {}", peer.node_id, e))?;
+            parents.push(block);
+        }
+        Ok(headers.into_iter().filter(|header| !bc.block_tree.contains(&header.hash)).collect())
+    }
+
+    // Bodies are requested in chunks spread over every peer that has them, all in flight at once
+    async fn fetch_blocks(&self, peers: &[PeerInfo], headers: &[BlockHeader]) -> Result<Vec<Block>, String> {
+        let chunks: Vec<&[BlockHeader]> = headers.chunks(p2p::MAX_BLOCKS_PER_REQUEST).collect();
+        let mut downloads = JoinSet::new();
+        for (position, chunk) in chunks.iter().enumerate() {
+            let last_index = chunk[chunk.len() - 1].index;
+            let holders: Vec<String> = peers.iter()
+                .filter(|peer| peer.height >= last_index)
+                .map(|peer| peer.node_id.clone())
+                .collect();
+            let hashes: Vec<String> = chunk.iter().map(|header| header.hash.clone()).collect();
+            let network = self.network.clone();
+            downloads.spawn(async move { (position, download_chunk(&network, &holders, position, hashes).await) });
+        }
+
+        let mut bodies = vec![Vec::new(); chunks.len()];
+        while let Some(finished) = downloads.join_next().await {
+            let (position, blocks) = finished.map_err(|e| format!("Block download failed: {}", e))?;
+            bodies[position] = blocks?;
+        }
+        Ok(bodies.concat())
+    }
+}
+
+// Try each holder in turn, starting at a different one per chunk so the load is shared
+async fn download_chunk(network: &Network, holders: &[String], offset: usize, hashes: Vec<String>) -> Result<Vec<Block>, String> {
+    let mut last_error = "No connected peer has these blocks".to_string();
+    for attempt in 0..holders.len() {
+        let peer_id = &holders[(offset + attempt) % holders.len()];
+        let response = network
+            .request(peer_id, |request_id| Message::GetBlocks { request_id, hashes: hashes.clone() })
+            .await;
+        match response {
+            Ok(Message::Blocks { blocks, .. }) if blocks.iter().map(|block| &block.hash).eq(hashes.iter()) => {
+                return Ok(blocks);
+            }
+            Ok(_) => last_error = format!("Peer {} did not send the requested blocks", peer_id),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::Amount;
+    use crate::chain_spec::{ChainSpec, GenesisAllocation};
+    use crate::crypto::{KeyPair, TransferAuth};
+    use crate::enhanced_transaction::EnhancedTransaction;
+    use crate::fee_market;
+    use crate::p2p::NetworkConfig;
+    use crate::token_launch::{LaunchTokenRequest, SellTokenRequest};
+
+    // Wait for `syncer` to reach the tip of the chain it follows
+    async fn catch_up(syncer: &Syncer) {
+        let caught_up = time::timeout(Duration::from_secs(20), async {
+            while syncer.status().state != SyncState::Synced {
+                time::sleep(Duration::from_millis(50)).await;
+            }
+        });
+        assert!(caught_up.await.is_ok());
+    }
+
+    // A fresh node with `spec`, syncing from `source`
+    async fn follow(source: Arc<Mutex<Blockchain>>, spec: &ChainSpec) -> (Arc<Mutex<Blockchain>>, Arc<Syncer>) {
+        let seed = Network::start(source, NetworkConfig { listen: ([127, 0, 0, 1], 0).into(), peers: Vec::new() }).await.unwrap();
+        let fresh = Arc::new(Mutex::new(Blockchain::with_spec(spec)));
+        let config = NetworkConfig { listen: ([127, 0, 0, 1], 0).into(), peers: vec![format!("127.0.0.1:{}", seed.listen_port())] };
+        let network = Network::start(fresh.clone(), config).await.unwrap();
+        let syncer = Syncer::start(network, fresh.clone());
+        (fresh, syncer)
+    }
+
+    #[tokio::test]
+    async fn test_fresh_node_catches_up() {
+        // Peers only take signed transfers, so the source pays out from an account funded at genesis
+        let funder = KeyPair::generate();
+        let mut spec = ChainSpec::default();
+        spec.genesis.allocations.push(GenesisAllocation { address: funder.address(), amount: Amount::from_l1(1_000), lock: None });
+        let mut source = Blockchain::with_spec(&spec);
+        for i in 0..20 {
+            let tx = EnhancedTransaction::new(funder.address(), format!("user{}", i), Amount::from_l1(10), Amount::from_l1(1))
+                .with_nonce(i)
+                .sign(&funder);
+            source.add_enhanced_transaction(tx).unwrap();
+            source.mine_pending_transactions("miner".to_string());
+        }
+        let source = Arc::new(Mutex::new(source));
+        let (fresh, syncer) = follow(source.clone(), &spec).await;
+        catch_up(&syncer).await;
+
+        let (fresh, source) = (fresh.lock().unwrap(), source.lock().unwrap());
+        assert_eq!(fresh.height(), 20);
+        assert_eq!(fresh.chain.last().unwrap().hash, source.chain.last().unwrap().hash);
+        assert_eq!(fresh.get_balance("user7"), Amount::from_l1(10));
+    }
+
+    #[tokio::test]
+    async fn test_payouts_sync() {
+        // A signup bonus, then a token sale paid out of its pool
+        let seller = KeyPair::generate();
+        let mut source = Blockchain::new();
+        let payload = Blockchain::username_signing_payload(&seller.address(), None, "seller");
+        source.register_username("seller".to_string(), seller.public_key_hex(), seller.sign(payload.as_bytes())).unwrap();
+        source.create_system_transaction("genesis", "token_pool_VEST".to_string(), Amount::from_l1(100)).unwrap();
+        source.mine_pending_transactions("miner".to_string());
+
+        let launch = LaunchTokenRequest {
+            symbol: "VEST".to_string(),
+            name: "Vesting Token".to_string(),
+            description: String::new(),
+            creator: seller.address(),
+            total_supply: Amount::from_l1(1_000_000),
+            initial_price: 0.001,
+            initial_liquidity: Amount::from_l1(100),
+            image_url: None,
+            website: None,
+            twitter: None,
+            telegram: None,
+            fee: None,
+            auth: TransferAuth::default(),
+        };
+        source.token_system.launch_token(launch, Amount::from_l1(1_000)).unwrap();
+        source.token_system.token_holdings.get_mut(&seller.address()).unwrap().get_mut("VEST").unwrap().locks.clear();
+        let sale = SellTokenRequest {
+            token_symbol: "VEST".to_string(),
+            seller: seller.address(),
+            token_amount: Amount::from_l1(1_000),
+            max_slippage: 100.0,
+            fee: Some(fee_market::MIN_BASE_FEE),
+            auth: TransferAuth::default(),
+        };
+        let order = EnhancedTransaction::new(seller.address(), "token_pool_VEST".to_string(), Amount::ZERO, fee_market::MIN_BASE_FEE)
+            .with_nonce(0)
+            .with_message(sale.order())
+            .sign(&seller);
+        let auth = TransferAuth {
+            timestamp: Some(order.timestamp),
+            nonce: Some(0),
+            public_key: order.public_key,
+            signature: Some(order.signature),
+        };
+        let (trade, _) = source.sell_token(SellTokenRequest { auth, ..sale }).unwrap();
+        source.mine_pending_transactions("miner".to_string());
+        let balance = source.get_balance(&seller.address());
+        assert!(balance > Amount::from_l1(1_000) && trade.l1_amount > Amount::ZERO);
+
+        let source = Arc::new(Mutex::new(source));
+        let (fresh, syncer) = follow(source.clone(), &ChainSpec::default()).await;
+        catch_up(&syncer).await;
+
+        let (fresh, source) = (fresh.lock().unwrap(), source.lock().unwrap());
+        assert_eq!(fresh.height(), source.height());
+        assert_eq!(fresh.chain.last().unwrap().hash, source.chain.last().unwrap().hash);
+        assert_eq!(fresh.get_balance(&seller.address()), balance);
+    }
+}
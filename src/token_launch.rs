@@ -2,6 +2,12 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 use sha2::{Digest, Sha256};
+use crate::amount::Amount;
+use crate::crypto::TransferAuth;
+use crate::vesting::{self, BalanceLock, LockSchedule};
+
+/// Default period over which a token creator's 20% allocation vests linearly
+const CREATOR_VESTING_SECS: u64 = 180 * 24 * 60 * 60;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token {
@@ -9,8 +15,8 @@ pub struct Token {
     pub name: String,
     pub description: String,
     pub creator: String,
-    pub total_supply: f64,
-    pub circulating_supply: f64,
+    pub total_supply: Amount,
+    pub circulating_supply: Amount,
     pub created_at: u64,
     pub image_url: Option<String>,
     pub website: Option<String>,
@@ -18,9 +24,9 @@ pub struct Token {
     pub telegram: Option<String>,
     pub contract_address: String,
     pub is_verified: bool,
-    pub market_cap: f64,
-    pub price_in_l1: f64,
-    pub liquidity_pool: f64,
+    pub market_cap: Amount,
+    pub price_in_l1: f64, // Spot price, derived from the pool reserves
+    pub liquidity_pool: Amount,
     pub holders_count: usize,
     pub trade_count: u64,
     pub status: TokenStatus,
@@ -38,9 +44,12 @@ pub enum TokenStatus {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenHolding {
     pub token_symbol: String,
-    pub amount: f64,
+    pub amount: Amount,
     pub acquired_at: u64,
     pub average_price: f64,
+    /// Tokens in `amount` that cannot be sold yet, such as a creator's vesting allocation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locks: Vec<BalanceLock>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,9 +58,9 @@ pub struct TokenTrade {
     pub token_symbol: String,
     pub trader: String,
     pub trade_type: TradeType,
-    pub amount: f64,
+    pub amount: Amount,
     pub price: f64,
-    pub l1_amount: f64,
+    pub l1_amount: Amount,
     pub timestamp: u64,
     pub slippage: f64,
 }
@@ -65,21 +74,28 @@ pub enum TradeType {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LiquidityPool {
     pub token_symbol: String,
-    pub token_reserve: f64,
-    pub l1_reserve: f64,
-    pub k_constant: f64, // x * y = k for AMM
-    pub lp_token_supply: f64,
-    pub fee_rate: f64, // 0.3% default
+    pub token_reserve: Amount,
+    pub l1_reserve: Amount,
+    pub k_constant: u128, // x * y = k for AMM, in base units
+    pub lp_token_supply: Amount,
+    pub fee_bps: u128, // 30 = 0.3% default
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenLaunchSystem {
     pub tokens: HashMap<String, Token>,
     pub token_holdings: HashMap<String, HashMap<String, TokenHolding>>, // user -> token -> holding
     pub liquidity_pools: HashMap<String, LiquidityPool>,
     pub recent_trades: Vec<TokenTrade>,
-    pub launch_fee: f64,
-    pub min_liquidity: f64,
-    pub graduation_threshold: f64, // Market cap needed to graduate
+    pub launch_fee: Amount,
+    pub min_liquidity: Amount,
+    pub graduation_threshold: Amount, // Market cap needed to graduate
+    #[serde(default = "default_creator_vesting_secs")]
+    pub creator_vesting_secs: u64,
+}
+
+fn default_creator_vesting_secs() -> u64 {
+    CREATOR_VESTING_SECS
 }
 
 impl TokenLaunchSystem {
@@ -89,13 +105,14 @@ impl TokenLaunchSystem {
             token_holdings: HashMap::new(),
             liquidity_pools: HashMap::new(),
             recent_trades: Vec::new(),
-            launch_fee: 10.0, // 10 L1 to launch a token
-            min_liquidity: 100.0, // Minimum L1 liquidity needed
-            graduation_threshold: 50000.0, // 50k L1 market cap to graduate
+            launch_fee: Amount::from_l1(10), // 10 L1 to launch a token
+            min_liquidity: Amount::from_l1(100), // Minimum L1 liquidity needed
+            graduation_threshold: Amount::from_l1(50_000), // 50k L1 market cap to graduate
+            creator_vesting_secs: CREATOR_VESTING_SECS,
         }
     }
 
-    pub fn launch_token(&mut self, req: LaunchTokenRequest, creator_balance: f64) -> Result<Token, String> {
+    pub fn launch_token(&mut self, req: LaunchTokenRequest, creator_balance: Amount) -> Result<Token, String> {
         // Validate launch fee
         if creator_balance < self.launch_fee {
             return Err(format!("Insufficient balance. Need {} L1 to launch token", self.launch_fee));
@@ -115,10 +132,22 @@ impl TokenLaunchSystem {
             return Err("Token name must be 3-50 characters".to_string());
         }
 
-        if req.total_supply < 1000000.0 || req.total_supply > 1000000000000.0 {
+        if req.total_supply < Amount::from_l1(1_000_000) || req.total_supply > Amount::from_l1(1_000_000_000_000) {
             return Err("Total supply must be between 1M and 1T tokens".to_string());
         }
 
+        if req.initial_liquidity.is_zero() {
+            return Err("Initial liquidity must be positive".to_string());
+        }
+
+        // 80% of supply goes to the pool, the rest to the creator
+        let pool_tokens = req.total_supply.mul_div(80, 100)
+            .ok_or("Total supply too large")?;
+        let creator_tokens = req.total_supply.try_sub(pool_tokens)?;
+        let k_constant = pool_tokens.base_units()
+            .checked_mul(req.initial_liquidity.base_units())
+            .ok_or("Initial liquidity too large for this supply")?;
+
         // Generate contract address
         let contract_address = self.generate_contract_address(&req.symbol, &req.creator);
         let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
@@ -130,7 +159,7 @@ impl TokenLaunchSystem {
             description: req.description.clone(),
             creator: req.creator.clone(),
             total_supply: req.total_supply,
-            circulating_supply: 0.0,
+            circulating_supply: creator_tokens, // Everything outside the pool
             created_at: now,
             image_url: req.image_url.clone(),
             website: req.website.clone(),
@@ -138,9 +167,9 @@ impl TokenLaunchSystem {
             telegram: req.telegram.clone(),
             contract_address: contract_address.clone(),
             is_verified: false,
-            market_cap: 0.0,
+            market_cap: Amount::ZERO,
             price_in_l1: req.initial_price,
-            liquidity_pool: 0.0,
+            liquidity_pool: Amount::ZERO,
             holders_count: 0,
             trade_count: 0,
             status: TokenStatus::Launching,
@@ -149,16 +178,21 @@ impl TokenLaunchSystem {
         // Create initial liquidity pool
         let pool = LiquidityPool {
             token_symbol: req.symbol.clone(),
-            token_reserve: req.total_supply * 0.8, // 80% of supply goes to pool
+            token_reserve: pool_tokens,
             l1_reserve: req.initial_liquidity,
-            k_constant: (req.total_supply * 0.8) * req.initial_liquidity,
-            lp_token_supply: ((req.total_supply * 0.8) * req.initial_liquidity).sqrt(),
-            fee_rate: 0.003, // 0.3% fee
+            k_constant,
+            lp_token_supply: Amount::from_base_units(k_constant.isqrt()),
+            fee_bps: 30, // 0.3% fee
         };
 
-        // Give creator 20% of tokens
-        let creator_tokens = req.total_supply * 0.2;
-        self.add_token_holding(&req.creator, &req.symbol, creator_tokens, req.initial_price);
+        // Give creator 20% of tokens, vesting linearly so they cannot be dumped on the pool at launch
+        self.add_token_holding(&req.creator, &req.symbol, creator_tokens, req.initial_price)?;
+        if let Some(holding) = self.token_holdings.get_mut(&req.creator).and_then(|holdings| holdings.get_mut(&req.symbol)) {
+            holding.locks.push(BalanceLock {
+                amount: creator_tokens,
+                schedule: LockSchedule::vesting(now, self.creator_vesting_secs),
+            });
+        }
 
         // Store token and pool
         self.tokens.insert(req.symbol.clone(), token.clone());
@@ -166,12 +200,13 @@ impl TokenLaunchSystem {
 
         // Now we can use the cloned values in println!
         println!("🚀 Token launched: {} ({}) by {}", req.name, req.symbol, req.creator);
-        println!("📊 Initial supply: {}, Creator allocation: {}", req.total_supply, creator_tokens);
+        println!("📊 Initial supply: {}, Creator allocation: {} (vesting over {} days)",
+                 req.total_supply, creator_tokens, self.creator_vesting_secs / 86_400);
 
         Ok(token)
     }
 
-    pub fn buy_token(&mut self, req: BuyTokenRequest, buyer_balance: f64) -> Result<TokenTrade, String> {
+    pub fn buy_token(&mut self, req: BuyTokenRequest, buyer_balance: Amount) -> Result<TokenTrade, String> {
         // Check if token exists
         let token = self.tokens.get_mut(&req.token_symbol)
             .ok_or("Token not found")?;
@@ -187,31 +222,40 @@ impl TokenLaunchSystem {
 
         // Calculate tokens to receive using AMM formula
         // tokens_out = (token_reserve * l1_in) / (l1_reserve + l1_in)
-        let l1_after_fee = req.l1_amount * (1.0 - pool.fee_rate);
-        let tokens_out = (pool.token_reserve * l1_after_fee) / (pool.l1_reserve + l1_after_fee);
+        let l1_after_fee = req.l1_amount.mul_div(10_000 - pool.fee_bps, 10_000)
+            .ok_or("L1 amount too large")?;
+        let tokens_out = pool.token_reserve
+            .mul_div(l1_after_fee.base_units(), pool.l1_reserve.try_add(l1_after_fee)?.base_units())
+            .ok_or("Trade too large for this pool")?;
+        if tokens_out.is_zero() {
+            return Err("Trade too small to receive any tokens".to_string());
+        }
         
         // Check slippage
-        let expected_price = req.l1_amount / tokens_out;
-        let current_price = pool.l1_reserve / pool.token_reserve;
+        let expected_price = req.l1_amount.to_f64() / tokens_out.to_f64();
+        let current_price = pool.l1_reserve.to_f64() / pool.token_reserve.to_f64();
         let slippage = ((expected_price - current_price) / current_price * 100.0).abs();
         
         if slippage > req.max_slippage {
             return Err(format!("Slippage too high: {:.2}% (max: {:.2}%)", slippage, req.max_slippage));
         }
 
+        // Compute the new reserves before touching any state
+        let l1_reserve = pool.l1_reserve.try_add(req.l1_amount)?;
+        let token_reserve = pool.token_reserve.try_sub(tokens_out)?;
+        let circulating_supply = token.circulating_supply.try_add(tokens_out)?;
+
         // Update pool reserves
-        pool.l1_reserve += req.l1_amount;
-        pool.token_reserve -= tokens_out;
+        pool.l1_reserve = l1_reserve;
+        pool.token_reserve = token_reserve;
 
         // Update token stats
-        token.circulating_supply += tokens_out;
-        token.price_in_l1 = pool.l1_reserve / pool.token_reserve;
-        token.market_cap = token.circulating_supply * token.price_in_l1;
-        token.liquidity_pool = pool.l1_reserve;
+        token.circulating_supply = circulating_supply;
+        Self::reprice(token, pool);
         token.trade_count += 1;
 
         // Add tokens to buyer
-        self.add_token_holding(&req.buyer, &req.token_symbol, tokens_out, expected_price);
+        self.add_token_holding(&req.buyer, &req.token_symbol, tokens_out, expected_price)?;
 
         // Create trade record
         let trade = TokenTrade {
@@ -229,13 +273,14 @@ impl TokenLaunchSystem {
         self.recent_trades.push(trade.clone());
         self.update_token_status(&req.token_symbol);
 
-        println!("💰 Token purchase: {} bought {:.2} {} for {:.2} L1", 
+        println!("💰 Token purchase: {} bought {} {} for {} L1", 
                  req.buyer, tokens_out, req.token_symbol, req.l1_amount);
 
         Ok(trade)
     }
 
-    pub fn sell_token(&mut self, req: SellTokenRequest) -> Result<TokenTrade, String> {
+    /// Sell into the pool; `height` is the block the L1 payout will land in, for height-locked holdings
+    pub fn sell_token(&mut self, req: SellTokenRequest, height: u64) -> Result<TokenTrade, String> {
         // Check if user has enough tokens
         let user_holdings = self.token_holdings.get_mut(&req.seller)
             .ok_or("No token holdings found")?;
@@ -243,9 +288,12 @@ impl TokenLaunchSystem {
         let holding = user_holdings.get_mut(&req.token_symbol)
             .ok_or("You don't own this token")?;
 
-        if holding.amount < req.token_amount {
-            return Err(format!("Insufficient tokens. You have: {}, trying to sell: {}", 
-                             holding.amount, req.token_amount));
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        holding.locks.retain(|lock| !lock.locked(height, now).is_zero());
+        let locked = vesting::locked_total(&holding.locks, height, now);
+        if holding.amount.saturating_sub(locked) < req.token_amount {
+            return Err(format!("Insufficient tokens. You have: {} ({} still locked), trying to sell: {}", 
+                             holding.amount, locked, req.token_amount));
         }
 
         // Get token and pool
@@ -257,32 +305,41 @@ impl TokenLaunchSystem {
 
         // Calculate L1 to receive using AMM formula
         // l1_out = (l1_reserve * tokens_in) / (token_reserve + tokens_in)
-        let l1_out_before_fee = (pool.l1_reserve * req.token_amount) / (pool.token_reserve + req.token_amount);
-        let l1_out = l1_out_before_fee * (1.0 - pool.fee_rate);
+        let l1_out_before_fee = pool.l1_reserve
+            .mul_div(req.token_amount.base_units(), pool.token_reserve.try_add(req.token_amount)?.base_units())
+            .ok_or("Trade too large for this pool")?;
+        let l1_out = l1_out_before_fee.mul_div(10_000 - pool.fee_bps, 10_000)
+            .ok_or("Trade too large for this pool")?;
+        if l1_out.is_zero() {
+            return Err("Trade too small to receive any L1".to_string());
+        }
 
         // Check slippage
-        let expected_price = l1_out / req.token_amount;
-        let current_price = pool.l1_reserve / pool.token_reserve;
+        let expected_price = l1_out.to_f64() / req.token_amount.to_f64();
+        let current_price = pool.l1_reserve.to_f64() / pool.token_reserve.to_f64();
         let slippage = ((current_price - expected_price) / current_price * 100.0).abs();
 
         if slippage > req.max_slippage {
             return Err(format!("Slippage too high: {:.2}% (max: {:.2}%)", slippage, req.max_slippage));
         }
 
+        // Compute the new reserves before touching any state
+        let l1_reserve = pool.l1_reserve.try_sub(l1_out)?;
+        let token_reserve = pool.token_reserve.try_add(req.token_amount)?;
+        let circulating_supply = token.circulating_supply.try_sub(req.token_amount)?;
+
         // Update pool reserves
-        pool.l1_reserve -= l1_out;
-        pool.token_reserve += req.token_amount;
+        pool.l1_reserve = l1_reserve;
+        pool.token_reserve = token_reserve;
 
         // Update token stats
-        token.circulating_supply -= req.token_amount;
-        token.price_in_l1 = pool.l1_reserve / pool.token_reserve;
-        token.market_cap = token.circulating_supply * token.price_in_l1;
-        token.liquidity_pool = pool.l1_reserve;
+        token.circulating_supply = circulating_supply;
+        Self::reprice(token, pool);
         token.trade_count += 1;
 
-        // Remove tokens from seller
-        holding.amount -= req.token_amount;
-        if holding.amount <= 0.0 {
+        // Remove tokens from seller (checked against the holding above)
+        holding.amount = holding.amount.saturating_sub(req.token_amount);
+        if holding.amount.is_zero() {
             user_holdings.remove(&req.token_symbol);
         }
 
@@ -302,20 +359,29 @@ impl TokenLaunchSystem {
         self.recent_trades.push(trade.clone());
         self.update_token_status(&req.token_symbol);
 
-        println!("💸 Token sale: {} sold {:.2} {} for {:.2} L1", 
+        println!("💸 Token sale: {} sold {} {} for {} L1", 
                  req.seller, req.token_amount, req.token_symbol, l1_out);
 
         Ok(trade)
     }
 
-    fn add_token_holding(&mut self, user: &str, token_symbol: &str, amount: f64, price: f64) {
-        let user_holdings = self.token_holdings.entry(user.to_string()).or_insert_with(HashMap::new);
+    // Refresh price, market cap and liquidity after the pool reserves change
+    fn reprice(token: &mut Token, pool: &LiquidityPool) {
+        token.price_in_l1 = pool.l1_reserve.to_f64() / pool.token_reserve.to_f64();
+        token.market_cap = token.circulating_supply
+            .mul_div(pool.l1_reserve.base_units(), pool.token_reserve.base_units())
+            .unwrap_or(Amount::MAX);
+        token.liquidity_pool = pool.l1_reserve;
+    }
+
+    fn add_token_holding(&mut self, user: &str, token_symbol: &str, amount: Amount, price: f64) -> Result<(), String> {
+        let user_holdings = self.token_holdings.entry(user.to_string()).or_default();
         
         if let Some(existing) = user_holdings.get_mut(token_symbol) {
             // Update average price
-            let total_value = (existing.amount * existing.average_price) + (amount * price);
-            existing.amount += amount;
-            existing.average_price = total_value / existing.amount;
+            let total_value = (existing.amount.to_f64() * existing.average_price) + (amount.to_f64() * price);
+            existing.amount = existing.amount.try_add(amount)?;
+            existing.average_price = total_value / existing.amount.to_f64();
         } else {
             // New holding
             user_holdings.insert(token_symbol.to_string(), TokenHolding {
@@ -323,6 +389,7 @@ impl TokenLaunchSystem {
                 amount,
                 acquired_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
                 average_price: price,
+                locks: Vec::new(),
             });
             
             // Update holders count
@@ -330,21 +397,19 @@ impl TokenLaunchSystem {
                 token.holders_count += 1;
             }
         }
+        Ok(())
     }
 
     fn update_token_status(&mut self, token_symbol: &str) {
         if let Some(token) = self.tokens.get_mut(token_symbol) {
-            match token.status {
-                TokenStatus::Launching => {
-                    if token.market_cap >= self.graduation_threshold {
-                        token.status = TokenStatus::Graduated;
-                        println!("🎓 Token {} has graduated to full DEX!", token_symbol);
-                    } else if token.liquidity_pool >= self.min_liquidity {
-                        token.status = TokenStatus::Trading;
-                        println!("📈 Token {} is now actively trading!", token_symbol);
-                    }
-                },
-                _ => {}
+            if let TokenStatus::Launching = token.status {
+                if token.market_cap >= self.graduation_threshold {
+                    token.status = TokenStatus::Graduated;
+                    println!("🎓 Token {} has graduated to full DEX!", token_symbol);
+                } else if token.liquidity_pool >= self.min_liquidity {
+                    token.status = TokenStatus::Trading;
+                    println!("📈 Token {} is now actively trading!", token_symbol);
+                }
             }
         }
     }
@@ -370,7 +435,7 @@ impl TokenLaunchSystem {
 
     pub fn get_trending_tokens(&self, limit: usize) -> Vec<&Token> {
         let mut tokens: Vec<&Token> = self.tokens.values().collect();
-        tokens.sort_by(|a, b| b.trade_count.cmp(&a.trade_count));
+        tokens.sort_by_key(|t| std::cmp::Reverse(t.trade_count));
         tokens.into_iter().take(limit).collect()
     }
 
@@ -390,29 +455,48 @@ pub struct LaunchTokenRequest {
     pub name: String,
     pub description: String,
     pub creator: String,
-    pub total_supply: f64,
+    pub total_supply: Amount,
     pub initial_price: f64,
-    pub initial_liquidity: f64,
+    pub initial_liquidity: Amount,
     pub image_url: Option<String>,
     pub website: Option<String>,
     pub twitter: Option<String>,
     pub telegram: Option<String>,
+    #[serde(default)]
+    pub fee: Option<Amount>, // Network fee on the launch fee transfer; required, and signed with it
+    #[serde(flatten)]
+    pub auth: TransferAuth, // Creator's signature over the launch fee transfer
 }
 
 #[derive(Deserialize)]
 pub struct BuyTokenRequest {
     pub token_symbol: String,
     pub buyer: String,
-    pub l1_amount: f64,
+    pub l1_amount: Amount,
     pub max_slippage: f64, // percentage
+    #[serde(default)]
+    pub fee: Option<Amount>, // Network fee on the L1 payment; required, and signed with it
+    #[serde(flatten)]
+    pub auth: TransferAuth, // Buyer's signature over the L1 payment
 }
 
 #[derive(Deserialize)]
 pub struct SellTokenRequest {
     pub token_symbol: String,
     pub seller: String,
-    pub token_amount: f64,
+    pub token_amount: Amount,
     pub max_slippage: f64, // percentage
+    #[serde(default)]
+    pub fee: Option<Amount>, // Network fee on the sale order; required, and signed with it
+    #[serde(flatten)]
+    pub auth: TransferAuth, // Seller's signature over the sale order
+}
+
+impl SellTokenRequest {
+    /// Message of the seller's zero transfer into the pool, so their signature covers the sale terms
+    pub fn order(&self) -> String {
+        format!("layer1-sell:{}:{}:{}", self.token_symbol, self.token_amount, self.max_slippage)
+    }
 }
 
 // Response structures
@@ -441,5 +525,123 @@ pub struct TokenStatsResponse {
 pub struct PricePoint {
     pub timestamp: u64,
     pub price: f64,
-    pub volume: f64,
-}
\ No newline at end of file
+    pub volume: Amount,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+    use crate::enhanced_transaction::EnhancedTransaction;
+    use crate::fee_market;
+
+    fn launch_request(creator: &str) -> LaunchTokenRequest {
+        LaunchTokenRequest {
+            symbol: "VEST".to_string(),
+            name: "Vesting Token".to_string(),
+            description: String::new(),
+            creator: creator.to_string(),
+            total_supply: Amount::from_l1(1_000_000),
+            initial_price: 0.001,
+            initial_liquidity: Amount::from_l1(100),
+            image_url: None,
+            website: None,
+            twitter: None,
+            telegram: None,
+            fee: None,
+            auth: TransferAuth::default(),
+        }
+    }
+
+    fn sell(amount: u64) -> SellTokenRequest {
+        SellTokenRequest {
+            token_symbol: "VEST".to_string(),
+            seller: "creator".to_string(),
+            token_amount: Amount::from_l1(amount),
+            max_slippage: 100.0,
+            fee: None,
+            auth: TransferAuth::default(),
+        }
+    }
+
+    // A sale order signed by `keys` as its seller
+    fn signed_sell(keys: &KeyPair, amount: u64, nonce: u64) -> SellTokenRequest {
+        let req = SellTokenRequest { seller: keys.address(), fee: Some(fee_market::MIN_BASE_FEE), ..sell(amount) };
+        let order = EnhancedTransaction::new(keys.address(), "token_pool_VEST".to_string(), Amount::ZERO, fee_market::MIN_BASE_FEE)
+            .with_nonce(nonce)
+            .with_message(req.order())
+            .sign(keys);
+        SellTokenRequest {
+            auth: TransferAuth {
+                timestamp: Some(order.timestamp),
+                nonce: Some(nonce),
+                public_key: order.public_key,
+                signature: Some(order.signature),
+            },
+            ..req
+        }
+    }
+
+    #[test]
+    fn test_creator_allocation_vests() {
+        let mut system = TokenLaunchSystem::new();
+        system.launch_token(launch_request("creator"), Amount::from_l1(1_000)).unwrap();
+        let holding = &system.get_user_holdings("creator").unwrap()["VEST"];
+        assert_eq!(holding.amount, Amount::from_l1(200_000));
+        assert_eq!(holding.locks.len(), 1);
+        assert!(system.sell_token(sell(1_000), 1).is_err());
+
+        // Once the schedule has run its course the whole allocation is sellable
+        system.token_holdings.get_mut("creator").unwrap().get_mut("VEST").unwrap().locks[0].schedule = LockSchedule::vesting(0, 1);
+        assert!(system.sell_token(sell(1_000), 1).is_ok());
+        assert!(system.get_user_holdings("creator").unwrap()["VEST"].locks.is_empty());
+    }
+
+    #[test]
+    fn test_failed_payout_leaves_tokens_with_seller() {
+        let mut blockchain = crate::Blockchain::new();
+        let creator = KeyPair::generate();
+        let system = &mut blockchain.token_system;
+        system.launch_token(launch_request(&creator.address()), Amount::from_l1(1_000)).unwrap();
+        system.token_holdings.get_mut(&creator.address()).unwrap().get_mut("VEST").unwrap().locks.clear();
+
+        // The pool's L1 never reached the chain, so the payout cannot be admitted
+        let err = blockchain.sell_token(signed_sell(&creator, 1_000, 0)).unwrap_err();
+        assert!(err.starts_with("Failed to process L1 payout"), "{}", err);
+        assert_eq!(blockchain.token_system.get_user_holdings(&creator.address()).unwrap()["VEST"].amount, Amount::from_l1(200_000));
+        assert!(blockchain.mempool.pending().is_empty());
+    }
+
+    #[test]
+    fn test_sales_need_the_sellers_signature() {
+        let mut blockchain = crate::Blockchain::new();
+        let creator = KeyPair::generate();
+        blockchain.create_system_transaction("genesis", creator.address(), Amount::from_l1(10)).unwrap();
+        blockchain.create_system_transaction("genesis", "token_pool_VEST".to_string(), Amount::from_l1(100)).unwrap();
+        blockchain.mine_pending_transactions("miner".to_string());
+        let system = &mut blockchain.token_system;
+        system.launch_token(launch_request(&creator.address()), Amount::from_l1(1_000)).unwrap();
+        system.token_holdings.get_mut(&creator.address()).unwrap().get_mut("VEST").unwrap().locks.clear();
+        let holding = |blockchain: &crate::Blockchain| blockchain.token_system.get_user_holdings(&creator.address()).unwrap()["VEST"].amount;
+
+        // Nobody else can sell the creator's tokens, signed or not
+        let unsigned = SellTokenRequest { seller: creator.address(), fee: Some(fee_market::MIN_BASE_FEE), ..sell(1_000) };
+        assert!(blockchain.sell_token(unsigned).is_err());
+        let forged = SellTokenRequest { seller: creator.address(), ..signed_sell(&KeyPair::generate(), 1_000, 0) };
+        assert!(blockchain.sell_token(forged).is_err());
+        // The signature covers the sale terms
+        let altered = SellTokenRequest { token_amount: Amount::from_l1(100_000), ..signed_sell(&creator, 1_000, 0) };
+        assert!(blockchain.sell_token(altered).is_err());
+        assert_eq!(holding(&blockchain), Amount::from_l1(200_000));
+
+        let (trade, _) = blockchain.sell_token(signed_sell(&creator, 1_000, 0)).unwrap();
+        assert_eq!(holding(&blockchain), Amount::from_l1(199_000));
+        // The order used up its nonce, so it cannot be replayed
+        assert!(blockchain.sell_token(signed_sell(&creator, 1_000, 0)).is_err());
+
+        blockchain.mine_pending_transactions("miner".to_string());
+        let expected = Amount::from_l1(10).saturating_sub(fee_market::MIN_BASE_FEE).saturating_add(trade.l1_amount);
+        assert_eq!(blockchain.get_balance(&creator.address()), expected);
+        assert_eq!(blockchain.ledger.nonce(&creator.address()), 1);
+    }
+}
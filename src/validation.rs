@@ -0,0 +1,538 @@
+use serde::Serialize;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::amount::Amount;
+use crate::crypto;
+use crate::difficulty::{self, RetargetParams};
+use crate::fee_market;
+use crate::Block;
+
+/// Recent blocks whose median timestamp a new block must be later than
+const MEDIAN_TIME_BLOCKS: usize = 11;
+
+/// How far ahead of this node's clock a block's timestamp may run, in seconds
+const MAX_FUTURE_BLOCK_TIME: u64 = 2 * 60 * 60;
+
+/// First invalid block found while walking the chain, and why it failed
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainValidationError {
+    pub block_index: u64,
+    pub reason: String,
+}
+
+impl ChainValidationError {
+    fn new(block_index: u64, reason: impl Into<String>) -> Self {
+        Self {
+            block_index,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ChainValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block {}: {}", self.block_index, self.reason)
+    }
+}
+
+/// Check hash integrity, linkage, difficulty and proof-of-work for every block in order
+pub fn validate_blocks(blocks: &[Block], params: &RetargetParams) -> Result<(), ChainValidationError> {
+    let genesis = blocks.first()
+        .ok_or_else(|| ChainValidationError::new(0, "Chain is empty"))?;
+
+    if genesis.index != 0 {
+        return Err(ChainValidationError::new(genesis.index, "First block is not a genesis block"));
+    }
+    if genesis.previous_hash != "0" {
+        return Err(ChainValidationError::new(0, "Genesis block has a previous hash"));
+    }
+    if genesis.hash != genesis.calculate_hash() {
+        return Err(ChainValidationError::new(0, "Stored hash does not match block contents"));
+    }
+    validate_merkle_root(genesis)?;
+    if genesis.difficulty != 0 && genesis.difficulty != params.initial_difficulty {
+        return Err(ChainValidationError::new(0, "Genesis block has the wrong difficulty"));
+    }
+
+    for i in 1..blocks.len() {
+        validate_next_block(&blocks[..i], &blocks[i], params)?;
+    }
+
+    Ok(())
+}
+
+/// Check that `block` validly extends `parents`, which must end at the block it builds on
+pub fn validate_next_block(parents: &[Block], block: &Block, params: &RetargetParams) -> Result<(), ChainValidationError> {
+    validate_header(parents, block, params)?;
+    if block.hash != block.calculate_hash() {
+        return Err(ChainValidationError::new(
+            block.index,
+            "Stored hash does not match block contents",
+        ));
+    }
+    validate_merkle_root(block)?;
+    validate_base_fee(parents, block)
+}
+
+/// Transaction rules for a block received from a peer. Every transfer must carry its sender's
+/// signature, except one coinbase paying the miner as the block's last transaction. Payouts from
+/// the other system addresses must carry the signature of `payout_authority`, the chain spec's
+/// payout key.
+pub fn validate_peer_transactions(block: &Block, payout_authority: &str) -> Result<(), ChainValidationError> {
+    let last = block.transactions.len().saturating_sub(1);
+    for (i, tx) in block.transactions.iter().enumerate() {
+        if tx.from == "mining_reward" {
+            if i != last {
+                return Err(ChainValidationError::new(block.index, "Coinbase must be the block's only reward and its last transaction"));
+            }
+        } else if crypto::is_system_address(&tx.from) {
+            tx.verify_payout(payout_authority).map_err(|e| ChainValidationError::new(
+                block.index,
+                format!("Transaction {} pays out from {} without the payout authority's signature: {}", i, tx.from, e),
+            ))?;
+        } else {
+            tx.verify_signature()
+                .map_err(|e| ChainValidationError::new(block.index, format!("Transaction {}: {}", i, e)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Coinbase rules for a block received from a peer: it records `subsidy`, the schedule's subsidy
+/// for its height, as its reward, and carries exactly one coinbase paying at most that subsidy
+/// plus the miner's share of its fees
+pub fn validate_coinbase(block: &Block, subsidy: Amount) -> Result<(), ChainValidationError> {
+    let mut coinbases = block.transactions.iter().filter(|tx| tx.from == "mining_reward");
+    let (Some(coinbase), None) = (coinbases.next(), coinbases.next()) else {
+        return Err(ChainValidationError::new(block.index, "Block must carry exactly one coinbase"));
+    };
+    if block.reward != subsidy {
+        return Err(ChainValidationError::new(
+            block.index,
+            format!("Expected reward {} but block records {}", subsidy, block.reward),
+        ));
+    }
+
+    let limit = block.transactions.iter()
+        .filter(|tx| tx.from != "mining_reward")
+        .map(|tx| fee_market::split_fee(tx.fee.unwrap_or_default(), block.base_fee).1)
+        .fold(subsidy, Amount::saturating_add);
+    if coinbase.amount > limit {
+        return Err(ChainValidationError::new(
+            block.index,
+            format!("Coinbase pays {} but the subsidy and fees allow at most {}", coinbase.amount, limit),
+        ));
+    }
+    Ok(())
+}
+
+/// Parent blocks `validate_header` needs to see for its difficulty and timestamp rules
+pub fn parents_needed(params: &RetargetParams) -> usize {
+    (params.retarget_interval.max(2) as usize).max(MEDIAN_TIME_BLOCKS)
+}
+
+/// Median timestamp of the last MEDIAN_TIME_BLOCKS of `parents`, which the next block must be later than
+pub fn median_time_past(parents: &[Block]) -> u64 {
+    let mut times: Vec<u64> = parents.iter().rev().take(MEDIAN_TIME_BLOCKS).map(|block| block.timestamp).collect();
+    times.sort_unstable();
+    times.get(times.len() / 2).copied().unwrap_or(0)
+}
+
+/// The checks a header alone allows: linkage, timestamp, hash, difficulty and proof-of-work
+pub fn validate_header(parents: &[Block], block: &Block, params: &RetargetParams) -> Result<(), ChainValidationError> {
+    let previous = parents.last()
+        .ok_or_else(|| ChainValidationError::new(block.index, "Block has no parent"))?;
+
+    if block.index != previous.index + 1 {
+        return Err(ChainValidationError::new(
+            block.index,
+            format!("Expected index {}", previous.index + 1),
+        ));
+    }
+    if block.previous_hash != previous.hash {
+        return Err(ChainValidationError::new(
+            block.index,
+            "Previous hash does not match the prior block",
+        ));
+    }
+    validate_timestamp(parents, block)?;
+    // Without a Merkle root the hash covers the transactions, so only the full block can be checked
+    if !block.merkle_root.is_empty() && block.hash != block.calculate_hash() {
+        return Err(ChainValidationError::new(
+            block.index,
+            "Stored hash does not match block contents",
+        ));
+    }
+    validate_difficulty(parents, block, params)?;
+    if !block.meets_difficulty() {
+        return Err(ChainValidationError::new(
+            block.index,
+            format!("Hash does not meet difficulty {}", block.difficulty),
+        ));
+    }
+    Ok(())
+}
+
+// Retargeting trusts block timestamps, so they may neither fall back behind recent blocks nor
+// run far ahead of the clock. Blocks from before difficulty was recorded were mined without the
+// first rule.
+fn validate_timestamp(parents: &[Block], block: &Block) -> Result<(), ChainValidationError> {
+    let median = median_time_past(parents);
+    if block.difficulty != 0 && block.timestamp <= median {
+        return Err(ChainValidationError::new(
+            block.index,
+            format!("Timestamp {} is not after the median time of recent blocks ({})", block.timestamp, median),
+        ));
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    if block.timestamp > now.saturating_add(MAX_FUTURE_BLOCK_TIME) {
+        return Err(ChainValidationError::new(
+            block.index,
+            format!("Timestamp {} is more than {} seconds ahead of this node's clock", block.timestamp, MAX_FUTURE_BLOCK_TIME),
+        ));
+    }
+    Ok(())
+}
+
+// Each block must record the difficulty the retarget rule gives for its parent chain
+fn validate_difficulty(parents: &[Block], block: &Block, params: &RetargetParams) -> Result<(), ChainValidationError> {
+    let previous = &parents[parents.len() - 1];
+    if block.difficulty == 0 {
+        // Only a chain that has never recorded difficulty may keep omitting it
+        if previous.difficulty != 0 {
+            return Err(ChainValidationError::new(block.index, "Block does not record its difficulty"));
+        }
+        return Ok(());
+    }
+
+    let expected = difficulty::next_difficulty(parents, params);
+    if block.difficulty != expected {
+        return Err(ChainValidationError::new(
+            block.index,
+            format!("Expected difficulty {} but block records {}", expected, block.difficulty),
+        ));
+    }
+    Ok(())
+}
+
+// The base fee depends on how full the parent was, so unlike difficulty it needs the parent's body
+fn validate_base_fee(parents: &[Block], block: &Block) -> Result<(), ChainValidationError> {
+    let previous = &parents[parents.len() - 1];
+    if block.base_fee.is_zero() {
+        // Only a chain that has never recorded a base fee may keep omitting it
+        if !previous.base_fee.is_zero() {
+            return Err(ChainValidationError::new(block.index, "Block does not record its base fee"));
+        }
+        return Ok(());
+    }
+
+    let expected = fee_market::next_base_fee(previous);
+    if block.base_fee != expected {
+        return Err(ChainValidationError::new(
+            block.index,
+            format!("Expected base fee {} but block records {}", expected, block.base_fee),
+        ));
+    }
+    // Every signed transfer pays at least the base fee; protocol payouts carry none
+    for (i, tx) in block.transactions.iter().enumerate() {
+        if !crypto::is_system_address(&tx.from) && tx.fee.is_none_or(|fee| fee < block.base_fee) {
+            return Err(ChainValidationError::new(
+                block.index,
+                format!("Transaction {} pays less than the base fee {}", i, block.base_fee),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// The header commits to the transactions only through the Merkle root
+fn validate_merkle_root(block: &Block) -> Result<(), ChainValidationError> {
+    if !block.merkle_root.is_empty() && block.merkle_root != Block::compute_merkle_root(&block.transactions) {
+        return Err(ChainValidationError::new(
+            block.index,
+            "Merkle root does not match block transactions",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain_spec::{ChainSpec, GenesisAllocation};
+    use crate::crypto::KeyPair;
+    use crate::enhanced_transaction::EnhancedTransaction;
+    use crate::{Blockchain, Transaction};
+
+    fn mined_chain() -> Blockchain {
+        let mut blockchain = Blockchain::new();
+        for i in 0..3 {
+            blockchain.create_system_transaction("genesis", format!("user{}", i), Amount::from_l1(10)).unwrap();
+            blockchain.mine_pending_transactions("miner".to_string());
+        }
+        blockchain
+    }
+
+    #[test]
+    fn test_valid_chain() {
+        let blockchain = mined_chain();
+        assert!(validate_blocks(&blockchain.chain, &blockchain.retarget).is_ok());
+    }
+
+    #[test]
+    fn test_header_fields_cannot_run_together() {
+        let header = |reward: u64, difficulty: u64| {
+            let mut block = Block::new(1, Vec::new(), "0".to_string(), "miner".to_string());
+            block.timestamp = 1;
+            block.reward = Amount::from_l1(reward);
+            block.with_difficulty(difficulty)
+        };
+        // "1" + "23" and "12" + "3" would otherwise hash the same
+        assert_ne!(header(1, 23).hash, header(12, 3).hash);
+    }
+
+    #[test]
+    fn test_tampered_transaction_detected() {
+        let mut blockchain = mined_chain();
+        blockchain.chain[2].transactions[0].amount = Amount::from_l1(1_000_000);
+
+        let err = validate_blocks(&blockchain.chain, &blockchain.retarget).unwrap_err();
+        assert_eq!(err.block_index, 2);
+        assert_eq!(err.reason, "Merkle root does not match block transactions");
+    }
+
+    #[test]
+    fn test_blocks_without_merkle_root_still_validate() {
+        let mut blockchain = mined_chain();
+        let block = &mut blockchain.chain[0];
+        block.merkle_root.clear();
+        block.hash = block.calculate_hash();
+        assert!(validate_blocks(&blockchain.chain[..1], &blockchain.retarget).is_ok());
+    }
+
+    #[test]
+    fn test_headers_validate_without_bodies() {
+        let blockchain = mined_chain();
+        let headers: Vec<Block> = blockchain.chain.iter().map(|block| block.header().to_block()).collect();
+        for i in 1..headers.len() {
+            assert!(validate_header(&headers[..i], &headers[i], &blockchain.retarget).is_ok());
+        }
+
+        let mut forged = headers[2].clone();
+        forged.nonce += 1;
+        assert!(validate_header(&headers[..2], &forged, &blockchain.retarget).is_err());
+    }
+
+    #[test]
+    fn test_broken_link_detected() {
+        let mut blockchain = mined_chain();
+        blockchain.chain[3].previous_hash = "00deadbeef".to_string();
+
+        let err = validate_blocks(&blockchain.chain, &blockchain.retarget).unwrap_err();
+        assert_eq!(err.block_index, 3);
+    }
+
+    #[test]
+    fn test_insufficient_work_detected() {
+        let mut blockchain = mined_chain();
+        let block = &mut blockchain.chain[1];
+        while block.meets_difficulty() {
+            block.nonce += 1;
+            block.hash = block.calculate_hash();
+        }
+
+        let err = validate_blocks(&blockchain.chain, &blockchain.retarget).unwrap_err();
+        assert_eq!(err.block_index, 1);
+    }
+
+    #[test]
+    fn test_wrong_difficulty_detected() {
+        let mut blockchain = mined_chain();
+        let mut easier = blockchain.chain[1].clone().with_difficulty(1);
+        easier.mine_block();
+        blockchain.chain[1] = easier;
+
+        let err = validate_blocks(&blockchain.chain[..2], &blockchain.retarget).unwrap_err();
+        assert_eq!(err.block_index, 1);
+        assert!(err.reason.starts_with("Expected difficulty"));
+    }
+
+    #[test]
+    fn test_timestamps_must_move_forward_and_not_run_ahead() {
+        let blockchain = mined_chain();
+        let parents = &blockchain.chain[..3];
+        let retimed = |timestamp: u64| {
+            let mut block = blockchain.chain[3].clone();
+            block.timestamp = timestamp;
+            let mut block = block.with_difficulty(blockchain.chain[3].difficulty);
+            block.mine_block();
+            block
+        };
+
+        // Back at the median of recent blocks: a time warp
+        let err = validate_next_block(parents, &retimed(median_time_past(parents)), &blockchain.retarget).unwrap_err();
+        assert!(err.reason.starts_with("Timestamp"), "{}", err.reason);
+        assert!(err.reason.contains("median time"));
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let err = validate_next_block(parents, &retimed(now + MAX_FUTURE_BLOCK_TIME + 60), &blockchain.retarget).unwrap_err();
+        assert!(err.reason.contains("ahead of this node's clock"));
+
+        assert!(validate_next_block(parents, &retimed(median_time_past(parents) + 1), &blockchain.retarget).is_ok());
+    }
+
+    #[test]
+    fn test_peer_blocks_need_signed_payouts() {
+        let mut peer = Blockchain::new();
+        let payout = |from: &str| Transaction {
+            from: from.to_string(),
+            to: "thief".to_string(),
+            amount: Amount::from_l1(5),
+            ..Default::default()
+        };
+        let signed_by = |keys: &KeyPair, from: &str| {
+            let mut tx = Transaction { public_key: Some(keys.public_key_hex()), nonce: Some(1), ..payout(from) };
+            tx.signature = keys.sign(tx.signing_payload().as_bytes());
+            tx
+        };
+        let (genesis_hash, difficulty) = (peer.chain[0].hash.clone(), peer.current_difficulty());
+        let forged = |transactions: Vec<Transaction>| {
+            let mut block = Block::new(1, transactions, genesis_hash.clone(), "thief".to_string())
+                .with_difficulty(difficulty);
+            block.mine_block();
+            block
+        };
+        let err = peer.import_block(forged(vec![payout("token_pool_X")])).unwrap_err();
+        assert!(err.contains("pays out from token_pool_X without the payout authority's signature"), "{}", err);
+        let err = peer.import_block(forged(vec![signed_by(&KeyPair::generate(), "genesis")])).unwrap_err();
+        assert!(err.contains("pays out from genesis without the payout authority's signature"), "{}", err);
+        let err = peer.import_block(forged(vec![payout("mining_reward"), payout("mining_reward")])).unwrap_err();
+        assert!(err.contains("Coinbase"), "{}", err);
+        assert_eq!(peer.height(), 0);
+
+        // Payouts the authority signed are checked and accepted like any signed transfer
+        let source = mined_chain();
+        peer.import_block(source.chain[1].clone()).unwrap();
+        assert_eq!(peer.height(), 1);
+    }
+
+    #[test]
+    fn test_inflated_coinbase_rejected() {
+        let funder = KeyPair::generate();
+        let mut spec = ChainSpec::default();
+        spec.genesis.allocations.push(GenesisAllocation { address: funder.address(), amount: Amount::from_l1(100), lock: None });
+        let mut source = Blockchain::with_spec(&spec);
+        let tx = EnhancedTransaction::new(funder.address(), "bob".to_string(), Amount::from_l1(10), Amount::from_l1(1))
+            .sign(&funder);
+        source.add_enhanced_transaction(tx).unwrap();
+        source.mine_pending_transactions("miner".to_string());
+
+        // Rebuild the mined block with changes, as a dishonest miner would before solving it
+        let mined = source.chain[1].clone();
+        let tampered = |change: &dyn Fn(&mut Block)| {
+            let mut block = Block::new(mined.index, mined.transactions.clone(), mined.previous_hash.clone(), mined.miner.clone());
+            block.timestamp = mined.timestamp;
+            block.reward = mined.reward;
+            block.base_fee = mined.base_fee;
+            change(&mut block);
+            block.merkle_root = Block::compute_merkle_root(&block.transactions);
+            let mut block = block.with_difficulty(mined.difficulty);
+            block.mine_block();
+            block
+        };
+
+        let mut peer = Blockchain::with_spec(&spec);
+        // The burned part of the base fee is not the miner's, so claiming the whole fee is too much
+        let err = peer.import_block(tampered(&|block| block.transactions[1].amount = block.reward.saturating_add(Amount::from_l1(1)))).unwrap_err();
+        assert!(err.contains("Coinbase pays"), "{}", err);
+        let err = peer.import_block(tampered(&|block| block.reward = Amount::from_l1(50))).unwrap_err();
+        assert!(err.contains("Expected reward"), "{}", err);
+        let err = peer.import_block(tampered(&|block| { block.transactions.pop(); })).unwrap_err();
+        assert!(err.contains("exactly one coinbase"), "{}", err);
+
+        // The subsidy plus the miner's share of the fee is exactly what the honest block pays
+        peer.import_block(mined).unwrap();
+        assert_eq!(peer.get_balance("miner"), source.get_balance("miner"));
+    }
+
+    #[test]
+    fn test_replayed_transfer_rejected() {
+        let funder = KeyPair::generate();
+        let mut spec = ChainSpec::default();
+        spec.genesis.allocations.push(GenesisAllocation { address: funder.address(), amount: Amount::from_l1(100), lock: None });
+        let mut source = Blockchain::with_spec(&spec);
+        let tx = EnhancedTransaction::new(funder.address(), "bob".to_string(), Amount::from_l1(10), Amount::from_l1(1))
+            .sign(&funder);
+        source.add_enhanced_transaction(tx).unwrap();
+        source.mine_pending_transactions("miner".to_string());
+        let mut peer = Blockchain::with_spec(&spec);
+        peer.import_block(source.chain[1].clone()).unwrap();
+
+        // The transfer is still validly signed, but its nonce is spent, so its tip was never paid
+        let tip = peer.chain[1].clone();
+        let replayed = tip.transactions[0].clone();
+        let (subsidy, base_fee) = (peer.emission.block_subsidy(2), peer.next_base_fee());
+        let coinbase = Transaction {
+            from: "mining_reward".to_string(),
+            to: "miner".to_string(),
+            amount: subsidy.saturating_add(fee_market::split_fee(replayed.fee.unwrap_or_default(), base_fee).1),
+            ..Default::default()
+        };
+        let mut block = Block::new(2, vec![replayed, coinbase], tip.hash.clone(), "miner".to_string());
+        block.timestamp = tip.timestamp + 1;
+        block.reward = subsidy;
+        block.base_fee = base_fee;
+        let mut block = block.with_difficulty(peer.current_difficulty());
+        block.mine_block();
+
+        let err = peer.import_block(block).unwrap_err();
+        assert!(err.contains("Transaction 0 would not apply"), "{}", err);
+        assert_eq!(peer.height(), 1);
+        assert_eq!(peer.get_balance("bob"), Amount::from_l1(10));
+    }
+
+    #[test]
+    fn test_wrong_base_fee_detected() {
+        let mut blockchain = mined_chain();
+        assert_eq!(blockchain.chain[1].base_fee, fee_market::MIN_BASE_FEE);
+
+        let mut cheaper = blockchain.chain[2].clone();
+        cheaper.base_fee = Amount::ZERO;
+        let mut cheaper = cheaper.with_difficulty(blockchain.chain[2].difficulty);
+        cheaper.mine_block();
+        let err = validate_blocks(&[blockchain.chain[0].clone(), blockchain.chain[1].clone(), cheaper], &blockchain.retarget).unwrap_err();
+        assert_eq!((err.block_index, err.reason.as_str()), (2, "Block does not record its base fee"));
+
+        let mut pricier = blockchain.chain[1].clone();
+        pricier.base_fee = Amount::from_l1(1);
+        let mut pricier = pricier.with_difficulty(blockchain.chain[1].difficulty);
+        pricier.mine_block();
+        blockchain.chain[1] = pricier;
+        let err = validate_blocks(&blockchain.chain[..2], &blockchain.retarget).unwrap_err();
+        assert_eq!(err.block_index, 1);
+        assert!(err.reason.starts_with("Expected base fee"));
+    }
+
+    #[test]
+    fn test_transfers_below_base_fee_rejected() {
+        let blockchain = mined_chain();
+        let with_fee = |fee: Option<Amount>| {
+            let mut block = blockchain.chain[2].clone();
+            block.transactions[0].from = "user0".to_string();
+            block.transactions[0].fee = fee;
+            block.merkle_root = Block::compute_merkle_root(&block.transactions);
+            let mut block = block.with_difficulty(blockchain.chain[2].difficulty);
+            block.mine_block();
+            vec![blockchain.chain[0].clone(), blockchain.chain[1].clone(), block]
+        };
+        let base_fee = blockchain.chain[2].base_fee;
+
+        for fee in [None, Some(base_fee.saturating_sub(Amount::from_base_units(1)))] {
+            let err = validate_blocks(&with_fee(fee), &blockchain.retarget).unwrap_err();
+            assert_eq!(err.block_index, 2);
+            assert!(err.reason.starts_with("Transaction 0 pays less than the base fee"), "{}", err.reason);
+        }
+        assert!(validate_blocks(&with_fee(Some(base_fee)), &blockchain.retarget).is_ok());
+    }
+}
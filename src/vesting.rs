@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+
+/// When coins credited by a transfer become spendable. Every condition that is set must have
+/// released them: a height or time lock holds the whole amount, vesting releases it linearly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockSchedule {
+    /// Locked in blocks below this height
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unlock_at_height: Option<u64>,
+    /// Locked in blocks timestamped before this Unix time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unlock_at_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vesting: Option<Vesting>,
+}
+
+/// Linear release over `duration_secs` starting at `start_time`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vesting {
+    pub start_time: u64,
+    pub duration_secs: u64,
+}
+
+/// Part of an account's balance held back by a schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceLock {
+    pub amount: Amount,
+    pub schedule: LockSchedule,
+}
+
+impl LockSchedule {
+    pub fn at_height(height: u64) -> Self {
+        Self { unlock_at_height: Some(height), ..Self::default() }
+    }
+
+    pub fn at_time(time: u64) -> Self {
+        Self { unlock_at_time: Some(time), ..Self::default() }
+    }
+
+    pub fn vesting(start_time: u64, duration_secs: u64) -> Self {
+        Self { vesting: Some(Vesting { start_time, duration_secs }), ..Self::default() }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.unlock_at_height.is_none() && self.unlock_at_time.is_none() && self.vesting.is_none() {
+            return Err("A lock needs unlock_at_height, unlock_at_time or vesting".to_string());
+        }
+        if self.vesting.is_some_and(|vesting| vesting.duration_secs == 0) {
+            return Err("Vesting duration must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// How much of `amount` is still locked in a block at `height` with timestamp `time`
+    pub fn locked(&self, amount: Amount, height: u64, time: u64) -> Amount {
+        if self.unlock_at_height.is_some_and(|unlock| height < unlock)
+            || self.unlock_at_time.is_some_and(|unlock| time < unlock)
+        {
+            return amount;
+        }
+        match self.vesting {
+            Some(vesting) if time < vesting.start_time.saturating_add(vesting.duration_secs) => {
+                let elapsed = time.saturating_sub(vesting.start_time);
+                let vested = amount.mul_div(elapsed as u128, vesting.duration_secs as u128).unwrap_or(amount);
+                amount.saturating_sub(vested)
+            }
+            _ => Amount::ZERO,
+        }
+    }
+
+    /// Appended to a transfer's signing payload so the lock cannot be stripped in transit
+    pub fn signing_suffix(&self) -> String {
+        let field = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or_default();
+        format!(
+            ":lock:{}:{}:{}:{}",
+            field(self.unlock_at_height),
+            field(self.unlock_at_time),
+            field(self.vesting.map(|vesting| vesting.start_time)),
+            field(self.vesting.map(|vesting| vesting.duration_secs)),
+        )
+    }
+}
+
+impl BalanceLock {
+    pub fn locked(&self, height: u64, time: u64) -> Amount {
+        self.schedule.locked(self.amount, height, time)
+    }
+}
+
+/// Total still held back by `locks` in a block at `height` with timestamp `time`
+pub fn locked_total(locks: &[BalanceLock], height: u64, time: u64) -> Amount {
+    locks.iter().map(|lock| lock.locked(height, time)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_height_and_time_locks() {
+        let amount = Amount::from_l1(100);
+        let by_height = LockSchedule::at_height(10);
+        assert_eq!(by_height.locked(amount, 9, u64::MAX), amount);
+        assert_eq!(by_height.locked(amount, 10, 0), Amount::ZERO);
+
+        let by_time = LockSchedule::at_time(1_000);
+        assert_eq!(by_time.locked(amount, u64::MAX, 999), amount);
+        assert_eq!(by_time.locked(amount, 0, 1_000), Amount::ZERO);
+
+        let both = LockSchedule { unlock_at_height: Some(10), unlock_at_time: Some(1_000), vesting: None };
+        assert_eq!(both.locked(amount, 10, 999), amount);
+        assert_eq!(both.locked(amount, 9, 1_000), amount);
+        assert!(LockSchedule::default().validate().is_err());
+    }
+
+    #[test]
+    fn test_linear_vesting() {
+        let amount = Amount::from_l1(100);
+        let vesting = LockSchedule::vesting(1_000, 400);
+        assert_eq!(vesting.locked(amount, 0, 500), amount);
+        assert_eq!(vesting.locked(amount, 0, 1_100), Amount::from_l1(75));
+        assert_eq!(vesting.locked(amount, 0, 1_400), Amount::ZERO);
+
+        // A time lock before the vesting ends acts as a cliff
+        let cliff = LockSchedule { unlock_at_time: Some(1_200), ..vesting };
+        assert_eq!(cliff.locked(amount, 0, 1_100), amount);
+        assert_eq!(cliff.locked(amount, 0, 1_200), Amount::from_l1(50));
+
+        let locks = [
+            BalanceLock { amount, schedule: vesting },
+            BalanceLock { amount: Amount::from_l1(5), schedule: LockSchedule::at_height(3) },
+        ];
+        assert_eq!(locked_total(&locks, 2, 1_300), Amount::from_l1(30));
+        assert!(LockSchedule::vesting(0, 0).validate().is_err());
+    }
+}